@@ -0,0 +1,445 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::cli::say;
+use crate::config::Config;
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Unknown operation: {0}")]
+    UnknownOperation(String),
+
+    #[error("Invalid time: {0} (expected YYYY-MM-DD or RFC 3339)")]
+    InvalidTime(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOperation {
+    Ingest,
+    Install,
+    Switch,
+    Remove,
+    Update,
+    Rollback,
+    Clean,
+    Uninstall,
+    Repair,
+    Pin,
+    Unpin,
+    Channel,
+}
+
+impl fmt::Display for AuditOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AuditOperation::Ingest => "ingest",
+            AuditOperation::Install => "install",
+            AuditOperation::Switch => "switch",
+            AuditOperation::Remove => "remove",
+            AuditOperation::Update => "update",
+            AuditOperation::Rollback => "rollback",
+            AuditOperation::Clean => "clean",
+            AuditOperation::Uninstall => "uninstall",
+            AuditOperation::Repair => "repair",
+            AuditOperation::Pin => "pin",
+            AuditOperation::Unpin => "unpin",
+            AuditOperation::Channel => "channel",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AuditOperation {
+    type Err = AuditError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_lowercase()))
+            .map_err(|_| AuditError::UnknownOperation(s.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+    Quarantined,
+}
+
+impl fmt::Display for AuditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditOutcome::Success => write!(f, "success"),
+            AuditOutcome::Failure => write!(f, "failure"),
+            AuditOutcome::Quarantined => write!(f, "quarantined"),
+        }
+    }
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub operation: AuditOperation,
+    /// Who ran appiman: the invoking user under sudo, otherwise the current
+    /// user.
+    pub actor: String,
+    pub app: Option<String>,
+    pub version: Option<String>,
+    pub path: Option<PathBuf>,
+    /// User whose home directory an ingested image came from.
+    pub source_user: Option<String>,
+    pub before_checksum: Option<String>,
+    pub after_checksum: Option<String>,
+    pub outcome: AuditOutcome,
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(operation: AuditOperation) -> Self {
+        AuditEvent {
+            timestamp: Utc::now(),
+            operation,
+            actor: current_actor(),
+            app: None,
+            version: None,
+            path: None,
+            source_user: None,
+            before_checksum: None,
+            after_checksum: None,
+            outcome: AuditOutcome::Success,
+            detail: None,
+        }
+    }
+
+    pub fn with_app(mut self, app: &str) -> Self {
+        self.app = Some(app.to_string());
+        self
+    }
+
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    pub fn with_source_user(mut self, user: Option<&str>) -> Self {
+        self.source_user = user.map(str::to_string);
+        self
+    }
+
+    pub fn with_checksums(mut self, before: Option<String>, after: Option<String>) -> Self {
+        self.before_checksum = before;
+        self.after_checksum = after;
+        self
+    }
+
+    pub fn with_outcome(mut self, outcome: AuditOutcome, detail: Option<String>) -> Self {
+        self.outcome = outcome;
+        self.detail = detail;
+        self
+    }
+
+    /// Sets the outcome from the result of the audited operation.
+    pub fn with_result<T, E: fmt::Display>(self, result: &Result<T, E>) -> Self {
+        match result {
+            Ok(_) => self.with_outcome(AuditOutcome::Success, None),
+            Err(e) => self.with_outcome(AuditOutcome::Failure, Some(e.to_string())),
+        }
+    }
+}
+
+fn current_actor() -> String {
+    std::env::var("SUDO_USER")
+        .or_else(|_| std::env::var("USER"))
+        .ok()
+        .filter(|user| !user.is_empty())
+        .unwrap_or_else(|| format!("uid:{}", nix::unistd::Uid::current()))
+}
+
+/// Which events `appiman audit` prints. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub app: Option<String>,
+    pub operation: Option<AuditOperation>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, event: &AuditEvent) -> bool {
+        self.app
+            .as_ref()
+            .is_none_or(|app| event.app.as_ref() == Some(app))
+            && self.operation.is_none_or(|op| event.operation == op)
+            && self.since.is_none_or(|since| event.timestamp >= since)
+            && self.until.is_none_or(|until| event.timestamp < until)
+    }
+}
+
+/// Parses `--since`/`--until` values. A bare date means midnight UTC.
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>, AuditError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+        .ok_or_else(|| AuditError::InvalidTime(value.to_string()))
+}
+
+/// Append-only JSONL log of every mutating operation.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        AuditLog { path }
+    }
+
+    /// The configured log, or `None` when auditing is disabled.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .audit
+            .enabled
+            .then(|| AuditLog::new(config.audit_log_path()))
+    }
+
+    #[allow(dead_code)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, event: &AuditEvent) -> Result<(), AuditError> {
+        if let Some(dir) = self.path.parent()
+            && !dir.exists()
+        {
+            fs::create_dir_all(dir)?;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o750))?;
+        }
+
+        let mut line = serde_json::to_string(event)?;
+        line.push('\n');
+
+        // A single write to an O_APPEND file keeps concurrent lines intact.
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o640)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Appends `event`, logging a warning instead of failing the operation
+    /// being audited.
+    pub fn record(&self, event: AuditEvent) {
+        if let Err(e) = self.append(&event) {
+            warn!(
+                "Failed to write audit log {:?} for {} event: {}",
+                self.path, event.operation, e
+            );
+        }
+    }
+
+    /// Events matching `filter`, oldest first. Malformed lines are skipped.
+    pub fn read(&self, filter: &AuditFilter) -> Result<Vec<AuditEvent>, AuditError> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let reader = BufReader::new(fs::File::open(&self.path)?);
+        let mut events = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<AuditEvent>(&line) {
+                Ok(event) if filter.matches(&event) => events.push(event),
+                Ok(_) => {}
+                Err(e) => warn!("Skipping malformed audit line {}: {}", i + 1, e),
+            }
+        }
+        Ok(events)
+    }
+}
+
+pub fn run_audit(filter: &AuditFilter) -> Result<Vec<AuditEvent>, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let events = AuditLog::new(config.audit_log_path()).read(filter)?;
+
+    if events.is_empty() {
+        say!("No audit events found");
+        return Ok(events);
+    }
+
+    for event in &events {
+        let mut line = format!(
+            "{} {:<8} {:<11} {}",
+            event.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            event.operation,
+            event.outcome,
+            event.actor
+        );
+        if let Some(app) = &event.app {
+            line.push_str(&format!(" app={}", app));
+        }
+        if let Some(version) = &event.version {
+            line.push_str(&format!(" version={}", version));
+        }
+        if let Some(user) = &event.source_user {
+            line.push_str(&format!(" from={}", user));
+        }
+        if let Some(path) = &event.path {
+            line.push_str(&format!(" path={}", path.display()));
+        }
+        if let Some(detail) = &event.detail {
+            line.push_str(&format!(" ({})", detail));
+        }
+        say!("{}", line);
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn append_and_filter_events() {
+        let temp = TempDir::new().unwrap();
+        let log = AuditLog::new(temp.path().join("log/audit.jsonl"));
+
+        let install = AuditEvent::new(AuditOperation::Install)
+            .with_app("firefox")
+            .with_checksums(None, Some("abc".to_string()));
+        let failed: Result<(), String> = Err("disk full".to_string());
+        let remove = AuditEvent::new(AuditOperation::Remove)
+            .with_app("gimp")
+            .with_result(&failed);
+        log.append(&install).unwrap();
+        log.append(&remove).unwrap();
+
+        let mode = fs::metadata(log.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+
+        assert_eq!(
+            log.read(&AuditFilter::default()).unwrap(),
+            vec![install.clone(), remove.clone()]
+        );
+
+        let by_app = AuditFilter {
+            app: Some("gimp".to_string()),
+            ..AuditFilter::default()
+        };
+        let events = log.read(&by_app).unwrap();
+        assert_eq!(events, vec![remove]);
+        assert_eq!(events[0].outcome, AuditOutcome::Failure);
+        assert_eq!(events[0].detail.as_deref(), Some("disk full"));
+
+        let by_operation = AuditFilter {
+            operation: Some("INSTALL".parse().unwrap()),
+            ..AuditFilter::default()
+        };
+        assert_eq!(log.read(&by_operation).unwrap(), vec![install.clone()]);
+
+        let future = AuditFilter {
+            since: Some(install.timestamp + chrono::Duration::days(1)),
+            ..AuditFilter::default()
+        };
+        assert!(log.read(&future).unwrap().is_empty());
+    }
+
+    #[test]
+    fn version_manager_records_checksums() {
+        use crate::core::VersionManager;
+        use crate::core::testing::fake_appimage;
+
+        let temp = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.directories.bin = temp.path().join("bin").display().to_string();
+        let log = AuditLog::new(temp.path().join("audit.jsonl"));
+        let manager = VersionManager::new(config).with_audit(Some(log.clone()));
+
+        for (version, body) in [("1.0", b"one"), ("2.0", b"two")] {
+            let image = temp.path().join(format!("{}.AppImage", version));
+            fs::write(&image, fake_appimage(body)).unwrap();
+            manager.install_version("app", version, &image).unwrap();
+        }
+        manager.switch_version("app", "1.0").unwrap();
+        assert!(manager.remove_version("app", "1.0").is_err());
+
+        let events = log.read(&AuditFilter::default()).unwrap();
+        let operations: Vec<_> = events.iter().map(|e| (e.operation, e.outcome)).collect();
+        assert_eq!(
+            operations,
+            vec![
+                (AuditOperation::Install, AuditOutcome::Success),
+                (AuditOperation::Install, AuditOutcome::Success),
+                (AuditOperation::Switch, AuditOutcome::Success),
+                (AuditOperation::Remove, AuditOutcome::Failure),
+            ]
+        );
+        assert_eq!(events[0].before_checksum, None);
+        assert_eq!(events[1].before_checksum, events[0].after_checksum);
+        assert_eq!(events[2].before_checksum, events[1].after_checksum);
+        assert_eq!(events[2].after_checksum, events[0].after_checksum);
+        assert_eq!(events[3].version.as_deref(), Some("1.0"));
+    }
+
+    #[test]
+    fn read_skips_malformed_lines() {
+        let temp = TempDir::new().unwrap();
+        let log = AuditLog::new(temp.path().join("audit.jsonl"));
+        let event = AuditEvent::new(AuditOperation::Clean);
+        log.append(&event).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(log.path())
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        assert_eq!(log.read(&AuditFilter::default()).unwrap(), vec![event]);
+    }
+
+    #[test]
+    fn parse_time_accepts_dates_and_rfc3339() {
+        assert_eq!(
+            parse_time("2026-01-02").unwrap().to_rfc3339(),
+            "2026-01-02T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_time("2026-01-02T03:04:05+01:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2026-01-02T02:04:05+00:00"
+        );
+        assert!(parse_time("yesterday").is_err());
+        assert!("frobnicate".parse::<AuditOperation>().is_err());
+    }
+}
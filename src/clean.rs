@@ -1,10 +1,15 @@
 // src/clean.rs
 
+use crate::audit::{AuditEvent, AuditLog, AuditOperation};
+use crate::cli::say;
 use crate::config::Config;
+use crate::output::{Outcome, Report, serialize_path_reasons};
 use crate::privileges::require_root;
 use regex::Regex;
+use serde::Serialize;
 use std::fs;
 use std::io;
+use std::path::{Path, PathBuf};
 
 lazy_static::lazy_static! {
     static ref CLEAN_REGEX: Regex = Regex::new(
@@ -12,7 +17,65 @@ lazy_static::lazy_static! {
     ).unwrap();
 }
 
-pub fn run_cleanup() -> io::Result<()> {
+/// What [`remove_audited`] did with a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Removal {
+    Removed,
+    /// Left alone in a dry run.
+    WouldRemove,
+}
+
+impl Removal {
+    fn verb(self) -> &'static str {
+        match self {
+            Removal::Removed => "Removed",
+            Removal::WouldRemove => "[DRY RUN] Would remove",
+        }
+    }
+}
+
+fn remove_audited(audit: Option<&AuditLog>, path: &Path, dry_run: bool) -> io::Result<Removal> {
+    if dry_run {
+        return Ok(Removal::WouldRemove);
+    }
+
+    let result = fs::remove_file(path);
+    if let Some(audit) = audit {
+        audit.record(
+            AuditEvent::new(AuditOperation::Clean)
+                .with_path(path)
+                .with_result(&result),
+        );
+    }
+    result.map(|()| Removal::Removed)
+}
+
+/// Files removed by `clean`, and those that could not be.
+#[derive(Debug, Default, Serialize)]
+pub struct CleanReport {
+    pub removed: Vec<PathBuf>,
+    #[serde(serialize_with = "serialize_path_reasons")]
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl CleanReport {
+    fn remove(&mut self, audit: Option<&AuditLog>, path: &Path, dry_run: bool) -> io::Result<Removal> {
+        let result = remove_audited(audit, path, dry_run);
+        match &result {
+            Ok(_) => self.removed.push(path.to_path_buf()),
+            Err(err) => self.errors.push((path.to_path_buf(), err.to_string())),
+        }
+        result
+    }
+}
+
+impl Report for CleanReport {
+    fn outcome(&self) -> Outcome {
+        Outcome::of_batch(self.removed.len(), self.errors.len())
+    }
+}
+
+pub fn run_cleanup(dry_run: bool) -> io::Result<CleanReport> {
     require_root()?;
 
     let config = Config::load().map_err(|e| {
@@ -25,23 +88,22 @@ pub fn run_cleanup() -> io::Result<()> {
     let symlink_dir = config.symlink_dir();
     let desktop_dir = config.desktop_dir();
     let icon_dir = config.icon_dir();
+    let audit = AuditLog::from_config(&config);
 
-    println!("🧹 Cleaning up legacy AppImage files and artifacts...");
+    say!("🧹 Cleaning up legacy AppImage files and artifacts...");
 
     let re = &CLEAN_REGEX;
 
-    let mut had_errors = false;
+    let mut report = CleanReport::default();
 
     // Clean bin directory
     if let Ok(entries) = fs::read_dir(&bin_dir) {
         for entry in entries.flatten() {
             let name = entry.file_name().to_string_lossy().into_owned();
             if re.is_match(&name) {
-                if let Err(err) = fs::remove_file(entry.path()) {
-                    had_errors = true;
-                    eprintln!("⚠️ Failed to remove bin entry {}: {}", name, err);
-                } else {
-                    println!("Removed bin entry: {}", name);
+                match report.remove(audit.as_ref(), &entry.path(), dry_run) {
+                    Ok(removal) => say!("{} bin entry: {}", removal.verb(), name),
+                    Err(err) => eprintln!("⚠️ Failed to remove bin entry {}: {}", name, err),
                 }
             }
         }
@@ -53,11 +115,9 @@ pub fn run_cleanup() -> io::Result<()> {
             let path = entry.path();
             if let Ok(target) = fs::read_link(&path)
                 && (!target.exists() || re.is_match(&target.to_string_lossy())) {
-                    if let Err(err) = fs::remove_file(&path) {
-                        had_errors = true;
-                        eprintln!("⚠️ Failed to remove symlink {}: {}", path.display(), err);
-                    } else {
-                        println!("Removed symlink: {}", path.display());
+                    match report.remove(audit.as_ref(), &path, dry_run) {
+                        Ok(removal) => say!("{} symlink: {}", removal.verb(), path.display()),
+                        Err(err) => eprintln!("⚠️ Failed to remove symlink {}: {}", path.display(), err),
                     }
                 }
         }
@@ -71,15 +131,15 @@ pub fn run_cleanup() -> io::Result<()> {
                 && let Ok(content) = fs::read_to_string(&path)
                     && content.contains(bin_dir.to_string_lossy().as_ref()) && re.is_match(&content)
                     {
-                        if let Err(err) = fs::remove_file(&path) {
-                            had_errors = true;
-                            eprintln!(
+                        match report.remove(audit.as_ref(), &path, dry_run) {
+                            Ok(removal) => {
+                                say!("{} desktop entry: {}", removal.verb(), path.display())
+                            }
+                            Err(err) => eprintln!(
                                 "⚠️ Failed to remove desktop entry {}: {}",
                                 path.display(),
                                 err
-                            );
-                        } else {
-                            println!("Removed desktop entry: {}", path.display());
+                            ),
                         }
                     }
         }
@@ -90,22 +150,41 @@ pub fn run_cleanup() -> io::Result<()> {
         for entry in entries.flatten() {
             let name = entry.file_name().to_string_lossy().into_owned();
             if re.is_match(&name) {
-                if let Err(err) = fs::remove_file(entry.path()) {
-                    had_errors = true;
-                    eprintln!("⚠️ Failed to remove icon {}: {}", name, err);
-                } else {
-                    println!("Removed icon: {}", name);
+                match report.remove(audit.as_ref(), &entry.path(), dry_run) {
+                    Ok(removal) => say!("{} icon: {}", removal.verb(), name),
+                    Err(err) => eprintln!("⚠️ Failed to remove icon {}: {}", name, err),
                 }
             }
         }
     }
 
-    if had_errors {
-        return Err(io::Error::other(
-            "Cleanup completed with errors.",
-        ));
+    if report.errors.is_empty() {
+        say!("✅ Cleanup complete.");
+    } else {
+        say!("⚠️  Cleanup completed with {} errors.", report.errors.len());
     }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
 
-    println!("✅ Cleanup complete.");
-    Ok(())
+    #[test]
+    fn dry_run_leaves_file_and_says_so() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("App-x86_64.AppImage");
+        fs::write(&path, "app").unwrap();
+
+        let mut report = CleanReport::default();
+        let removal = report.remove(None, &path, true).unwrap();
+        assert_eq!(removal, Removal::WouldRemove);
+        assert_eq!(removal.verb(), "[DRY RUN] Would remove");
+        assert!(path.exists());
+
+        assert_eq!(report.remove(None, &path, false).unwrap(), Removal::Removed);
+        assert!(!path.exists());
+        assert_eq!(report.removed.len(), 2);
+    }
 }
@@ -0,0 +1,421 @@
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::audit::{AuditFilter, AuditOperation, parse_time};
+use crate::registrar::InstallOptions;
+use crate::uninstall::UninstallOptions;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// `println!` for progress and confirmation messages, silenced by `--quiet`.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !$crate::cli::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+pub(crate) use say;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "appiman",
+    version,
+    about = "System-wide AppImage management",
+    after_help = "For more information, visit: https://github.com/derungo/appiman"
+)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct GlobalArgs {
+    /// Config file to use instead of /etc/appiman/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Show what would be done without making changes
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Print machine-readable JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Only print errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Log more (-v for debug, -vv for trace)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+impl GlobalArgs {
+    /// Log filter implied by `--quiet`/`--verbose`, if any.
+    pub fn log_level(&self) -> Option<&'static str> {
+        if self.quiet {
+            return Some("error");
+        }
+        match self.verbose {
+            0 => None,
+            1 => Some("debug"),
+            _ => Some("trace"),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Create dir structure and install units, completions and man pages
+    Init,
+    /// Enable and start systemd .path units
+    Enable,
+    /// Disable and stop systemd .path units
+    Disable,
+    /// Show systemd status of watchers and AppImage inventory
+    Status,
+    /// Move user-downloaded AppImages into staging
+    Ingest,
+    /// Run AppImage re-index manually
+    Scan,
+    /// Ingest then register AppImages
+    Sync,
+    /// Install an AppImage from a file or URL right away
+    Install(InstallArgs),
+    /// Remove an app with its versions, desktop entry, icon and symlink
+    Uninstall(UninstallArgs),
+    /// Show versions, files, security report and origin of an app
+    Info { app: String },
+    /// Check for and apply AppImage updates
+    Update {
+        #[command(subcommand)]
+        action: Option<UpdateCommand>,
+    },
+    /// Manage AppImage versions
+    Versions {
+        #[command(subcommand)]
+        action: VersionsCommand,
+    },
+    /// Manage per-app signing key pins
+    Trust {
+        #[command(subcommand)]
+        action: TrustCommand,
+    },
+    /// List, inspect, release or purge rejected AppImages
+    Quarantine {
+        #[command(subcommand)]
+        action: QuarantineCommand,
+    },
+    /// Check that versions, links, desktop entries, icons and cache agree
+    Doctor {
+        /// Repair the problems found
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Remove versions and update backups the retention policy does not keep
+    Gc,
+    /// Show the audit log of changes made by appiman
+    Audit(AuditArgs),
+    /// Remove legacy AppImages and artifacts
+    Clean,
+    /// Print a shell completion script
+    Completions {
+        shell: Shell,
+    },
+}
+
+impl Command {
+    /// Whether the command changes registration state, and so must take
+    /// turns with other appiman runs.
+    pub fn changes_state(&self) -> bool {
+        match self {
+            Command::Init
+            | Command::Enable
+            | Command::Disable
+            | Command::Status
+            | Command::Info { .. }
+            | Command::Audit(_)
+            | Command::Completions { .. } => false,
+            Command::Update { action } => {
+                !matches!(action, None | Some(UpdateCommand::Check))
+            }
+            Command::Versions { action } => !matches!(action, VersionsCommand::List { .. }),
+            Command::Trust { action } => !matches!(action, TrustCommand::List),
+            Command::Quarantine { action } => !matches!(
+                action,
+                QuarantineCommand::List | QuarantineCommand::Show { .. }
+            ),
+            Command::Doctor { fix } => *fix,
+            Command::Ingest
+            | Command::Scan
+            | Command::Sync
+            | Command::Install(_)
+            | Command::Uninstall(_)
+            | Command::Gc
+            | Command::Clean => true,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct InstallArgs {
+    /// Local path or http(s):// URL of the AppImage
+    #[arg(value_name = "PATH_OR_URL")]
+    pub source: String,
+
+    /// App name (default: guessed from the file name)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Version (default: guessed from the file name)
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// Do not create a desktop entry or extract the icon
+    #[arg(long)]
+    pub no_desktop: bool,
+}
+
+impl InstallArgs {
+    pub fn options(&self) -> InstallOptions {
+        InstallOptions {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            desktop_entry: !self.no_desktop,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct UninstallArgs {
+    pub app: String,
+
+    /// Keep the signing key pin and update backups
+    #[arg(long)]
+    pub keep_data: bool,
+
+    /// Remove the app even if several versions are installed
+    #[arg(long)]
+    pub all_versions: bool,
+}
+
+impl UninstallArgs {
+    pub fn options(&self) -> UninstallOptions {
+        UninstallOptions {
+            keep_data: self.keep_data,
+            all_versions: self.all_versions,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UpdateCommand {
+    /// Check for available updates (default)
+    Check,
+    /// Download and install available updates
+    Apply,
+    /// Switch an app back to the version installed before the current one
+    Rollback { app: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VersionsCommand {
+    /// List versions for an app, or the active version of every app
+    List { app: Option<String> },
+    /// Switch an app to the given version
+    Switch { app: String, version: String },
+    /// Remove a version that is not active
+    Remove { app: String, version: String },
+    /// Hold an app at a version (default: the active one) so updates skip it
+    Pin {
+        app: String,
+        version: Option<String>,
+    },
+    /// Let updates move a pinned app forward again
+    Unpin { app: String },
+    /// Follow an update channel such as stable, beta or nightly; `default`
+    /// follows the release named by the image's update information
+    Channel { app: String, channel: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrustCommand {
+    /// List pinned signing keys
+    List,
+    /// Pin a key (default: signer of the active version)
+    Pin {
+        app: String,
+        fingerprint: Option<String>,
+    },
+    /// Remove the pin so the next signer is trusted
+    Revoke { app: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum QuarantineCommand {
+    /// List quarantined AppImages
+    List,
+    /// Show why an AppImage was quarantined
+    Show { id: String },
+    /// Move an AppImage back and register it
+    Release { id: String },
+    /// Delete quarantined AppImages
+    Purge {
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
+
+        /// Delete every quarantined AppImage
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct AuditArgs {
+    /// Only events for this app
+    #[arg(long)]
+    pub app: Option<String>,
+
+    /// Only events of this operation (ingest, install, switch, remove,
+    /// update, rollback, clean, uninstall, repair, pin, unpin or channel)
+    #[arg(long = "op", value_name = "OPERATION")]
+    pub operation: Option<AuditOperation>,
+
+    /// Events at or after this time (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_time, value_name = "TIME")]
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Events before this time (YYYY-MM-DD or RFC 3339)
+    #[arg(long, value_parser = parse_time, value_name = "TIME")]
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl AuditArgs {
+    pub fn filter(&self) -> AuditFilter {
+        AuditFilter {
+            app: self.app.clone(),
+            operation: self.operation,
+            since: self.since,
+            until: self.until,
+        }
+    }
+}
+
+/// Writes bash, zsh and fish completions and man pages below `share_dir`
+/// (normally `/usr/share`).
+pub fn install_shell_integration(share_dir: &Path) -> io::Result<()> {
+    let completions = [
+        (Shell::Bash, "bash-completion/completions/appiman"),
+        (Shell::Zsh, "zsh/site-functions/_appiman"),
+        (Shell::Fish, "fish/vendor_completions.d/appiman.fish"),
+    ];
+    for (shell, relative) in completions {
+        let path = share_dir.join(relative);
+        say!("Installing {} completions: {}", shell, path.display());
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::File::create(&path)?;
+        clap_complete::generate(shell, &mut Cli::command(), "appiman", &mut file);
+    }
+
+    let man_dir = share_dir.join("man/man1");
+    say!("Installing man pages: {}", man_dir.display());
+    fs::create_dir_all(&man_dir)?;
+    clap_mangen::generate_to(Cli::command(), &man_dir)?;
+    Ok(())
+}
+
+pub fn print_completions(shell: Shell) {
+    clap_complete::generate(shell, &mut Cli::command(), "appiman", &mut io::stdout());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn global_flags_work_after_subcommand() {
+        let cli = Cli::try_parse_from([
+            "appiman", "versions", "switch", "firefox", "2.0", "--dry-run", "--json", "-vv",
+        ])
+        .unwrap();
+        assert!(cli.global.dry_run);
+        assert!(cli.global.json);
+        assert_eq!(cli.global.log_level(), Some("trace"));
+        assert!(matches!(
+            cli.command,
+            Command::Versions {
+                action: VersionsCommand::Switch { .. }
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["appiman", "-q", "-v", "status"]).is_err());
+    }
+
+    #[test]
+    fn audit_and_purge_arguments_are_typed() {
+        let cli = Cli::try_parse_from(["appiman", "audit", "--op", "update", "--since", "2026-01-01"])
+            .unwrap();
+        let Command::Audit(args) = cli.command else {
+            panic!("expected audit");
+        };
+        assert_eq!(args.filter().operation, Some(AuditOperation::Update));
+        assert!(args.filter().since.is_some());
+
+        assert!(Cli::try_parse_from(["appiman", "audit", "--op", "frobnicate"]).is_err());
+        assert!(Cli::try_parse_from(["appiman", "quarantine", "purge"]).is_err());
+        assert!(Cli::try_parse_from(["appiman", "quarantine", "purge", "--all"]).is_ok());
+    }
+
+    #[test]
+    fn install_options_come_from_flags() {
+        let cli = Cli::try_parse_from([
+            "appiman", "install", "./Tool.AppImage", "--name", "tool", "--version", "1.0", "--no-desktop",
+        ])
+        .unwrap();
+        let Command::Install(args) = cli.command else {
+            panic!("expected install");
+        };
+        let options = args.options();
+        assert_eq!(args.source, "./Tool.AppImage");
+        assert_eq!(options.name.as_deref(), Some("tool"));
+        assert_eq!(options.version.as_deref(), Some("1.0"));
+        assert!(!options.desktop_entry);
+    }
+
+    #[test]
+    fn shell_integration_is_installed() {
+        let temp = TempDir::new().unwrap();
+        install_shell_integration(temp.path()).unwrap();
+
+        assert!(temp.path().join("bash-completion/completions/appiman").is_file());
+        assert!(temp.path().join("zsh/site-functions/_appiman").is_file());
+        assert!(temp.path().join("fish/vendor_completions.d/appiman.fish").is_file());
+        assert!(temp.path().join("man/man1/appiman.1").is_file());
+        assert!(temp.path().join("man/man1/appiman-update.1").is_file());
+    }
+}
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::logging::LogTarget;
+use crate::policy::Policy;
+
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum ConfigError {
     #[error("Failed to read config file: {0}")]
     IoError(#[from] std::io::Error),
@@ -27,6 +32,9 @@ pub struct Directories {
     pub symlink: String,
 
     pub home_root: String,
+
+    #[serde(default = "default_quarantine_dir")]
+    pub quarantine: String,
 }
 
 impl Default for Directories {
@@ -38,6 +46,7 @@ impl Default for Directories {
             desktop: default_desktop_dir(),
             symlink: default_symlink_dir(),
             home_root: default_home_root(),
+            quarantine: default_quarantine_dir(),
         }
     }
 }
@@ -48,6 +57,12 @@ pub struct Logging {
 
     #[serde(default)]
     pub json_output: bool,
+
+    #[serde(default)]
+    pub target: LogTarget,
+
+    #[serde(default = "default_journald_socket")]
+    pub journald_socket: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +78,9 @@ pub struct Security {
 
     #[serde(default = "default_detect_sandboxing")]
     pub detect_sandboxing: bool,
+
+    #[serde(default = "default_trusted_keys_dir")]
+    pub trusted_keys_dir: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +93,9 @@ pub struct Updates {
 
     #[serde(default = "default_max_backups")]
     pub max_backups: usize,
+
+    #[serde(default = "default_github_api_url")]
+    pub github_api_url: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -84,6 +105,18 @@ pub struct Versions {
 
     #[serde(default = "default_auto_cleanup_enabled")]
     pub auto_cleanup_enabled: bool,
+
+    /// Also keep versions installed less than this many days ago.
+    #[serde(default)]
+    pub keep_days: Option<u64>,
+
+    /// Disk usage cap per app, in MiB.
+    #[serde(default)]
+    pub max_app_size_mb: Option<u64>,
+
+    /// Disk usage cap for all apps together, in MiB.
+    #[serde(default)]
+    pub max_total_size_mb: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -111,6 +144,7 @@ impl Default for Security {
             require_signatures: default_require_signatures(),
             warn_unsigned: default_warn_unsigned(),
             detect_sandboxing: default_detect_sandboxing(),
+            trusted_keys_dir: default_trusted_keys_dir(),
         }
     }
 }
@@ -121,6 +155,7 @@ impl Default for Updates {
             auto_update_enabled: default_auto_update_enabled(),
             backup_enabled: default_backup_enabled(),
             max_backups: default_max_backups(),
+            github_api_url: default_github_api_url(),
         }
     }
 }
@@ -130,10 +165,97 @@ impl Default for Logging {
         Logging {
             level: default_log_level(),
             json_output: false,
+            target: LogTarget::default(),
+            journald_socket: default_journald_socket(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Audit {
+    #[serde(default = "default_audit_enabled")]
+    pub enabled: bool,
+
+    #[serde(default = "default_audit_path")]
+    pub path: String,
+}
+
+impl Default for Audit {
+    fn default() -> Self {
+        Audit {
+            enabled: default_audit_enabled(),
+            path: default_audit_path(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ingest {
+    /// Seconds an image must go unmodified before it is ingested; partial
+    /// downloads and images open for writing are always left alone.
+    #[serde(default = "default_settle_secs")]
+    pub settle_secs: u64,
+
+    /// Folders below each home directory that are swept for AppImages.
+    #[serde(default = "default_watched_folders")]
+    pub watched_folders: Vec<String>,
+
+    /// Patterns for files and folders to leave alone, matched against the
+    /// path below the home directory and against the name; `*` matches any
+    /// run of characters.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    /// Users whose homes are never swept.
+    #[serde(default)]
+    pub opt_out_users: Vec<String>,
+
+    /// Whether users may change their folders and excludes, or opt out, in
+    /// `~/.config/appiman/ingest.toml`.
+    #[serde(default = "default_user_config")]
+    pub user_config: bool,
+}
+
+impl Default for Ingest {
+    fn default() -> Self {
+        Ingest {
+            settle_secs: default_settle_secs(),
+            watched_folders: default_watched_folders(),
+            exclude: Vec::new(),
+            opt_out_users: Vec::new(),
+            user_config: default_user_config(),
+        }
+    }
+}
+
+impl Ingest {
+    pub fn settle_window(&self) -> Duration {
+        Duration::from_secs(self.settle_secs)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Locking {
+    /// Seconds to wait for another appiman run to release a lock before
+    /// giving up.
+    #[serde(default = "default_lock_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for Locking {
+    fn default() -> Self {
+        Locking {
+            timeout_secs: default_lock_timeout_secs(),
         }
     }
 }
 
+impl Locking {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default)]
@@ -153,6 +275,18 @@ pub struct Config {
 
     #[serde(default)]
     pub performance: Performance,
+
+    #[serde(default)]
+    pub policy: Policy,
+
+    #[serde(default)]
+    pub audit: Audit,
+
+    #[serde(default)]
+    pub locking: Locking,
+
+    #[serde(default)]
+    pub ingest: Ingest,
 }
 
 impl Config {
@@ -201,6 +335,18 @@ impl Config {
         PathBuf::from(&self.directories.home_root)
     }
 
+    pub fn quarantine_dir(&self) -> PathBuf {
+        PathBuf::from(&self.directories.quarantine)
+    }
+
+    pub fn journald_socket(&self) -> PathBuf {
+        PathBuf::from(&self.logging.journald_socket)
+    }
+
+    pub fn audit_log_path(&self) -> PathBuf {
+        PathBuf::from(&self.audit.path)
+    }
+
     #[allow(dead_code)]
     pub fn log_level(&self) -> &str {
         &self.logging.level
@@ -230,6 +376,12 @@ impl Config {
         if let Ok(val) = std::env::var("APPIMAN_HOME_ROOT") {
             self.directories.home_root = val;
         }
+        if let Ok(val) = std::env::var("APPIMAN_QUARANTINE_DIR") {
+            self.directories.quarantine = val;
+        }
+        if let Ok(val) = std::env::var("APPIMAN_AUDIT_LOG") {
+            self.audit.path = val;
+        }
         if let Ok(val) = std::env::var("RUST_LOG") {
             self.logging.level = val;
         }
@@ -260,10 +412,18 @@ fn default_home_root() -> String {
     "/home".to_string()
 }
 
+fn default_quarantine_dir() -> String {
+    "/opt/applications/quarantine".to_string()
+}
+
 fn default_log_level() -> String {
     "info".to_string()
 }
 
+fn default_journald_socket() -> String {
+    "/run/systemd/journal/socket".to_string()
+}
+
 fn default_auto_update_enabled() -> bool {
     false
 }
@@ -276,6 +436,10 @@ fn default_max_backups() -> usize {
     3
 }
 
+fn default_github_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
 fn default_max_versions_per_app() -> usize {
     5
 }
@@ -300,6 +464,34 @@ fn default_detect_sandboxing() -> bool {
     true
 }
 
+fn default_trusted_keys_dir() -> String {
+    "/etc/appiman/keys".to_string()
+}
+
+fn default_audit_enabled() -> bool {
+    true
+}
+
+fn default_audit_path() -> String {
+    "/var/log/appiman/audit.jsonl".to_string()
+}
+
+fn default_settle_secs() -> u64 {
+    30
+}
+
+fn default_watched_folders() -> Vec<String> {
+    vec!["Downloads".to_string(), "Applications".to_string()]
+}
+
+fn default_user_config() -> bool {
+    true
+}
+
+fn default_lock_timeout_secs() -> u64 {
+    60
+}
+
 fn default_parallel_processing_enabled() -> bool {
     true
 }
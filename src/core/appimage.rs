@@ -1,13 +1,32 @@
 use hex;
 use sha2::{Digest, Sha256};
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
+use super::elf::{ElfError, ElfFile};
+use super::files::sha256_file;
 use super::normalize_appimage_name;
+use super::payload::{AppImagePayload, PayloadError};
+use super::update_info::{EmbeddedSignature, UpdateInfoError, UpdateInformation};
 use super::Metadata;
 
+/// Offset of the primary volume descriptor's standard identifier in an ISO 9660 image.
+const ISO9660_MAGIC_OFFSET: u64 = 0x8001;
+const ISO9660_MAGIC: &[u8] = b"CD001";
+const SQUASHFS_MAGIC: &[u8] = b"hsqs";
+
+const UPDATE_INFO_SECTION: &str = ".upd_info";
+const SIGNATURE_SECTION: &str = ".sha256_sig";
+const SIGNATURE_KEY_SECTION: &str = ".sig_key";
+/// Type 1 images keep update information in the "application use" field of
+/// the ISO 9660 primary volume descriptor.
+const TYPE1_UPDATE_INFO_OFFSET: u64 = 33651;
+const TYPE1_UPDATE_INFO_LEN: usize = 512;
+
 #[derive(Debug, Error)]
 pub enum AppImageError {
     #[error("AppImage file not found: {0}")]
@@ -16,15 +35,30 @@ pub enum AppImageError {
     #[error("Invalid AppImage format: {0}")]
     InvalidFormat(String),
 
+    #[error("Not an AppImage: {path:?} ({reason})")]
+    NotAnAppImage { path: PathBuf, reason: String },
+
+    #[error("{0} AppImages are not supported for this operation")]
+    UnsupportedType(AppImageType),
+
     #[error("Failed to extract AppImage: {0}")]
     ExtractFailed(#[from] ExtractError),
 
+    #[error("Failed to read AppImage payload: {0}")]
+    Payload(#[from] PayloadError),
+
+    #[error("Failed to read AppImage runtime: {0}")]
+    Elf(#[from] ElfError),
+
+    #[error("Invalid update information: {0}")]
+    UpdateInfo(#[from] UpdateInfoError),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
 #[derive(Debug, Error)]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::enum_variant_names)]
 pub enum ExtractError {
     #[error("Extraction failed: {0}")]
     ExtractionFailed(String),
@@ -36,9 +70,77 @@ pub enum ExtractError {
     ExecutionFailed { status: std::process::ExitStatus },
 }
 
+/// AppImage format generation, identified by the magic bytes the runtime
+/// stores at `e_ident[8..11]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppImageType {
+    /// Legacy images: an ISO 9660 filesystem with the runtime embedded in it.
+    Type1,
+    /// A runtime followed by a squashfs filesystem.
+    Type2,
+}
+
+impl AppImageType {
+    pub fn detect(path: &Path) -> Result<Self, AppImageError> {
+        let not_an_appimage = |reason: String| AppImageError::NotAnAppImage {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let file = File::open(path)?;
+        let elf = match ElfFile::parse(&file) {
+            Ok(elf) => elf,
+            Err(ElfError::Io(e)) => return Err(e.into()),
+            Err(e) => return Err(not_an_appimage(e.to_string())),
+        };
+
+        match &elf.ident[8..11] {
+            b"AI\x01" => return Ok(AppImageType::Type1),
+            b"AI\x02" => return Ok(AppImageType::Type2),
+            _ => {}
+        }
+
+        // Images built before the magic was introduced can still be told
+        // apart by the filesystem that carries the payload.
+        if has_bytes_at(&file, ISO9660_MAGIC_OFFSET, ISO9660_MAGIC)? {
+            return Ok(AppImageType::Type1);
+        }
+        if has_bytes_at(&file, elf.end_offset(), SQUASHFS_MAGIC)? {
+            return Ok(AppImageType::Type2);
+        }
+
+        Err(not_an_appimage(
+            "ELF executable without AppImage magic".to_string(),
+        ))
+    }
+}
+
+impl fmt::Display for AppImageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppImageType::Type1 => write!(f, "Type 1"),
+            AppImageType::Type2 => write!(f, "Type 2"),
+        }
+    }
+}
+
+fn has_bytes_at(file: &File, offset: u64, expected: &[u8]) -> std::io::Result<bool> {
+    let mut buf = vec![0u8; expected.len()];
+    let mut read = 0;
+    while read < buf.len() {
+        let n = file.read_at(&mut buf[read..], offset + read as u64)?;
+        if n == 0 {
+            return Ok(false);
+        }
+        read += n;
+    }
+    Ok(buf == expected)
+}
+
 #[derive(Debug, PartialEq)]
 pub struct AppImage {
     pub path: PathBuf,
+    pub image_type: AppImageType,
     pub metadata: Option<Metadata>,
 }
 
@@ -58,8 +160,11 @@ impl AppImage {
             )));
         }
 
+        let image_type = AppImageType::detect(&path)?;
+
         Ok(AppImage {
             path,
+            image_type,
             metadata: None,
         })
     }
@@ -76,6 +181,15 @@ impl AppImage {
             ));
         }
 
+        // The file may have been replaced since it was first inspected.
+        let image_type = AppImageType::detect(&self.path)?;
+        if image_type != self.image_type {
+            return Err(AppImageError::InvalidFormat(format!(
+                "Expected a {} AppImage, found {}",
+                self.image_type, image_type
+            )));
+        }
+
         Ok(())
     }
 
@@ -99,26 +213,96 @@ impl AppImage {
         Ok(true)
     }
 
-    pub fn get_checksum(&self) -> Result<String, AppImageError> {
+    /// Opens the embedded filesystem for reading without running the image.
+    pub fn payload(&self) -> Result<AppImagePayload, AppImageError> {
+        if self.image_type != AppImageType::Type2 {
+            return Err(AppImageError::UnsupportedType(self.image_type));
+        }
+        Ok(AppImagePayload::open(&self.path)?)
+    }
+
+    /// Update information embedded by the packager, read without running the
+    /// image.
+    pub fn update_information(&self) -> Result<Option<UpdateInformation>, AppImageError> {
+        let file = File::open(&self.path)?;
+        let data = match self.image_type {
+            AppImageType::Type1 => {
+                let mut data = vec![0u8; TYPE1_UPDATE_INFO_LEN];
+                match file.read_exact_at(&mut data, TYPE1_UPDATE_INFO_OFFSET) {
+                    Ok(()) => data,
+                    Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            AppImageType::Type2 => match ElfFile::parse(&file)?.read_section(&file, UPDATE_INFO_SECTION)? {
+                Some(data) => data,
+                None => return Ok(None),
+            },
+        };
+        Ok(UpdateInformation::from_section(&data)?)
+    }
+
+    /// The signature embedded by `appimagetool --sign`, if any. Only Type 2
+    /// images can carry one.
+    pub fn embedded_signature(&self) -> Result<Option<EmbeddedSignature>, AppImageError> {
+        if self.image_type != AppImageType::Type2 {
+            return Ok(None);
+        }
+        let file = File::open(&self.path)?;
+        let elf = ElfFile::parse(&file)?;
+        let Some(signature) = elf.read_section(&file, SIGNATURE_SECTION)? else {
+            return Ok(None);
+        };
+        let key = elf.read_section(&file, SIGNATURE_KEY_SECTION)?;
+        Ok(EmbeddedSignature::from_sections(&signature, key.as_deref()))
+    }
+
+    /// The hex SHA-256 digest that `appimagetool --sign` signs: the whole
+    /// file with the signature and key sections read as zeros.
+    pub fn signed_digest(&self) -> Result<String, AppImageError> {
+        if self.image_type != AppImageType::Type2 {
+            return Err(AppImageError::UnsupportedType(self.image_type));
+        }
+
         let mut file = File::open(&self.path)?;
+        let elf = ElfFile::parse(&file)?;
+        let skipped: Vec<_> = [SIGNATURE_SECTION, SIGNATURE_KEY_SECTION]
+            .iter()
+            .filter_map(|name| elf.section(name))
+            .map(|s| s.offset..s.offset.saturating_add(s.size))
+            .collect();
+
         let mut hasher = Sha256::new();
         let mut buffer = [0u8; 8192];
-
+        let mut offset = 0u64;
         loop {
             let n = file.read(&mut buffer)?;
             if n == 0 {
                 break;
             }
+            for range in &skipped {
+                let start = range.start.max(offset);
+                let end = range.end.min(offset + n as u64);
+                if start < end {
+                    buffer[(start - offset) as usize..(end - offset) as usize].fill(0);
+                }
+            }
             hasher.update(&buffer[..n]);
+            offset += n as u64;
         }
 
         Ok(hex::encode(hasher.finalize()))
     }
+
+    pub fn get_checksum(&self) -> Result<String, AppImageError> {
+        Ok(sha256_file(&self.path)?)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::testing::{appimage, fake_appimage, ElfBuilder, SquashfsBuilder};
     use std::fs;
     use tempfile::TempDir;
 
@@ -133,7 +317,7 @@ mod tests {
     fn appimage_new_rejects_invalid_extension() {
         let temp_dir = TempDir::new().unwrap();
         let path = temp_dir.path().join("testfile.txt");
-        fs::write(&path, b"test content").unwrap();
+        fs::write(&path, fake_appimage(b"")).unwrap();
 
         let result = AppImage::new(path);
         assert!(matches!(result, Err(AppImageError::InvalidFormat(_))));
@@ -143,17 +327,177 @@ mod tests {
     fn appimage_new_accepts_valid_extension() {
         let temp_dir = TempDir::new().unwrap();
         let path = temp_dir.path().join("TestApp.AppImage");
-        fs::write(&path, b"test content").unwrap();
+        fs::write(&path, fake_appimage(b"")).unwrap();
 
         let result = AppImage::new(path);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn appimage_new_rejects_renamed_non_appimages() {
+        let temp_dir = TempDir::new().unwrap();
+
+        for (name, content) in [
+            ("script.AppImage", b"#!/bin/sh\necho hi\n".to_vec()),
+            ("archive.AppImage", b"\x1f\x8b\x08\x00tarball".to_vec()),
+            ("plain-elf.AppImage", ElfBuilder::new().magic(None).build()),
+        ] {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, content).unwrap();
+
+            let result = AppImage::new(path);
+            assert!(
+                matches!(result, Err(AppImageError::NotAnAppImage { .. })),
+                "{} was accepted",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn detect_reads_type_from_magic() {
+        let temp_dir = TempDir::new().unwrap();
+        let type1 = temp_dir.path().join("Old.AppImage");
+        let type2 = temp_dir.path().join("New.AppImage");
+        fs::write(&type1, ElfBuilder::new().magic(Some(*b"AI\x01")).build()).unwrap();
+        fs::write(&type2, fake_appimage(b"")).unwrap();
+
+        assert_eq!(AppImage::new(type1).unwrap().image_type, AppImageType::Type1);
+        assert_eq!(AppImage::new(type2).unwrap().image_type, AppImageType::Type2);
+    }
+
+    #[test]
+    fn detect_recognises_images_without_magic_by_filesystem() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let squashfs = temp_dir.path().join("Squash.AppImage");
+        fs::write(
+            &squashfs,
+            appimage(
+                &ElfBuilder::new().magic(None),
+                &SquashfsBuilder::new().file("AppRun", b"#!/bin/sh\n"),
+            ),
+        )
+        .unwrap();
+        assert_eq!(AppImageType::detect(&squashfs).unwrap(), AppImageType::Type2);
+
+        let iso = temp_dir.path().join("Iso.AppImage");
+        let mut image = ElfBuilder::new().magic(None).build();
+        image.resize(ISO9660_MAGIC_OFFSET as usize, 0);
+        image.extend_from_slice(ISO9660_MAGIC);
+        fs::write(&iso, image).unwrap();
+        assert_eq!(AppImageType::detect(&iso).unwrap(), AppImageType::Type1);
+    }
+
+    #[test]
+    fn payload_is_unsupported_for_type1() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("Old.AppImage");
+        fs::write(&path, ElfBuilder::new().magic(Some(*b"AI\x01")).build()).unwrap();
+
+        let app = AppImage::new(path).unwrap();
+        assert!(matches!(
+            app.payload(),
+            Err(AppImageError::UnsupportedType(AppImageType::Type1))
+        ));
+    }
+
+    #[test]
+    fn update_information_reads_runtime_sections() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("App.AppImage");
+        let mut upd_info = b"gh-releases-zsync|owner|app|latest|App-*.AppImage.zsync".to_vec();
+        upd_info.resize(1024, 0);
+        let runtime = ElfBuilder::new()
+            .section(".upd_info", &upd_info)
+            .section(".sha256_sig", b"-----BEGIN PGP SIGNATURE-----\0\0")
+            .section(".sig_key", b"-----BEGIN PGP PUBLIC KEY BLOCK-----\0");
+        fs::write(&path, runtime.build()).unwrap();
+
+        let app = AppImage::new(path).unwrap();
+        assert_eq!(
+            app.update_information().unwrap(),
+            Some(UpdateInformation::GitHubReleases {
+                owner: "owner".to_string(),
+                repo: "app".to_string(),
+                tag: "latest".to_string(),
+                filename: "App-*.AppImage.zsync".to_string(),
+            })
+        );
+        let signature = app.embedded_signature().unwrap().unwrap();
+        assert_eq!(signature.signature, "-----BEGIN PGP SIGNATURE-----");
+        assert_eq!(
+            signature.key.as_deref(),
+            Some("-----BEGIN PGP PUBLIC KEY BLOCK-----")
+        );
+    }
+
+    #[test]
+    fn signed_digest_ignores_signature_sections() {
+        let temp_dir = TempDir::new().unwrap();
+        let signed = temp_dir.path().join("Signed.AppImage");
+        let unsigned = temp_dir.path().join("Unsigned.AppImage");
+        let runtime = |sig: &[u8], key: &[u8]| {
+            ElfBuilder::new()
+                .section(".sha256_sig", sig)
+                .section(".sig_key", key)
+                .build()
+        };
+        fs::write(&signed, runtime(&[b'S'; 64], &[b'K'; 64])).unwrap();
+        fs::write(&unsigned, runtime(&[0; 64], &[0; 64])).unwrap();
+
+        let unsigned_bytes = fs::read(&unsigned).unwrap();
+        let expected = hex::encode(sha2::Sha256::digest(&unsigned_bytes));
+        assert_eq!(AppImage::new(signed).unwrap().signed_digest().unwrap(), expected);
+        assert_eq!(AppImage::new(unsigned).unwrap().signed_digest().unwrap(), expected);
+    }
+
+    #[test]
+    fn update_information_is_none_when_sections_are_missing_or_empty() {
+        let temp_dir = TempDir::new().unwrap();
+        let bare = temp_dir.path().join("Bare.AppImage");
+        let empty = temp_dir.path().join("Empty.AppImage");
+        fs::write(&bare, fake_appimage(b"")).unwrap();
+        fs::write(
+            &empty,
+            ElfBuilder::new()
+                .section(".upd_info", &[0u8; 1024])
+                .section(".sha256_sig", &[0u8; 1024])
+                .build(),
+        )
+        .unwrap();
+
+        for path in [bare, empty] {
+            let app = AppImage::new(path).unwrap();
+            assert_eq!(app.update_information().unwrap(), None);
+            assert_eq!(app.embedded_signature().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn update_information_reads_type1_volume_descriptor() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("Old.AppImage");
+        let mut image = ElfBuilder::new().magic(Some(*b"AI\x01")).build();
+        image.resize(TYPE1_UPDATE_INFO_OFFSET as usize, 0);
+        image.extend_from_slice(b"zsync|https://example.com/old.zsync");
+        image.resize(TYPE1_UPDATE_INFO_OFFSET as usize + TYPE1_UPDATE_INFO_LEN, 0);
+        fs::write(&path, image).unwrap();
+
+        let app = AppImage::new(path).unwrap();
+        assert_eq!(
+            app.update_information().unwrap(),
+            Some(UpdateInformation::Zsync {
+                url: "https://example.com/old.zsync".to_string()
+            })
+        );
+    }
+
     #[test]
     fn normalize_name_handles_appimage_files() {
         let temp_dir = TempDir::new().unwrap();
         let path = temp_dir.path().join("TestApp-v1.2.3-x86_64.AppImage");
-        fs::write(&path, b"test content").unwrap();
+        fs::write(&path, fake_appimage(b"")).unwrap();
 
         let app = AppImage::new(path).unwrap();
         assert_eq!(app.normalize_name(), "testapp");
@@ -163,19 +507,34 @@ mod tests {
     fn validate_works_on_valid_appimage() {
         let temp_dir = TempDir::new().unwrap();
         let path = temp_dir.path().join("TestApp.AppImage");
-        fs::write(&path, b"test content").unwrap();
+        fs::write(&path, fake_appimage(b"")).unwrap();
 
         let app = AppImage::new(path).unwrap();
         assert!(app.validate().is_ok());
     }
 
+    #[test]
+    fn validate_rejects_replaced_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("TestApp.AppImage");
+        fs::write(&path, fake_appimage(b"")).unwrap();
+
+        let app = AppImage::new(path.clone()).unwrap();
+        fs::write(&path, b"#!/bin/sh\n").unwrap();
+
+        assert!(matches!(
+            app.validate(),
+            Err(AppImageError::NotAnAppImage { .. })
+        ));
+    }
+
     #[test]
     fn get_checksum_returns_sha256_hash() {
         let temp_dir = TempDir::new().unwrap();
         let test_file = temp_dir.path().join("test.AppImage");
-        let content = b"test content for checksum";
+        let content = fake_appimage(b"test content for checksum");
 
-        fs::write(&test_file, content).unwrap();
+        fs::write(&test_file, &content).unwrap();
 
         let app = AppImage::new(test_file).unwrap();
         let checksum = app.get_checksum().unwrap();
@@ -185,7 +544,7 @@ mod tests {
             .chars()
             .all(|c| c.is_ascii_hexdigit() || c.is_ascii_lowercase()));
 
-        let expected_hash = sha2::Sha256::digest(content);
+        let expected_hash = sha2::Sha256::digest(&content);
         let expected_hex = hex::encode(expected_hash);
         assert_eq!(checksum, expected_hex);
     }
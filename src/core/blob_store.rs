@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::config::Config;
+use crate::core::files::sha256_file;
+use crate::core::journal::sync_dir;
+
+/// Content-addressed store for AppImage files, keyed by SHA-256.
+///
+/// Version images and update backups are hard links to a blob, so identical
+/// files are stored once. The link count is the reference count: a blob
+/// whose only link is the store's own is no longer used and can be removed.
+/// Where a hard link cannot be made, the file is copied instead.
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    pub fn new(root: PathBuf) -> Self {
+        BlobStore { root }
+    }
+
+    /// The store below the bin directory, so links never cross filesystems.
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.bin_dir().join(".blobs"))
+    }
+
+    pub fn blob_path(&self, checksum: &str) -> io::Result<PathBuf> {
+        if checksum.len() != 64 || !checksum.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not a SHA-256 checksum: {}", checksum),
+            ));
+        }
+        let checksum = checksum.to_ascii_lowercase();
+        Ok(self.root.join(&checksum[..2]).join(checksum))
+    }
+
+    /// Stores `source` as the blob for `checksum`, unless an intact blob is
+    /// already there, and returns the blob's path.
+    pub fn insert(&self, source: &Path, checksum: &str) -> io::Result<PathBuf> {
+        let blob = self.blob_path(checksum)?;
+        if blob.exists() {
+            // A blob changed in place through one of its links must not be
+            // handed out again under its old checksum.
+            if sha256_file(&blob)?.eq_ignore_ascii_case(checksum) {
+                return Ok(blob);
+            }
+            warn!("Blob {:?} does not match its checksum, replacing it", blob);
+        }
+
+        let dir = blob.parent().unwrap_or(&self.root);
+        fs::create_dir_all(dir)?;
+        let staged = tempfile::Builder::new().prefix(".blob-").tempfile_in(dir)?;
+        fs::copy(source, staged.path())?;
+        // Metadata that points at the blob is synced; the blob must be too.
+        staged.as_file().sync_all()?;
+        // Replacing keeps existing links on the old file intact.
+        staged.persist(&blob).map_err(|e| e.error)?;
+        sync_dir(dir)?;
+        debug!("Stored blob {:?}", blob);
+        Ok(blob)
+    }
+
+    /// Stores `source` and links `target` to its blob, replacing `target`
+    /// if it exists.
+    pub fn store(&self, source: &Path, checksum: &str, target: &Path) -> io::Result<()> {
+        let blob = self.insert(source, checksum)?;
+        match fs::remove_file(target) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        if let Err(e) = fs::hard_link(&blob, target) {
+            debug!("Cannot link {:?} to {:?} ({}), copying", target, blob, e);
+            fs::copy(&blob, target)?;
+            File::open(target)?.sync_all()?;
+        }
+        match target.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => sync_dir(dir),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes the blob for `checksum` if nothing links to it any more and
+    /// returns the bytes freed.
+    pub fn release(&self, checksum: &str) -> io::Result<u64> {
+        let blob = self.blob_path(checksum)?;
+        release_blob(&blob)
+    }
+
+    /// Blobs nothing links to any more, with their sizes.
+    pub fn unreferenced(&self) -> io::Result<Vec<(PathBuf, u64)>> {
+        let mut unreferenced = Vec::new();
+        for blob in self.blobs()? {
+            let metadata = fs::symlink_metadata(&blob)?;
+            if metadata.nlink() <= 1 {
+                unreferenced.push((blob, metadata.len()));
+            }
+        }
+        Ok(unreferenced)
+    }
+
+    /// Device and inode numbers of every blob, to tell links into the
+    /// store from independent files.
+    pub fn inodes(&self) -> io::Result<HashSet<(u64, u64)>> {
+        let mut inodes = HashSet::new();
+        for blob in self.blobs()? {
+            let metadata = fs::symlink_metadata(&blob)?;
+            inodes.insert((metadata.dev(), metadata.ino()));
+        }
+        Ok(inodes)
+    }
+
+    fn blobs(&self) -> io::Result<Vec<PathBuf>> {
+        let shards = match fs::read_dir(&self.root) {
+            Ok(shards) => shards,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut blobs = Vec::new();
+        for shard in shards {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&shard)? {
+                let path = entry?.path();
+                // Skip blobs still being written.
+                if path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| !name.starts_with('.'))
+                {
+                    blobs.push(path);
+                }
+            }
+        }
+        blobs.sort();
+        Ok(blobs)
+    }
+}
+
+fn release_blob(blob: &Path) -> io::Result<u64> {
+    let metadata = match fs::symlink_metadata(blob) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    if metadata.nlink() > 1 {
+        return Ok(0);
+    }
+    fs::remove_file(blob)?;
+    debug!("Removed unreferenced blob {:?}", blob);
+    Ok(metadata.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use tempfile::TempDir;
+
+    fn checksum(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    #[test]
+    fn identical_files_share_one_blob_until_unreferenced() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path().join("blobs"));
+        let source = temp.path().join("App.AppImage");
+        fs::write(&source, b"image").unwrap();
+        let sum = checksum(b"image");
+
+        let a = temp.path().join("a");
+        let b = temp.path().join("b");
+        store.store(&source, &sum, &a).unwrap();
+        store.store(&source, &sum, &b).unwrap();
+        let blob = store.blob_path(&sum).unwrap();
+        assert_eq!(fs::metadata(&blob).unwrap().nlink(), 3);
+        assert_eq!(
+            fs::metadata(&a).unwrap().ino(),
+            fs::metadata(&b).unwrap().ino()
+        );
+
+        fs::remove_file(&a).unwrap();
+        assert_eq!(store.release(&sum).unwrap(), 0);
+        assert!(blob.exists());
+        fs::remove_file(&b).unwrap();
+        assert_eq!(store.release(&sum).unwrap(), 5);
+        assert!(!blob.exists());
+        assert!(store.unreferenced().unwrap().is_empty());
+    }
+
+    #[test]
+    fn tampered_blob_is_replaced_not_reused() {
+        let temp = TempDir::new().unwrap();
+        let store = BlobStore::new(temp.path().join("blobs"));
+        let source = temp.path().join("App.AppImage");
+        fs::write(&source, b"image").unwrap();
+        let sum = checksum(b"image");
+
+        let old = temp.path().join("old");
+        store.store(&source, &sum, &old).unwrap();
+        fs::write(&old, b"evil!").unwrap();
+
+        let new = temp.path().join("new");
+        store.store(&source, &sum, &new).unwrap();
+        assert_eq!(fs::read(&new).unwrap(), b"image");
+        assert_eq!(fs::read(&old).unwrap(), b"evil!");
+        assert!(store.blob_path("../../etc/passwd").is_err());
+    }
+}
@@ -100,6 +100,32 @@ impl MetadataCache {
         Ok(())
     }
 
+    /// Drops every entry registered under `normalized_name` and returns the
+    /// image paths they were for.
+    pub fn remove_app(&mut self, normalized_name: &str) -> Vec<PathBuf> {
+        let keys: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.normalized_name == normalized_name)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            self.entries.remove(key);
+        }
+        keys.into_iter().map(PathBuf::from).collect()
+    }
+
+    /// Image paths and their entries.
+    pub fn entries(&self) -> impl Iterator<Item = (&Path, &CacheEntry)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (Path::new(path.as_str()), entry))
+    }
+
+    pub fn remove_entry(&mut self, path: &Path) -> bool {
+        self.entries.remove(&path.display().to_string()).is_some()
+    }
+
     #[allow(dead_code)]
     pub fn cleanup_stale_entries(&mut self, raw_dir: &Path) -> Result<(), CacheError> {
         if !raw_dir.exists() {
@@ -107,7 +133,7 @@ impl MetadataCache {
         }
 
         let mut to_remove = Vec::new();
-        for (path_str, _) in &self.entries {
+        for path_str in self.entries.keys() {
             let path = Path::new(path_str);
             if !path.exists() {
                 to_remove.push(path_str.clone());
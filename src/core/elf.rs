@@ -0,0 +1,368 @@
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use thiserror::Error;
+
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+const SHT_NOBITS: u32 = 8;
+/// Upper bound for sections read into memory; AppImage metadata sections are
+/// a few KiB at most.
+const MAX_SECTION_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ElfError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Not an ELF file")]
+    NotElf,
+
+    #[error("Malformed ELF header: {0}")]
+    Malformed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Elf32,
+    Elf64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub kind: u32,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// The parts of an ELF file needed to locate data appended to or embedded in
+/// an AppImage runtime. Only headers are read; section contents are fetched
+/// on demand.
+#[derive(Debug, Clone)]
+pub struct ElfFile {
+    pub ident: [u8; 16],
+    pub sections: Vec<Section>,
+    section_table_end: u64,
+    program_table_end: u64,
+    segments_end: u64,
+}
+
+struct Fields<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Fields<'_> {
+    fn u16(&self, at: usize) -> Result<u16, ElfError> {
+        let b = self.slice(at, 2)?;
+        let raw = [b[0], b[1]];
+        Ok(if self.little_endian {
+            u16::from_le_bytes(raw)
+        } else {
+            u16::from_be_bytes(raw)
+        })
+    }
+
+    fn u32(&self, at: usize) -> Result<u32, ElfError> {
+        let b = self.slice(at, 4)?;
+        let raw = [b[0], b[1], b[2], b[3]];
+        Ok(if self.little_endian {
+            u32::from_le_bytes(raw)
+        } else {
+            u32::from_be_bytes(raw)
+        })
+    }
+
+    fn u64(&self, at: usize) -> Result<u64, ElfError> {
+        let b = self.slice(at, 8)?;
+        let mut raw = [0u8; 8];
+        raw.copy_from_slice(b);
+        Ok(if self.little_endian {
+            u64::from_le_bytes(raw)
+        } else {
+            u64::from_be_bytes(raw)
+        })
+    }
+
+    fn addr(&self, class: Class, at: usize) -> Result<u64, ElfError> {
+        match class {
+            Class::Elf32 => self.u32(at).map(u64::from),
+            Class::Elf64 => self.u64(at),
+        }
+    }
+
+    fn slice(&self, at: usize, len: usize) -> Result<&[u8], ElfError> {
+        self.bytes
+            .get(at..at + len)
+            .ok_or_else(|| ElfError::Malformed(format!("truncated field at {}", at)))
+    }
+}
+
+impl ElfFile {
+    pub fn open(path: &Path) -> Result<Self, ElfError> {
+        let file = File::open(path)?;
+        Self::parse(&file)
+    }
+
+    pub fn parse(file: &File) -> Result<Self, ElfError> {
+        let mut header = [0u8; 64];
+        let mut read = 0;
+        while read < header.len() {
+            let n = file.read_at(&mut header[read..], read as u64)?;
+            if n == 0 {
+                break;
+            }
+            read += n;
+        }
+        if read < 16 || header[..4] != ELF_MAGIC {
+            return Err(ElfError::NotElf);
+        }
+
+        let class = match header[4] {
+            1 => Class::Elf32,
+            2 => Class::Elf64,
+            other => return Err(ElfError::Malformed(format!("unknown class {}", other))),
+        };
+        let little_endian = match header[5] {
+            1 => true,
+            2 => false,
+            other => {
+                return Err(ElfError::Malformed(format!(
+                    "unknown data encoding {}",
+                    other
+                )));
+            }
+        };
+
+        let fields = Fields {
+            bytes: &header[..read],
+            little_endian,
+        };
+
+        let (phoff, shoff, phentsize, phnum, shentsize, shnum, shstrndx) = match class {
+            Class::Elf32 => (
+                fields.addr(class, 28)?,
+                fields.addr(class, 32)?,
+                fields.u16(42)?,
+                fields.u16(44)?,
+                fields.u16(46)?,
+                fields.u16(48)?,
+                fields.u16(50)?,
+            ),
+            Class::Elf64 => (
+                fields.addr(class, 32)?,
+                fields.addr(class, 40)?,
+                fields.u16(54)?,
+                fields.u16(56)?,
+                fields.u16(58)?,
+                fields.u16(60)?,
+                fields.u16(62)?,
+            ),
+        };
+
+        let file_len = file.metadata()?.len();
+        let section_table_len = u64::from(shentsize) * u64::from(shnum);
+        let program_table_len = u64::from(phentsize) * u64::from(phnum);
+        if shoff.saturating_add(section_table_len) > file_len
+            || phoff.saturating_add(program_table_len) > file_len
+        {
+            return Err(ElfError::Malformed(
+                "header tables extend past end of file".to_string(),
+            ));
+        }
+
+        let mut ident = [0u8; 16];
+        ident.copy_from_slice(&header[..16]);
+
+        let mut elf = ElfFile {
+            ident,
+            sections: Vec::new(),
+            section_table_end: shoff + section_table_len,
+            program_table_end: phoff + program_table_len,
+            segments_end: 0,
+        };
+
+        if phnum > 0 {
+            let mut table = vec![0u8; usize::from(phentsize) * usize::from(phnum)];
+            file.read_exact_at(&mut table, phoff)?;
+            let fields = Fields {
+                bytes: &table,
+                little_endian,
+            };
+            for i in 0..usize::from(phnum) {
+                let base = i * usize::from(phentsize);
+                let (offset, filesz) = match class {
+                    Class::Elf32 => (
+                        fields.addr(class, base + 4)?,
+                        fields.addr(class, base + 16)?,
+                    ),
+                    Class::Elf64 => (
+                        fields.addr(class, base + 8)?,
+                        fields.addr(class, base + 32)?,
+                    ),
+                };
+                elf.segments_end = elf.segments_end.max(offset.saturating_add(filesz));
+            }
+        }
+
+        if shnum > 0 {
+            let mut table = vec![0u8; usize::from(shentsize) * usize::from(shnum)];
+            file.read_exact_at(&mut table, shoff)?;
+            let fields = Fields {
+                bytes: &table,
+                little_endian,
+            };
+
+            let mut raw = Vec::with_capacity(usize::from(shnum));
+            for i in 0..usize::from(shnum) {
+                let base = i * usize::from(shentsize);
+                let name = fields.u32(base)?;
+                let kind = fields.u32(base + 4)?;
+                let (offset, size) = match class {
+                    Class::Elf32 => (
+                        fields.addr(class, base + 16)?,
+                        fields.addr(class, base + 20)?,
+                    ),
+                    Class::Elf64 => (
+                        fields.addr(class, base + 24)?,
+                        fields.addr(class, base + 32)?,
+                    ),
+                };
+                raw.push((name, kind, offset, size));
+            }
+
+            let names = match raw.get(usize::from(shstrndx)) {
+                Some(&(_, _, offset, size))
+                    if size > 0 && offset.saturating_add(size) <= file_len =>
+                {
+                    let mut buf = vec![0u8; size as usize];
+                    file.read_exact_at(&mut buf, offset)?;
+                    buf
+                }
+                _ => Vec::new(),
+            };
+
+            for (name, kind, offset, size) in raw {
+                let name = names
+                    .get(name as usize..)
+                    .and_then(|rest| rest.split(|&b| b == 0).next())
+                    .map(|n| String::from_utf8_lossy(n).into_owned())
+                    .unwrap_or_default();
+                elf.sections.push(Section {
+                    name,
+                    kind,
+                    offset,
+                    size,
+                });
+            }
+        }
+
+        Ok(elf)
+    }
+
+    /// Offset of the first byte after the ELF image, which is where an
+    /// AppImage runtime expects its filesystem payload to start.
+    pub fn end_offset(&self) -> u64 {
+        let sections_end = self
+            .sections
+            .iter()
+            .filter(|s| s.kind != SHT_NOBITS)
+            .map(|s| s.offset.saturating_add(s.size))
+            .max()
+            .unwrap_or(0);
+
+        self.section_table_end
+            .max(self.program_table_end)
+            .max(self.segments_end)
+            .max(sections_end)
+    }
+
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    /// Reads the contents of the named section from `file`, which must be the
+    /// file this header was parsed from.
+    pub fn read_section(&self, file: &File, name: &str) -> Result<Option<Vec<u8>>, ElfError> {
+        let Some(section) = self.section(name) else {
+            return Ok(None);
+        };
+        if section.kind == SHT_NOBITS {
+            return Ok(Some(Vec::new()));
+        }
+        if section.size > MAX_SECTION_SIZE {
+            return Err(ElfError::Malformed(format!(
+                "section {} is {} bytes",
+                name, section.size
+            )));
+        }
+        if section.offset.saturating_add(section.size) > file.metadata()?.len() {
+            return Err(ElfError::Malformed(format!(
+                "section {} extends past end of file",
+                name
+            )));
+        }
+
+        let mut data = vec![0u8; section.size as usize];
+        file.read_exact_at(&mut data, section.offset)?;
+        Ok(Some(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ElfBuilder;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_rejects_non_elf_files() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("script.AppImage");
+        std::fs::write(&path, b"#!/bin/sh\necho hi\n").unwrap();
+
+        assert!(matches!(ElfFile::open(&path), Err(ElfError::NotElf)));
+    }
+
+    #[test]
+    fn parse_reads_section_names() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("runtime");
+        let image = ElfBuilder::new()
+            .section(".upd_info", b"zsync|https://example.com/app.zsync")
+            .build();
+        std::fs::write(&path, &image).unwrap();
+
+        let elf = ElfFile::open(&path).unwrap();
+        let section = elf.section(".upd_info").unwrap();
+        assert_eq!(section.size, 35);
+        assert!(elf.section(".shstrtab").is_some());
+    }
+
+    #[test]
+    fn read_section_returns_contents() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("runtime");
+        let image = ElfBuilder::new().section(".sig_key", b"key").build();
+        std::fs::write(&path, &image).unwrap();
+
+        let file = File::open(&path).unwrap();
+        let elf = ElfFile::parse(&file).unwrap();
+        assert_eq!(
+            elf.read_section(&file, ".sig_key").unwrap().as_deref(),
+            Some(&b"key"[..])
+        );
+        assert!(elf.read_section(&file, ".upd_info").unwrap().is_none());
+    }
+
+    #[test]
+    fn end_offset_points_past_section_table() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("runtime");
+        let image = ElfBuilder::new().section(".note", b"abc").build();
+        std::fs::write(&path, &image).unwrap();
+
+        let elf = ElfFile::open(&path).unwrap();
+        assert_eq!(elf.end_offset(), image.len() as u64);
+    }
+}
@@ -0,0 +1,77 @@
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Entries of `dir` matching `keep`, sorted; none if `dir` does not exist.
+pub fn list_dir(dir: &Path, keep: impl Fn(&Path) -> bool) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if keep(&path) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Hex-encoded SHA-256 of the file at `path`.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Total size of the files below `path`, not following symlinks.
+pub fn dir_size(path: &Path) -> io::Result<u64> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    if !metadata.is_dir() {
+        return Ok(if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        });
+    }
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += dir_size(&entry?.path())?;
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn list_dir_filters_and_sorts() {
+        let temp = TempDir::new().unwrap();
+        for name in ["b", "a", "c.tmp"] {
+            fs::write(temp.path().join(name), name).unwrap();
+        }
+
+        let paths = list_dir(temp.path(), |path| path.extension().is_none()).unwrap();
+        assert_eq!(paths, vec![temp.path().join("a"), temp.path().join("b")]);
+        assert!(list_dir(&temp.path().join("missing"), |_| true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sha256_file_hashes_contents() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("file");
+        fs::write(&path, b"data").unwrap();
+
+        assert_eq!(sha256_file(&path).unwrap(), hex::encode(Sha256::digest(b"data")));
+    }
+}
@@ -0,0 +1,298 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+/// Writes `data` to `path` so that readers see either the old or the new
+/// contents, even across a crash: the data goes to a temporary file in the
+/// same directory, which is synced and renamed over `path`.
+pub fn atomic_write(path: &Path, data: &[u8]) -> io::Result<()> {
+    let dir = parent(path);
+    let mut staged = tempfile::Builder::new()
+        .prefix(".appiman-")
+        .tempfile_in(dir)?;
+    staged.write_all(data)?;
+    staged.as_file().sync_all()?;
+    staged.persist(path).map_err(|e| e.error)?;
+    sync_dir(dir)
+}
+
+/// Points the symlink `link` at `target`, replacing whatever is at `link`
+/// in one rename so the link never goes missing.
+pub fn atomic_symlink(target: &Path, link: &Path) -> io::Result<()> {
+    let dir = parent(link);
+    let name = link
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let staged = dir.join(format!(".{}.{}.tmp", name, std::process::id()));
+    match fs::remove_file(&staged) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    symlink(target, &staged)?;
+    if let Err(e) = fs::rename(&staged, link) {
+        let _ = fs::remove_file(&staged);
+        return Err(e);
+    }
+    sync_dir(dir)
+}
+
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Makes a rename in `dir` durable.
+pub(crate) fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// An operation on one app's registration state, written down before it
+/// starts so that a crash can be undone or completed on the next run.
+///
+/// Until the new `metadata.json` is written the operation is rolled back:
+/// paths it created are removed and the old metadata is restored. After
+/// that it is committed and rolled forward: paths it made obsolete are
+/// removed. Either way the `current` link is then pointed at the active
+/// version again. Operations therefore only delete data after committing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub operation: String,
+    pub app: String,
+    pub started_at: DateTime<Utc>,
+    /// `metadata.json` as it was before; `None` for a new app.
+    pub metadata_before: Option<String>,
+    /// Removed on rollback.
+    pub created: Vec<PathBuf>,
+    /// Removed once committed.
+    pub obsolete: Vec<PathBuf>,
+    pub committed: bool,
+}
+
+/// One journal file per app with an operation in flight.
+pub struct Journal {
+    dir: PathBuf,
+}
+
+impl Journal {
+    pub fn new(dir: PathBuf) -> Self {
+        Journal { dir }
+    }
+
+    pub fn path(&self, app: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", app))
+    }
+
+    /// Records `transaction` as in flight.
+    pub fn write(&self, transaction: &Transaction) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_vec_pretty(transaction).map_err(io::Error::other)?;
+        atomic_write(&self.path(&transaction.app), &json)
+    }
+
+    /// The unfinished transaction of `app`, if any.
+    pub fn pending(&self, app: &str) -> io::Result<Option<Transaction>> {
+        match fs::read(self.path(app)) {
+            Ok(data) => Ok(Some(
+                serde_json::from_slice(&data).map_err(io::Error::other)?,
+            )),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Apps with an unfinished transaction.
+    pub fn pending_apps(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut apps = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json")
+                && let Some(app) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                apps.push(app.to_string());
+            }
+        }
+        apps.sort();
+        Ok(apps)
+    }
+
+    pub fn finish(&self, app: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(app)) {
+            Ok(()) => sync_dir(&self.dir),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::core::VersionManager;
+    use crate::core::testing::fake_appimage;
+    use tempfile::TempDir;
+
+    fn manager(temp: &TempDir) -> VersionManager {
+        let mut config = Config::default();
+        config.directories.bin = temp.path().join("bin").display().to_string();
+        let manager = VersionManager::new(config);
+        for (version, body) in [("1.0", b"one"), ("2.0", b"two")] {
+            let image = temp.path().join(format!("{}.AppImage", version));
+            fs::write(&image, fake_appimage(body)).unwrap();
+            manager.install_version("app", version, &image).unwrap();
+        }
+        manager
+    }
+
+    fn interrupted(manager: &VersionManager, operation: &str, committed: bool) -> Transaction {
+        Transaction {
+            operation: operation.to_string(),
+            app: "app".to_string(),
+            started_at: Utc::now(),
+            metadata_before: fs::read_to_string(manager.get_metadata_path("app")).ok(),
+            created: Vec::new(),
+            obsolete: Vec::new(),
+            committed,
+        }
+    }
+
+    #[test]
+    fn uncommitted_install_is_rolled_back() {
+        let temp = TempDir::new().unwrap();
+        let manager = manager(&temp);
+        let before = fs::read_to_string(manager.get_metadata_path("app")).unwrap();
+
+        // Crashed after copying the image, before saving the metadata.
+        let version_dir = manager.get_version_dir("app", "3.0");
+        fs::create_dir_all(&version_dir).unwrap();
+        fs::write(manager.get_appimage_path("app", "3.0"), "three").unwrap();
+        let transaction = Transaction {
+            created: vec![version_dir.clone()],
+            ..interrupted(&manager, "install", false)
+        };
+        manager.journal().write(&transaction).unwrap();
+        fs::remove_file(manager.get_current_link("app")).unwrap();
+
+        let recovered = manager.recover().unwrap();
+        assert_eq!(recovered, [transaction]);
+        assert!(!version_dir.exists());
+        assert_eq!(
+            fs::read_to_string(manager.get_metadata_path("app")).unwrap(),
+            before
+        );
+        assert_eq!(
+            fs::read_link(manager.get_current_link("app")).unwrap(),
+            manager.get_version_dir("app", "2.0")
+        );
+        assert!(manager.journal().pending_apps().unwrap().is_empty());
+    }
+
+    #[test]
+    fn committed_remove_is_rolled_forward_before_the_next_change() {
+        let temp = TempDir::new().unwrap();
+        let manager = manager(&temp);
+
+        // Crashed after saving the metadata, before removing the directory.
+        let mut metadata = manager.load_app_metadata("app").unwrap();
+        metadata.remove_version("1.0");
+        manager.save_app_metadata(&metadata).unwrap();
+        let version_dir = manager.get_version_dir("app", "1.0");
+        let transaction = Transaction {
+            obsolete: vec![version_dir.clone()],
+            ..interrupted(&manager, "remove", true)
+        };
+        manager.journal().write(&transaction).unwrap();
+
+        manager.set_channel("app", Some("beta")).unwrap();
+        assert!(!version_dir.exists());
+        assert!(manager.journal().pending("app").unwrap().is_none());
+        let versions = manager.list_versions("app").unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(manager.channel("app").as_deref(), Some("beta"));
+    }
+
+    #[test]
+    fn failed_update_removes_its_backup() {
+        let temp = TempDir::new().unwrap();
+        let manager = manager(&temp);
+        let before = fs::read_to_string(manager.get_metadata_path("app")).unwrap();
+
+        // Fails after the backup is made, when creating the version directory.
+        fs::write(manager.get_version_dir("app", "3.0"), "in the way").unwrap();
+        let image = temp.path().join("3.0.AppImage");
+        fs::write(&image, fake_appimage(b"three")).unwrap();
+        let backup = temp.path().join("bin/backups/app_backup_1.AppImage");
+        assert!(manager
+            .install_update("app", "3.0", &image, Some(&backup))
+            .is_err());
+
+        assert!(!backup.exists());
+        assert_eq!(
+            fs::read_to_string(manager.get_metadata_path("app")).unwrap(),
+            before
+        );
+        assert!(manager.journal().pending_apps().unwrap().is_empty());
+    }
+
+    #[test]
+    fn committed_uninstall_is_completed_on_recovery() {
+        let temp = TempDir::new().unwrap();
+        let manager = manager(&temp);
+        let app_dir = manager.get_app_dir("app");
+
+        // Crashed after removing the metadata, before removing the versions.
+        let transaction = Transaction {
+            obsolete: vec![app_dir.clone()],
+            ..interrupted(&manager, "uninstall", true)
+        };
+        fs::remove_file(manager.get_metadata_path("app")).unwrap();
+        manager.journal().write(&transaction).unwrap();
+
+        assert_eq!(manager.recover().unwrap().len(), 1);
+        assert!(!app_dir.exists());
+        assert!(manager.journal().pending_apps().unwrap().is_empty());
+    }
+
+    #[test]
+    fn remove_app_keeps_listed_paths_and_frees_blobs() {
+        let temp = TempDir::new().unwrap();
+        let manager = manager(&temp);
+        let kept = manager.get_app_dir("app").join("signing_key.pin");
+        fs::write(&kept, "ABCD").unwrap();
+
+        let removed = manager.remove_app("app", std::slice::from_ref(&kept)).unwrap();
+        assert!(removed.contains(&manager.get_versions_dir("app")));
+        assert!(!manager.get_metadata_path("app").exists());
+        assert!(!manager.get_versions_dir("app").exists());
+        assert!(kept.exists());
+        assert!(manager.blobs().inodes().unwrap().is_empty());
+        assert!(manager.journal().pending_apps().unwrap().is_empty());
+    }
+
+    #[test]
+    fn atomic_symlink_replaces_existing_link() {
+        let temp = TempDir::new().unwrap();
+        let link = temp.path().join("current");
+        atomic_symlink(Path::new("versions/1.0"), &link).unwrap();
+        atomic_symlink(Path::new("versions/2.0"), &link).unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), Path::new("versions/2.0"));
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 1);
+
+        atomic_write(&temp.path().join("metadata.json"), b"{}").unwrap();
+        atomic_write(&temp.path().join("metadata.json"), b"[]").unwrap();
+        assert_eq!(fs::read(temp.path().join("metadata.json")).unwrap(), b"[]");
+        assert_eq!(fs::read_dir(temp.path()).unwrap().count(), 2);
+    }
+}
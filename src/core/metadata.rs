@@ -3,6 +3,12 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use thiserror::Error;
 
+use crate::core::Version;
+
+/// Name shown for apps without a channel, and accepted by `versions
+/// channel` to clear it.
+pub const DEFAULT_CHANNEL: &str = "default";
+
 #[derive(Debug, Error)]
 pub enum MetadataError {
     #[error("Failed to parse desktop entry: {0}")]
@@ -31,6 +37,16 @@ pub struct AppMetadata {
     pub categories: Vec<String>,
     pub icon_path: Option<String>,
     pub versions: Vec<VersionInfo>,
+    /// Version the app is held at; updates and switches leave it alone.
+    #[serde(default)]
+    pub pinned_version: Option<String>,
+    /// Update channel to follow, like `stable` or `beta`; `None` follows
+    /// whatever release the image's update information names.
+    #[serde(default)]
+    pub channel: Option<String>,
+    /// Version that was active before the current one.
+    #[serde(default)]
+    pub previous_version: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -57,11 +73,27 @@ impl Metadata {
         }
     }
 
+    #[allow(dead_code)]
     pub fn from_desktop_entry(path: &Path) -> Result<Self, MetadataError> {
         let content = std::fs::read_to_string(path)?;
+        Ok(Self::from_desktop_entry_content(&content))
+    }
+
+    pub fn from_desktop_entry_content(content: &str) -> Self {
         let mut metadata = Metadata::new("Unknown".to_string(), String::new());
+        let mut in_main_group = true;
 
         for line in content.lines() {
+            let line = line.trim_end();
+            if line.starts_with('[') {
+                // Keys in [Desktop Action ...] groups must not override the main entry.
+                in_main_group = line == "[Desktop Entry]";
+                continue;
+            }
+            if !in_main_group {
+                continue;
+            }
+
             if let Some(stripped) = line.strip_prefix("Name=") {
                 metadata.name = stripped.trim().to_string();
             } else if let Some(stripped) = line.strip_prefix("Categories=") {
@@ -75,7 +107,7 @@ impl Metadata {
             }
         }
 
-        Ok(metadata)
+        metadata
     }
 
     #[allow(dead_code)]
@@ -104,6 +136,12 @@ impl Metadata {
     }
 }
 
+impl VersionInfo {
+    pub fn parsed(&self) -> Version {
+        Version::parse(&self.version)
+    }
+}
+
 impl AppMetadata {
     pub fn new(display_name: String, normalized_name: String) -> Self {
         AppMetadata {
@@ -112,22 +150,34 @@ impl AppMetadata {
             categories: vec!["Utility".to_string()],
             icon_path: None,
             versions: Vec::new(),
+            pinned_version: None,
+            channel: None,
+            previous_version: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
 
+    /// Adds `version` and makes it active, unless the app is pinned, in
+    /// which case it is stored inactive next to the pinned version.
     pub fn add_version(&mut self, version: String, checksum: String) -> &mut VersionInfo {
-        // Deactivate all other versions
-        for v in &mut self.versions {
-            v.is_active = false;
+        let activate = self.pinned_version.is_none();
+        if activate {
+            if let Some(active) = self.get_active_version() {
+                self.previous_version = Some(active.version.clone());
+            }
+
+            // Deactivate all other versions
+            for v in &mut self.versions {
+                v.is_active = false;
+            }
         }
 
         let version_info = VersionInfo {
             version: version.clone(),
             checksum,
             installed_at: Utc::now(),
-            is_active: true,
+            is_active: activate,
         };
 
         self.versions.push(version_info);
@@ -141,6 +191,7 @@ impl AppMetadata {
     }
 
     pub fn set_active_version(&mut self, version: &str) -> bool {
+        let before = self.get_active_version().map(|v| v.version.clone());
         let mut found = false;
         for v in &mut self.versions {
             if v.version == version {
@@ -151,11 +202,37 @@ impl AppMetadata {
             }
         }
         if found {
+            if before.as_deref() != Some(version) {
+                self.previous_version = before;
+            }
             self.updated_at = Utc::now();
         }
         found
     }
 
+    /// Makes the newest version active.
+    pub fn activate_newest(&mut self) {
+        if let Some(newest) = self.newest_version().map(|v| v.version.clone()) {
+            self.set_active_version(&newest);
+        }
+    }
+
+    /// Versions from oldest to newest by version number; install time
+    /// breaks ties, such as between unversioned images.
+    pub fn sorted_versions(&self) -> Vec<&VersionInfo> {
+        let mut versions: Vec<&VersionInfo> = self.versions.iter().collect();
+        versions.sort_by(|a, b| {
+            a.parsed()
+                .cmp(&b.parsed())
+                .then(a.installed_at.cmp(&b.installed_at))
+        });
+        versions
+    }
+
+    pub fn newest_version(&self) -> Option<&VersionInfo> {
+        self.sorted_versions().pop()
+    }
+
     pub fn get_version(&self, version: &str) -> Option<&VersionInfo> {
         self.versions.iter().find(|v| v.version == version)
     }
@@ -163,6 +240,9 @@ impl AppMetadata {
     pub fn remove_version(&mut self, version: &str) -> bool {
         if let Some(pos) = self.versions.iter().position(|v| v.version == version) {
             self.versions.remove(pos);
+            if self.previous_version.as_deref() == Some(version) {
+                self.previous_version = None;
+            }
             self.updated_at = Utc::now();
             true
         } else {
@@ -202,6 +282,16 @@ Icon=testapp
         assert_eq!(metadata.icon_path, Some("testapp".to_string()));
     }
 
+    #[test]
+    fn metadata_from_desktop_entry_ignores_action_groups() {
+        let content = "[Desktop Entry]\nName=Browser\nIcon=browser\n\n[Desktop Action new-window]\nName=New Window\n";
+
+        let metadata = Metadata::from_desktop_entry_content(content);
+
+        assert_eq!(metadata.name, "Browser");
+        assert_eq!(metadata.icon_path, Some("browser".to_string()));
+    }
+
     #[test]
     fn metadata_serialization_works() {
         let metadata = Metadata::new("TestApp".to_string(), "abc123".to_string());
@@ -212,6 +302,49 @@ Icon=testapp
         assert_eq!(metadata.checksum, deserialized.checksum);
     }
 
+    #[test]
+    fn app_metadata_without_pin_or_channel_loads_defaults() {
+        let mut metadata = AppMetadata::new("App".to_string(), "app".to_string());
+        metadata.add_version("1.0".to_string(), "abc".to_string());
+        let mut json: serde_json::Value = serde_json::from_str(&metadata.to_json().unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("pinned_version");
+        json.as_object_mut().unwrap().remove("channel");
+        json.as_object_mut().unwrap().remove("previous_version");
+
+        let loaded = AppMetadata::from_json(&json.to_string()).unwrap();
+        assert_eq!(loaded.pinned_version, None);
+        assert_eq!(loaded.channel, None);
+        assert_eq!(loaded, metadata);
+    }
+
+    #[test]
+    fn versions_sort_by_version_number() {
+        let mut metadata = AppMetadata::new("App".to_string(), "app".to_string());
+        for version in ["1.10.0", "latest", "2.0.0-rc1", "1.9.2", "2.0.0"] {
+            metadata.add_version(version.to_string(), String::new());
+        }
+        let sorted: Vec<&str> = metadata
+            .sorted_versions()
+            .iter()
+            .map(|v| v.version.as_str())
+            .collect();
+        assert_eq!(sorted, ["latest", "1.9.2", "1.10.0", "2.0.0-rc1", "2.0.0"]);
+        assert_eq!(metadata.newest_version().unwrap().version, "2.0.0");
+    }
+
+    #[test]
+    fn add_version_keeps_pinned_version_active() {
+        let mut metadata = AppMetadata::new("App".to_string(), "app".to_string());
+        metadata.add_version("1.0.0".to_string(), "abc".to_string());
+        metadata.pinned_version = Some("1.0.0".to_string());
+
+        metadata.add_version("2.0.0".to_string(), "def".to_string());
+
+        assert_eq!(metadata.get_active_version().unwrap().version, "1.0.0");
+        assert!(!metadata.get_version("2.0.0").unwrap().is_active);
+        assert_eq!(metadata.previous_version, None);
+    }
+
     #[test]
     fn metadata_setters_work() {
         let mut metadata = Metadata::new("TestApp".to_string(), "abc123".to_string());
@@ -1,11 +1,25 @@
 pub mod appimage;
+pub mod blob_store;
 pub mod cache;
+pub mod elf;
+pub mod files;
+pub mod journal;
 pub mod metadata;
 pub mod normalization;
+pub mod payload;
+pub mod retention;
+pub mod squashfs;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod update_info;
+pub mod version;
 pub mod version_manager;
 
-pub use appimage::{AppImage, AppImageError};
+pub use appimage::{AppImage, AppImageError, AppImageType};
+pub use blob_store::BlobStore;
 pub use cache::MetadataCache;
-pub use metadata::{AppMetadata, Metadata, VersionInfo};
-pub use normalization::normalize_appimage_name;
+pub use metadata::{AppMetadata, DEFAULT_CHANNEL, Metadata, VersionInfo};
+pub use normalization::{normalize_appimage_name, wildcard_matches};
+pub use update_info::UpdateInformation;
+pub use version::{Version, version_from_file_name};
 pub use version_manager::{VersionError, VersionManager};
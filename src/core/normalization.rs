@@ -19,6 +19,13 @@ pub fn normalize_appimage_name(name: &str) -> String {
     normalized_separators.trim_matches('-').trim().to_string()
 }
 
+/// Matches `name` against a pattern in which `*` stands for any run of
+/// characters.
+pub fn wildcard_matches(pattern: &str, name: &str) -> bool {
+    let regex = format!("^{}$", regex::escape(pattern).replace(r"\*", ".*"));
+    Regex::new(&regex).is_ok_and(|re| re.is_match(name))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
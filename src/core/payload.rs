@@ -0,0 +1,313 @@
+use regex::Regex;
+use std::path::Path;
+use thiserror::Error;
+
+use super::elf::{ElfError, ElfFile};
+use super::squashfs::{EntryKind, SquashFs, SquashfsError};
+
+const ICON_EXTENSIONS: &[&str] = &["png", "svg", "xpm"];
+const ICON_SEARCH_DIRS: &[&str] = &[
+    "usr/share/icons/hicolor/scalable/apps",
+    "usr/share/icons/hicolor/512x512/apps",
+    "usr/share/icons/hicolor/256x256/apps",
+    "usr/share/icons/hicolor/128x128/apps",
+    "usr/share/icons/hicolor/64x64/apps",
+    "usr/share/icons/hicolor/48x48/apps",
+    "usr/share/pixmaps",
+];
+const APPSTREAM_DIRS: &[&str] = &["usr/share/metainfo", "usr/share/appdata"];
+
+lazy_static::lazy_static! {
+    // AppStream lists releases newest first. Versions become directory
+    // names, so only plain version characters are accepted.
+    static ref RELEASE_VERSION: Regex = Regex::new(
+        r#"<release\b[^>]*?\bversion\s*=\s*["']([0-9A-Za-z][0-9A-Za-z.+~_-]*)["']"#
+    ).unwrap();
+}
+
+#[derive(Debug, Error)]
+pub enum PayloadError {
+    #[error("ELF error: {0}")]
+    Elf(#[from] ElfError),
+
+    #[error("Squashfs error: {0}")]
+    Squashfs(#[from] SquashfsError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedIcon {
+    pub data: Vec<u8>,
+    pub extension: String,
+}
+
+/// The filesystem bundled inside an AppImage, read in-process without
+/// executing the image's runtime.
+#[derive(Debug)]
+pub struct AppImagePayload {
+    fs: SquashFs,
+}
+
+impl AppImagePayload {
+    pub fn open(path: &Path) -> Result<Self, PayloadError> {
+        let offset = ElfFile::open(path)?.end_offset();
+        Ok(AppImagePayload {
+            fs: SquashFs::open_at(path, offset)?,
+        })
+    }
+
+    /// The first `.desktop` file at the image root, as `(file name, contents)`.
+    pub fn desktop_entry(&self) -> Result<Option<(String, String)>, PayloadError> {
+        let entry = self
+            .fs
+            .read_dir("/")?
+            .into_iter()
+            .filter(|e| e.kind != EntryKind::Directory && e.name.ends_with(".desktop"))
+            .min_by(|a, b| a.name.cmp(&b.name));
+
+        match entry {
+            Some(entry) => {
+                let content = self.fs.read_file(&entry.name)?;
+                Ok(Some((
+                    entry.name,
+                    String::from_utf8_lossy(&content).into_owned(),
+                )))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the application icon, preferring `.DirIcon`, then the name from
+    /// the desktop entry's `Icon=` key at the root and in the usual theme
+    /// directories, then any image at the root.
+    pub fn icon(&self, icon_name: Option<&str>) -> Result<Option<EmbeddedIcon>, PayloadError> {
+        if let Some(icon) = self.read_icon(".DirIcon")? {
+            return Ok(Some(icon));
+        }
+
+        if let Some(name) = icon_name.filter(|n| !n.is_empty() && !n.contains('/')) {
+            let mut candidates = Vec::new();
+            for ext in ICON_EXTENSIONS {
+                candidates.push(format!("{}.{}", name, ext));
+                for dir in ICON_SEARCH_DIRS {
+                    candidates.push(format!("{}/{}.{}", dir, name, ext));
+                }
+            }
+            for candidate in candidates {
+                if let Some(icon) = self.read_icon(&candidate)? {
+                    return Ok(Some(icon));
+                }
+            }
+        }
+
+        let mut root_images: Vec<_> = self
+            .fs
+            .read_dir("/")?
+            .into_iter()
+            .filter(|e| e.kind != EntryKind::Directory && icon_extension(&e.name).is_some())
+            .map(|e| e.name)
+            .collect();
+        root_images.sort();
+        for name in root_images {
+            if let Some(icon) = self.read_icon(&name)? {
+                return Ok(Some(icon));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Version of the newest release in the image's AppStream metadata.
+    pub fn release_version(&self) -> Result<Option<String>, PayloadError> {
+        Ok(self.appstream()?.and_then(|xml| {
+            RELEASE_VERSION
+                .captures(&xml)
+                .map(|captures| captures[1].to_string())
+        }))
+    }
+
+    /// Raw AppStream metadata, if the image ships any.
+    fn appstream(&self) -> Result<Option<String>, PayloadError> {
+        for dir in APPSTREAM_DIRS {
+            let entries = match self.fs.read_dir(dir) {
+                Ok(entries) => entries,
+                Err(SquashfsError::NotFound(_)) | Err(SquashfsError::NotADirectory(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let mut names: Vec<_> = entries
+                .into_iter()
+                .filter(|e| e.name.ends_with(".xml"))
+                .map(|e| e.name)
+                .collect();
+            names.sort();
+            if let Some(name) = names.first() {
+                let content = self.fs.read_file(&format!("{}/{}", dir, name))?;
+                return Ok(Some(String::from_utf8_lossy(&content).into_owned()));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.fs.exists(path)
+    }
+
+    fn read_icon(&self, path: &str) -> Result<Option<EmbeddedIcon>, PayloadError> {
+        let data = match self.fs.read_file(path) {
+            Ok(data) => data,
+            Err(SquashfsError::NotFound(_))
+            | Err(SquashfsError::NotAFile(_))
+            | Err(SquashfsError::NotADirectory(_)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let extension = sniff_image_type(&data)
+            .or_else(|| icon_extension(path))
+            .unwrap_or("png");
+        Ok(Some(EmbeddedIcon {
+            data,
+            extension: extension.to_string(),
+        }))
+    }
+}
+
+fn icon_extension(name: &str) -> Option<&'static str> {
+    let ext = Path::new(name).extension()?.to_str()?;
+    ICON_EXTENSIONS
+        .iter()
+        .find(|known| ext.eq_ignore_ascii_case(known))
+        .copied()
+}
+
+fn sniff_image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some("png");
+    }
+    if data.starts_with(b"/* XPM */") {
+        return Some("xpm");
+    }
+    let head = String::from_utf8_lossy(&data[..data.len().min(512)]);
+    if head.contains("<svg") {
+        return Some("svg");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{ElfBuilder, SquashfsBuilder, appimage};
+    use tempfile::TempDir;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nfake";
+
+    fn write(payload: SquashfsBuilder) -> (TempDir, std::path::PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("Test.AppImage");
+        std::fs::write(&path, appimage(&ElfBuilder::new(), &payload)).unwrap();
+        (temp, path)
+    }
+
+    #[test]
+    fn open_finds_payload_after_runtime() {
+        let (_temp, path) = write(
+            SquashfsBuilder::new()
+                .file("test.desktop", b"[Desktop Entry]\nName=Test\nIcon=test\n")
+                .file("AppRun", b"#!/bin/sh\n"),
+        );
+
+        let payload = AppImagePayload::open(&path).unwrap();
+        let (name, content) = payload.desktop_entry().unwrap().unwrap();
+        assert_eq!(name, "test.desktop");
+        assert!(content.contains("Name=Test"));
+        assert!(payload.exists("AppRun"));
+    }
+
+    #[test]
+    fn open_rejects_runtime_without_payload() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("Test.AppImage");
+        std::fs::write(&path, ElfBuilder::new().build()).unwrap();
+
+        assert!(matches!(
+            AppImagePayload::open(&path),
+            Err(PayloadError::Squashfs(SquashfsError::BadMagic(_)))
+        ));
+    }
+
+    #[test]
+    fn icon_prefers_diricon_and_sniffs_type() {
+        let (_temp, path) = write(
+            SquashfsBuilder::new()
+                .file("usr/share/icons/hicolor/256x256/apps/test.png", PNG)
+                .symlink(".DirIcon", "usr/share/icons/hicolor/256x256/apps/test.png")
+                .file("other.svg", b"<svg/>"),
+        );
+
+        let icon = AppImagePayload::open(&path)
+            .unwrap()
+            .icon(None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(icon.data, PNG);
+        assert_eq!(icon.extension, "png");
+    }
+
+    #[test]
+    fn icon_falls_back_to_named_theme_icon() {
+        let (_temp, path) = write(
+            SquashfsBuilder::new()
+                .file("usr/share/icons/hicolor/scalable/apps/test.svg", b"<svg/>"),
+        );
+
+        let payload = AppImagePayload::open(&path).unwrap();
+        let icon = payload.icon(Some("test")).unwrap().unwrap();
+        assert_eq!(icon.extension, "svg");
+        assert!(payload.icon(Some("missing")).unwrap().is_none());
+    }
+
+    #[test]
+    fn appstream_reads_metainfo() {
+        let (_temp, path) = write(SquashfsBuilder::new().file(
+            "usr/share/metainfo/org.test.App.appdata.xml",
+            b"<component/>",
+        ));
+
+        let payload = AppImagePayload::open(&path).unwrap();
+        assert_eq!(
+            payload.appstream().unwrap().as_deref(),
+            Some("<component/>")
+        );
+        assert_eq!(payload.release_version().unwrap(), None);
+    }
+
+    #[test]
+    fn release_version_takes_newest_safe_release() {
+        let (_temp, path) = write(SquashfsBuilder::new().file(
+            "usr/share/appdata/org.test.App.appdata.xml",
+            br#"<component><releases>
+  <release date="2026-03-01" version="2.1.0-rc1"/>
+  <release version="2.0.0" date="2026-01-01"/>
+</releases></component>"#,
+        ));
+        assert_eq!(
+            AppImagePayload::open(&path)
+                .unwrap()
+                .release_version()
+                .unwrap()
+                .as_deref(),
+            Some("2.1.0-rc1")
+        );
+
+        let (_temp, path) = write(SquashfsBuilder::new().file(
+            "usr/share/metainfo/org.test.App.metainfo.xml",
+            br#"<releases><release version="../../etc"/></releases>"#,
+        ));
+        assert_eq!(
+            AppImagePayload::open(&path)
+                .unwrap()
+                .release_version()
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,410 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::core::AppMetadata;
+use crate::core::files::list_dir;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Which installed versions and update backups to keep, from the
+/// `[versions]` config section and `updates.max_backups`.
+///
+/// The active, pinned and previously active versions are always kept.
+/// Other versions are kept while they are among the newest `keep_last` or
+/// were installed less than `keep_days` ago, and backups while they are
+/// among the newest `keep_backups` or younger than `keep_days`, unless the
+/// app or all apps together use more than the size caps allow. Backups
+/// count toward the caps like versions do.
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    pub keep_last: usize,
+    pub keep_backups: usize,
+    pub keep_days: Option<u64>,
+    pub max_app_bytes: Option<u64>,
+    pub max_total_bytes: Option<u64>,
+}
+
+/// Why a version or backup is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeepReason {
+    Active,
+    Pinned,
+    PreviouslyActive,
+    /// Among the newest `keep_last` versions or `keep_backups` backups.
+    Newest,
+    /// Installed or backed up less than `keep_days` ago.
+    Recent,
+}
+
+impl KeepReason {
+    /// Protected versions are kept even when a size cap is exceeded.
+    pub fn is_protected(self) -> bool {
+        matches!(
+            self,
+            KeepReason::Active | KeepReason::Pinned | KeepReason::PreviouslyActive
+        )
+    }
+}
+
+/// What a [`Verdict`] is about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Retained {
+    Version(String),
+    /// An update backup, `<app>_backup_<timestamp>.AppImage`.
+    Backup(PathBuf),
+}
+
+/// An update backup found in the backup directory.
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
+
+/// The policy's verdict on one installed version or update backup.
+#[derive(Debug, Clone, Serialize)]
+pub struct Verdict {
+    pub app: String,
+    pub item: Retained,
+    /// When the version was installed or the backup made.
+    pub installed_at: DateTime<Utc>,
+    pub size_bytes: u64,
+    /// `None` if it is to be removed.
+    pub keep: Option<KeepReason>,
+}
+
+impl RetentionPolicy {
+    pub fn from_config(config: &Config) -> Self {
+        let versions = &config.versions;
+        RetentionPolicy {
+            keep_last: versions.max_versions_per_app,
+            keep_backups: config.updates.max_backups,
+            keep_days: versions.keep_days,
+            max_app_bytes: versions.max_app_size_mb.map(|mb| mb * MIB),
+            max_total_bytes: versions.max_total_size_mb.map(|mb| mb * MIB),
+        }
+    }
+
+    /// Judges every version of one app, oldest first, then its update
+    /// `backups`, applying everything but the global size cap. `size_of`
+    /// gives the disk usage of a version.
+    pub fn plan_app(
+        &self,
+        metadata: &AppMetadata,
+        size_of: impl Fn(&str) -> u64,
+        backups: &[Backup],
+        now: DateTime<Utc>,
+    ) -> Vec<Verdict> {
+        let sorted = metadata.sorted_versions();
+        let previous = metadata.previous_version.clone().or_else(|| {
+            // Metadata written before the previous version was recorded.
+            let active = sorted.iter().position(|v| v.is_active)?;
+            Some(sorted.get(active.checked_sub(1)?)?.version.clone())
+        });
+        let newest_from = sorted.len().saturating_sub(self.keep_last);
+
+        let mut verdicts: Vec<Verdict> = sorted
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let keep = if v.is_active {
+                    Some(KeepReason::Active)
+                } else if metadata.pinned_version.as_ref() == Some(&v.version) {
+                    Some(KeepReason::Pinned)
+                } else if previous.as_ref() == Some(&v.version) {
+                    Some(KeepReason::PreviouslyActive)
+                } else if i >= newest_from {
+                    Some(KeepReason::Newest)
+                } else if self.is_recent(v.installed_at, now) {
+                    Some(KeepReason::Recent)
+                } else {
+                    None
+                };
+                Verdict {
+                    app: metadata.name.clone(),
+                    item: Retained::Version(v.version.clone()),
+                    installed_at: v.installed_at,
+                    size_bytes: size_of(&v.version),
+                    keep,
+                }
+            })
+            .collect();
+
+        let mut backups: Vec<&Backup> = backups.iter().collect();
+        backups.sort_by_key(|backup| backup.created_at);
+        let newest_from = backups.len().saturating_sub(self.keep_backups);
+        let first_backup = verdicts.len();
+        verdicts.extend(backups.iter().enumerate().map(|(i, backup)| {
+            let keep = if i >= newest_from {
+                Some(KeepReason::Newest)
+            } else if self.is_recent(backup.created_at, now) {
+                Some(KeepReason::Recent)
+            } else {
+                None
+            };
+            Verdict {
+                app: metadata.name.clone(),
+                item: Retained::Backup(backup.path.clone()),
+                installed_at: backup.created_at,
+                size_bytes: backup.size_bytes,
+                keep,
+            }
+        }));
+
+        if let Some(cap) = self.max_app_bytes {
+            // Backups copy versions, so they go first, oldest first; then
+            // the oldest versions.
+            let order: Vec<usize> = (first_backup..verdicts.len())
+                .chain(0..first_backup)
+                .collect();
+            evict(&mut verdicts, &order, cap);
+        }
+        verdicts
+    }
+
+    fn is_recent(&self, time: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        self.keep_days
+            .is_some_and(|days| now - time < Duration::days(days as i64))
+    }
+
+    /// Applies the global size cap to the verdicts of all apps, removing
+    /// the oldest installs first.
+    pub fn apply_total_cap(&self, verdicts: &mut [Verdict]) {
+        let Some(cap) = self.max_total_bytes else {
+            return;
+        };
+        let mut order: Vec<usize> = (0..verdicts.len()).collect();
+        order.sort_by_key(|&i| verdicts[i].installed_at);
+        evict(verdicts, &order, cap);
+    }
+}
+
+/// Update backups by app, from files in the backup directory named
+/// `<app>_backup_<timestamp>.AppImage`.
+pub fn list_backups(config: &Config) -> io::Result<BTreeMap<String, Vec<Backup>>> {
+    let backup_dir = config.bin_dir().join("backups");
+    let mut by_app: BTreeMap<String, Vec<Backup>> = BTreeMap::new();
+    for path in list_dir(&backup_dir, |path| backup_app(path).is_some())? {
+        let metadata = fs::symlink_metadata(&path)?;
+        if let Some(app) = backup_app(&path)
+            && metadata.is_file()
+        {
+            by_app.entry(app).or_default().push(Backup {
+                created_at: metadata.modified()?.into(),
+                size_bytes: metadata.len(),
+                path,
+            });
+        }
+    }
+    Ok(by_app)
+}
+
+/// App of a backup named `<app>_backup_<timestamp>.AppImage`.
+fn backup_app(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    let (app, _) = name.strip_suffix(".AppImage")?.rsplit_once("_backup_")?;
+    Some(app.to_string())
+}
+
+/// Drops unprotected versions and backups in `order` until the kept ones fit in `cap`
+/// bytes.
+fn evict(verdicts: &mut [Verdict], order: &[usize], cap: u64) {
+    let mut kept: u64 = verdicts
+        .iter()
+        .filter(|v| v.keep.is_some())
+        .map(|v| v.size_bytes)
+        .sum();
+    for &i in order {
+        if kept <= cap {
+            break;
+        }
+        let verdict = &mut verdicts[i];
+        if verdict.keep.is_some_and(|reason| !reason.is_protected()) {
+            verdict.keep = None;
+            kept -= verdict.size_bytes;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(versions: &[(&str, i64)], active: &str) -> AppMetadata {
+        let now = Utc::now();
+        let mut metadata = AppMetadata::new("App".to_string(), "app".to_string());
+        for (version, age_days) in versions {
+            metadata
+                .add_version(version.to_string(), String::new())
+                .installed_at = now - Duration::days(*age_days);
+        }
+        metadata.set_active_version(active);
+        metadata
+    }
+
+    fn kept(verdicts: &[Verdict]) -> Vec<(&str, KeepReason)> {
+        verdicts
+            .iter()
+            .filter_map(|v| {
+                let name = match &v.item {
+                    Retained::Version(version) => version.as_str(),
+                    Retained::Backup(path) => path.to_str()?,
+                };
+                Some((name, v.keep?))
+            })
+            .collect()
+    }
+
+    fn backup(name: &str, age_days: i64) -> Backup {
+        Backup {
+            path: PathBuf::from(name),
+            created_at: Utc::now() - Duration::days(age_days),
+            size_bytes: 100,
+        }
+    }
+
+    fn policy() -> RetentionPolicy {
+        RetentionPolicy {
+            keep_last: 2,
+            keep_backups: 1,
+            keep_days: None,
+            max_app_bytes: None,
+            max_total_bytes: None,
+        }
+    }
+
+    #[test]
+    fn keeps_protected_newest_and_recent_versions() {
+        // 1.5 became active after 2.0, so 2.0 was active before it.
+        let mut metadata = app(
+            &[
+                ("1.0", 90),
+                ("1.1", 60),
+                ("1.2", 5),
+                ("1.5", 40),
+                ("2.0", 30),
+                ("3.0", 1),
+            ],
+            "2.0",
+        );
+        metadata.set_active_version("1.5");
+        metadata.pinned_version = Some("1.0".to_string());
+        let policy = RetentionPolicy {
+            keep_days: Some(7),
+            ..policy()
+        };
+
+        let verdicts = policy.plan_app(&metadata, |_| 1, &[], Utc::now());
+        assert_eq!(
+            kept(&verdicts),
+            [
+                ("1.0", KeepReason::Pinned),
+                ("1.2", KeepReason::Recent),
+                ("1.5", KeepReason::Active),
+                ("2.0", KeepReason::PreviouslyActive),
+                ("3.0", KeepReason::Newest),
+            ]
+        );
+    }
+
+    #[test]
+    fn size_caps_evict_oldest_unprotected_versions() {
+        let metadata = app(&[("1.0", 3), ("2.0", 2), ("3.0", 1), ("4.0", 0)], "4.0");
+        let capped = RetentionPolicy {
+            keep_last: 10,
+            max_app_bytes: Some(250),
+            ..policy()
+        };
+
+        // 3.0 was active before 4.0 and survives the cap.
+        let verdicts = capped.plan_app(&metadata, |_| 100, &[], Utc::now());
+        assert_eq!(
+            kept(&verdicts),
+            [
+                ("3.0", KeepReason::PreviouslyActive),
+                ("4.0", KeepReason::Active)
+            ]
+        );
+
+        let mut all = RetentionPolicy {
+            keep_last: 10,
+            ..policy()
+        }
+        .plan_app(&metadata, |_| 100, &[], Utc::now());
+        RetentionPolicy {
+            max_total_bytes: Some(300),
+            ..policy()
+        }
+        .apply_total_cap(&mut all);
+        assert_eq!(
+            kept(&all),
+            [
+                ("2.0", KeepReason::Newest),
+                ("3.0", KeepReason::PreviouslyActive),
+                ("4.0", KeepReason::Active)
+            ]
+        );
+    }
+
+    #[test]
+    fn backups_are_kept_by_count_and_age_and_count_toward_caps() {
+        let metadata = app(&[("1.0", 20), ("2.0", 10)], "2.0");
+        let backups = [backup("b1", 30), backup("b2", 3), backup("b3", 1)];
+        let policy = RetentionPolicy {
+            keep_days: Some(7),
+            ..policy()
+        };
+
+        let verdicts = policy.plan_app(&metadata, |_| 100, &backups, Utc::now());
+        assert_eq!(
+            kept(&verdicts),
+            [
+                ("1.0", KeepReason::PreviouslyActive),
+                ("2.0", KeepReason::Active),
+                ("b2", KeepReason::Recent),
+                ("b3", KeepReason::Newest),
+            ]
+        );
+
+        // Backups go before unprotected versions under the per-app cap.
+        let metadata = app(&[("1.0", 20), ("2.0", 10), ("3.0", 0)], "3.0");
+        let capped = RetentionPolicy {
+            keep_last: 10,
+            max_app_bytes: Some(300),
+            ..policy
+        };
+        let verdicts = capped.plan_app(&metadata, |_| 100, &backups, Utc::now());
+        assert_eq!(
+            kept(&verdicts),
+            [
+                ("1.0", KeepReason::Newest),
+                ("2.0", KeepReason::PreviouslyActive),
+                ("3.0", KeepReason::Active),
+            ]
+        );
+
+        // And by age under the global cap.
+        let mut all = policy.plan_app(&metadata, |_| 100, &backups, Utc::now());
+        RetentionPolicy {
+            max_total_bytes: Some(300),
+            ..policy
+        }
+        .apply_total_cap(&mut all);
+        assert_eq!(
+            kept(&all),
+            [
+                ("2.0", KeepReason::PreviouslyActive),
+                ("3.0", KeepReason::Active),
+                ("b3", KeepReason::Newest),
+            ]
+        );
+    }
+}
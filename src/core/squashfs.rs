@@ -0,0 +1,791 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use thiserror::Error;
+
+const SQUASHFS_MAGIC: u32 = 0x7371_7368;
+const SUPERBLOCK_SIZE: usize = 96;
+const METADATA_SIZE: usize = 8192;
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+const DATA_UNCOMPRESSED: u32 = 1 << 24;
+const NO_FRAGMENT: u32 = 0xFFFF_FFFF;
+const FRAGMENT_ENTRY_SIZE: u64 = 16;
+const MAX_SYMLINK_HOPS: usize = 16;
+
+/// Upper bound on a single file read out of an image. Desktop entries, icons
+/// and AppStream files are tiny; anything larger is not something we should
+/// be buffering from an untrusted download.
+pub const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum SquashfsError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("No squashfs superblock at offset {0}")]
+    BadMagic(u64),
+
+    #[error("Unsupported squashfs version {0}.{1}")]
+    UnsupportedVersion(u16, u16),
+
+    #[error("Unsupported squashfs compression id {0}")]
+    UnsupportedCompression(u16),
+
+    #[error("Decompression failed: {0}")]
+    Decompress(String),
+
+    #[error("Corrupt squashfs image: {0}")]
+    Corrupt(String),
+
+    #[error("Path not found in image: {0}")]
+    NotFound(String),
+
+    #[error("Not a directory: {0}")]
+    NotADirectory(String),
+
+    #[error("Not a regular file: {0}")]
+    NotAFile(String),
+
+    #[error("Too many symlink hops resolving {0}")]
+    SymlinkLoop(String),
+
+    #[error("File too large to read from image: {0} bytes")]
+    TooLarge(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    fn from_id(id: u16) -> Result<Self, SquashfsError> {
+        match id {
+            1 => Ok(Compression::Gzip),
+            4 => Ok(Compression::Xz),
+            6 => Ok(Compression::Zstd),
+            other => Err(SquashfsError::UnsupportedCompression(other)),
+        }
+    }
+
+    fn decompress(&self, input: &[u8], limit: usize) -> Result<Vec<u8>, SquashfsError> {
+        let mut out = Vec::with_capacity(limit);
+        let result = match self {
+            Compression::Gzip => flate2::read::ZlibDecoder::new(input)
+                .take(limit as u64 + 1)
+                .read_to_end(&mut out)
+                .map(|_| ()),
+            Compression::Zstd => ruzstd::StreamingDecoder::new(input)
+                .map_err(|e| std::io::Error::other(e.to_string()))
+                .and_then(|d| d.take(limit as u64 + 1).read_to_end(&mut out).map(|_| ())),
+            Compression::Xz => {
+                let mut reader = std::io::BufReader::new(input);
+                let mut writer = LimitedWriter {
+                    inner: &mut out,
+                    limit: limit + 1,
+                };
+                lzma_rs::xz_decompress(&mut reader, &mut writer)
+                    .map_err(|e| std::io::Error::other(e.to_string()))
+            }
+        };
+        result.map_err(|e| SquashfsError::Decompress(e.to_string()))?;
+
+        if out.len() > limit {
+            return Err(SquashfsError::Decompress(format!(
+                "block expands past {} bytes",
+                limit
+            )));
+        }
+        Ok(out)
+    }
+}
+
+struct LimitedWriter<'a> {
+    inner: &'a mut Vec<u8>,
+    limit: usize,
+}
+
+impl std::io::Write for LimitedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.inner.len() + buf.len() > self.limit {
+            return Err(std::io::Error::other("decompressed data exceeds limit"));
+        }
+        self.inner.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Superblock {
+    block_size: u32,
+    fragment_count: u32,
+    compression: Compression,
+    root_inode: u64,
+    bytes_used: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Directory,
+    File,
+    Symlink,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub kind: EntryKind,
+    inode_ref: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Inode {
+    Directory {
+        block: u32,
+        offset: u16,
+        size: u32,
+    },
+    File {
+        blocks_start: u64,
+        size: u64,
+        fragment: u32,
+        fragment_offset: u32,
+        block_sizes: Vec<u32>,
+    },
+    Symlink(String),
+    Other,
+}
+
+/// Read-only access to a squashfs 4.0 filesystem embedded at an offset
+/// inside a file, such as the payload of a type 2 AppImage.
+#[derive(Debug)]
+pub struct SquashFs {
+    file: File,
+    offset: u64,
+    superblock: Superblock,
+}
+
+impl SquashFs {
+    pub fn open_at(path: &Path, offset: u64) -> Result<Self, SquashfsError> {
+        let file = File::open(path)?;
+        Self::from_file(file, offset)
+    }
+
+    pub fn from_file(file: File, offset: u64) -> Result<Self, SquashfsError> {
+        let mut raw = [0u8; SUPERBLOCK_SIZE];
+        file.read_exact_at(&mut raw, offset)
+            .map_err(|_| SquashfsError::BadMagic(offset))?;
+
+        if le_u32(&raw, 0) != SQUASHFS_MAGIC {
+            return Err(SquashfsError::BadMagic(offset));
+        }
+
+        let (major, minor) = (le_u16(&raw, 28), le_u16(&raw, 30));
+        if major != 4 {
+            return Err(SquashfsError::UnsupportedVersion(major, minor));
+        }
+
+        let block_size = le_u32(&raw, 12);
+        if !(4096..=1024 * 1024).contains(&block_size) || !block_size.is_power_of_two() {
+            return Err(SquashfsError::Corrupt(format!(
+                "invalid block size {}",
+                block_size
+            )));
+        }
+
+        let superblock = Superblock {
+            block_size,
+            fragment_count: le_u32(&raw, 16),
+            compression: Compression::from_id(le_u16(&raw, 20))?,
+            root_inode: le_u64(&raw, 32),
+            bytes_used: le_u64(&raw, 40),
+            inode_table: le_u64(&raw, 64),
+            directory_table: le_u64(&raw, 72),
+            fragment_table: le_u64(&raw, 80),
+        };
+        let payload_len = file.metadata()?.len().saturating_sub(offset);
+        if superblock.bytes_used > payload_len {
+            return Err(SquashfsError::Corrupt(format!(
+                "filesystem of {} bytes exceeds the {} bytes available",
+                superblock.bytes_used, payload_len
+            )));
+        }
+        let mut tables = vec![superblock.inode_table, superblock.directory_table];
+        // Images without fragments have no fragment table.
+        if superblock.fragment_count > 0 {
+            tables.push(superblock.fragment_table);
+        }
+        if tables.iter().any(|&table| table > superblock.bytes_used) {
+            return Err(SquashfsError::Corrupt(
+                "table offset beyond the end of the filesystem".into(),
+            ));
+        }
+
+        Ok(SquashFs {
+            file,
+            offset,
+            superblock,
+        })
+    }
+
+    pub fn read_dir(&self, path: &str) -> Result<Vec<DirEntry>, SquashfsError> {
+        match self.resolve(path, true)? {
+            Inode::Directory {
+                block,
+                offset,
+                size,
+            } => self.read_listing(block, offset, size),
+            _ => Err(SquashfsError::NotADirectory(path.to_string())),
+        }
+    }
+
+    pub fn read_file(&self, path: &str) -> Result<Vec<u8>, SquashfsError> {
+        match self.resolve(path, true)? {
+            Inode::File {
+                blocks_start,
+                size,
+                fragment,
+                fragment_offset,
+                block_sizes,
+            } => self.read_file_data(blocks_start, size, fragment, fragment_offset, &block_sizes),
+            _ => Err(SquashfsError::NotAFile(path.to_string())),
+        }
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.resolve(path, false).is_ok()
+    }
+
+    fn resolve(&self, path: &str, follow_last: bool) -> Result<Inode, SquashfsError> {
+        let root = self.read_inode(self.superblock.root_inode)?;
+        let mut pending: VecDeque<String> = split_path(path).collect();
+        let mut stack: Vec<Inode> = Vec::new();
+        let mut hops = 0;
+
+        while let Some(component) = pending.pop_front() {
+            match component.as_str() {
+                "." => continue,
+                ".." => {
+                    stack.pop();
+                    continue;
+                }
+                _ => {}
+            }
+
+            let current = stack.last().unwrap_or(&root);
+            let Inode::Directory {
+                block,
+                offset,
+                size,
+            } = *current
+            else {
+                return Err(SquashfsError::NotADirectory(path.to_string()));
+            };
+
+            let entry = self
+                .read_listing(block, offset, size)?
+                .into_iter()
+                .find(|e| e.name == component)
+                .ok_or_else(|| SquashfsError::NotFound(path.to_string()))?;
+            let inode = self.read_inode(entry.inode_ref)?;
+
+            if let Inode::Symlink(target) = &inode
+                && (follow_last || !pending.is_empty())
+            {
+                hops += 1;
+                if hops > MAX_SYMLINK_HOPS {
+                    return Err(SquashfsError::SymlinkLoop(path.to_string()));
+                }
+                if target.starts_with('/') {
+                    // Absolute links point at the host filesystem, not the image.
+                    return Err(SquashfsError::NotFound(path.to_string()));
+                }
+                for part in split_path(target).collect::<Vec<_>>().into_iter().rev() {
+                    pending.push_front(part);
+                }
+                continue;
+            }
+
+            stack.push(inode);
+        }
+
+        Ok(stack.pop().unwrap_or(root))
+    }
+
+    fn read_listing(
+        &self,
+        block: u32,
+        offset: u16,
+        size: u32,
+    ) -> Result<Vec<DirEntry>, SquashfsError> {
+        // Directory sizes include three bytes for the implicit "." and ".." entries.
+        if size <= 3 {
+            return Ok(Vec::new());
+        }
+        let mut reader = MetadataReader::new(
+            self,
+            checked_offset(self.superblock.directory_table, u64::from(block))?,
+            offset,
+        );
+        let mut remaining = (size - 3) as usize;
+        let mut entries = Vec::new();
+
+        while remaining > 0 {
+            let header = reader.read(12)?;
+            remaining = remaining.checked_sub(12).ok_or_else(|| {
+                SquashfsError::Corrupt("directory header overruns listing".into())
+            })?;
+            let count = le_u32(&header, 0) as usize + 1;
+            let start = le_u32(&header, 4);
+            if count > 256 {
+                return Err(SquashfsError::Corrupt(
+                    "directory header count too large".into(),
+                ));
+            }
+
+            for _ in 0..count {
+                let raw = reader.read(8)?;
+                let inode_offset = le_u16(&raw, 0);
+                let kind = match le_u16(&raw, 4) {
+                    1 | 8 => EntryKind::Directory,
+                    2 | 9 => EntryKind::File,
+                    3 | 10 => EntryKind::Symlink,
+                    _ => EntryKind::Other,
+                };
+                let name_len = le_u16(&raw, 6) as usize + 1;
+                let name = reader.read(name_len)?;
+                remaining = remaining.checked_sub(8 + name_len).ok_or_else(|| {
+                    SquashfsError::Corrupt("directory entry overruns listing".into())
+                })?;
+
+                entries.push(DirEntry {
+                    name: String::from_utf8_lossy(&name).into_owned(),
+                    kind,
+                    inode_ref: (u64::from(start) << 16) | u64::from(inode_offset),
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn read_inode(&self, inode_ref: u64) -> Result<Inode, SquashfsError> {
+        let block = inode_ref >> 16;
+        let offset = (inode_ref & 0xFFFF) as u16;
+        let mut reader =
+            MetadataReader::new(self, checked_offset(self.superblock.inode_table, block)?, offset);
+
+        let header = reader.read(16)?;
+        match le_u16(&header, 0) {
+            1 => {
+                let raw = reader.read(16)?;
+                Ok(Inode::Directory {
+                    block: le_u32(&raw, 0),
+                    size: u32::from(le_u16(&raw, 8)),
+                    offset: le_u16(&raw, 10),
+                })
+            }
+            8 => {
+                let raw = reader.read(24)?;
+                Ok(Inode::Directory {
+                    size: le_u32(&raw, 4),
+                    block: le_u32(&raw, 8),
+                    offset: le_u16(&raw, 18),
+                })
+            }
+            2 => {
+                let raw = reader.read(16)?;
+                let size = u64::from(le_u32(&raw, 12));
+                let fragment = le_u32(&raw, 4);
+                let block_sizes = self.read_block_sizes(&mut reader, size, fragment)?;
+                Ok(Inode::File {
+                    blocks_start: u64::from(le_u32(&raw, 0)),
+                    size,
+                    fragment,
+                    fragment_offset: le_u32(&raw, 8),
+                    block_sizes,
+                })
+            }
+            9 => {
+                let raw = reader.read(40)?;
+                let size = le_u64(&raw, 8);
+                let fragment = le_u32(&raw, 28);
+                let block_sizes = self.read_block_sizes(&mut reader, size, fragment)?;
+                Ok(Inode::File {
+                    blocks_start: le_u64(&raw, 0),
+                    size,
+                    fragment,
+                    fragment_offset: le_u32(&raw, 32),
+                    block_sizes,
+                })
+            }
+            3 | 10 => {
+                let raw = reader.read(8)?;
+                let len = le_u32(&raw, 4) as usize;
+                if len > 4096 {
+                    return Err(SquashfsError::Corrupt("symlink target too long".into()));
+                }
+                let target = reader.read(len)?;
+                Ok(Inode::Symlink(
+                    String::from_utf8_lossy(&target).into_owned(),
+                ))
+            }
+            _ => Ok(Inode::Other),
+        }
+    }
+
+    fn read_block_sizes(
+        &self,
+        reader: &mut MetadataReader<'_>,
+        size: u64,
+        fragment: u32,
+    ) -> Result<Vec<u32>, SquashfsError> {
+        if size > MAX_FILE_SIZE {
+            // Large files are never read, so don't bother walking their block list.
+            return Ok(Vec::new());
+        }
+        let block_size = u64::from(self.superblock.block_size);
+        let mut count = size / block_size;
+        if fragment == NO_FRAGMENT && !size.is_multiple_of(block_size) {
+            count += 1;
+        }
+        let raw = reader.read(count as usize * 4)?;
+        Ok(raw.chunks_exact(4).map(|c| le_u32(c, 0)).collect())
+    }
+
+    fn read_file_data(
+        &self,
+        blocks_start: u64,
+        size: u64,
+        fragment: u32,
+        fragment_offset: u32,
+        block_sizes: &[u32],
+    ) -> Result<Vec<u8>, SquashfsError> {
+        if size > MAX_FILE_SIZE {
+            return Err(SquashfsError::TooLarge(size));
+        }
+
+        let block_size = self.superblock.block_size as usize;
+        let mut data = Vec::with_capacity(size as usize);
+        let mut position = checked_offset(self.offset, blocks_start)?;
+
+        for &entry in block_sizes {
+            let on_disk = entry & !DATA_UNCOMPRESSED;
+            let want = block_size.min(size as usize - data.len());
+            if on_disk == 0 {
+                data.resize(data.len() + want, 0);
+                continue;
+            }
+            let block = self.read_data_block(position, entry)?;
+            position = checked_offset(position, u64::from(on_disk))?;
+            data.extend_from_slice(&block[..want.min(block.len())]);
+        }
+
+        if fragment != NO_FRAGMENT {
+            let tail = size as usize - data.len();
+            let (start, entry) = self.fragment_entry(fragment)?;
+            let block = self.read_data_block(checked_offset(self.offset, start)?, entry)?;
+            let from = fragment_offset as usize;
+            let slice = block
+                .get(from..from + tail)
+                .ok_or_else(|| SquashfsError::Corrupt("fragment slice out of range".into()))?;
+            data.extend_from_slice(slice);
+        }
+
+        if data.len() as u64 != size {
+            return Err(SquashfsError::Corrupt(format!(
+                "file data length {} does not match inode size {}",
+                data.len(),
+                size
+            )));
+        }
+        Ok(data)
+    }
+
+    fn fragment_entry(&self, index: u32) -> Result<(u64, u32), SquashfsError> {
+        if index >= self.superblock.fragment_count {
+            return Err(SquashfsError::Corrupt(format!(
+                "fragment index {} out of range",
+                index
+            )));
+        }
+        let per_block = METADATA_SIZE as u64 / FRAGMENT_ENTRY_SIZE;
+        let mut pointer = [0u8; 8];
+        let table = checked_offset(self.offset, self.superblock.fragment_table)?;
+        self.file.read_exact_at(
+            &mut pointer,
+            checked_offset(table, (u64::from(index) / per_block) * 8)?,
+        )?;
+        let within = ((u64::from(index) % per_block) * FRAGMENT_ENTRY_SIZE) as u16;
+        let mut reader = MetadataReader::new(self, le_u64(&pointer, 0), within);
+        let raw = reader.read(FRAGMENT_ENTRY_SIZE as usize)?;
+        Ok((le_u64(&raw, 0), le_u32(&raw, 8)))
+    }
+
+    fn read_data_block(&self, position: u64, entry: u32) -> Result<Vec<u8>, SquashfsError> {
+        let on_disk = (entry & !DATA_UNCOMPRESSED) as usize;
+        let block_size = self.superblock.block_size as usize;
+        if on_disk > block_size {
+            return Err(SquashfsError::Corrupt(format!(
+                "data block of {} bytes exceeds block size",
+                on_disk
+            )));
+        }
+        let mut raw = vec![0u8; on_disk];
+        self.file.read_exact_at(&mut raw, position)?;
+        if entry & DATA_UNCOMPRESSED != 0 {
+            Ok(raw)
+        } else {
+            self.superblock.compression.decompress(&raw, block_size)
+        }
+    }
+
+    /// Reads one metadata block at `position` (relative to the filesystem
+    /// start), returning its contents and the position of the next block.
+    fn read_metadata_block(&self, position: u64) -> Result<(Vec<u8>, u64), SquashfsError> {
+        let start = checked_offset(self.offset, position)?;
+        let mut header = [0u8; 2];
+        self.file.read_exact_at(&mut header, start)?;
+        let header = u16::from_le_bytes(header);
+        let on_disk = (header & !METADATA_UNCOMPRESSED) as usize;
+        if on_disk == 0 || on_disk > METADATA_SIZE {
+            return Err(SquashfsError::Corrupt(format!(
+                "metadata block at {} has invalid size {}",
+                position, on_disk
+            )));
+        }
+
+        let mut raw = vec![0u8; on_disk];
+        self.file.read_exact_at(&mut raw, checked_offset(start, 2)?)?;
+        let data = if header & METADATA_UNCOMPRESSED != 0 {
+            raw
+        } else {
+            self.superblock
+                .compression
+                .decompress(&raw, METADATA_SIZE)?
+        };
+        Ok((data, checked_offset(position, 2 + on_disk as u64)?))
+    }
+}
+
+/// Sequential reader over a chain of metadata blocks.
+struct MetadataReader<'a> {
+    fs: &'a SquashFs,
+    next_block: u64,
+    buffer: Vec<u8>,
+    cursor: usize,
+}
+
+impl<'a> MetadataReader<'a> {
+    fn new(fs: &'a SquashFs, block: u64, offset: u16) -> Self {
+        MetadataReader {
+            fs,
+            next_block: block,
+            buffer: Vec::new(),
+            cursor: offset as usize,
+        }
+    }
+
+    fn read(&mut self, len: usize) -> Result<Vec<u8>, SquashfsError> {
+        let mut out = Vec::with_capacity(len);
+        while out.len() < len {
+            if self.cursor >= self.buffer.len() {
+                let skip = self.cursor - self.buffer.len();
+                let (block, next) = self.fs.read_metadata_block(self.next_block)?;
+                self.buffer = block;
+                self.next_block = next;
+                self.cursor = skip;
+                if self.cursor > self.buffer.len() {
+                    return Err(SquashfsError::Corrupt(
+                        "metadata offset out of range".into(),
+                    ));
+                }
+                continue;
+            }
+            let take = (len - out.len()).min(self.buffer.len() - self.cursor);
+            out.extend_from_slice(&self.buffer[self.cursor..self.cursor + take]);
+            self.cursor += take;
+        }
+        Ok(out)
+    }
+}
+
+/// `base + offset` for offsets read from the image, which a crafted image
+/// can make overflow.
+fn checked_offset(base: u64, offset: u64) -> Result<u64, SquashfsError> {
+    base.checked_add(offset)
+        .ok_or_else(|| SquashfsError::Corrupt("offset out of range".into()))
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = String> + '_ {
+    path.split('/')
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_string())
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+fn le_u64(bytes: &[u8], at: usize) -> u64 {
+    let mut raw = [0u8; 8];
+    raw.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::SquashfsBuilder;
+    use tempfile::TempDir;
+
+    fn open(image: Vec<u8>) -> (TempDir, SquashFs) {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("image.squashfs");
+        std::fs::write(&path, image).unwrap();
+        let fs = SquashFs::open_at(&path, 0).unwrap();
+        (temp, fs)
+    }
+
+    #[test]
+    fn open_rejects_missing_superblock() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("image.squashfs");
+        std::fs::write(&path, vec![0u8; 128]).unwrap();
+
+        assert!(matches!(
+            SquashFs::open_at(&path, 0),
+            Err(SquashfsError::BadMagic(0))
+        ));
+    }
+
+    #[test]
+    fn open_rejects_tables_beyond_the_image() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("image.squashfs");
+        let image = SquashfsBuilder::new().file("a.txt", b"a").build();
+
+        // bytes_used, then inode_table
+        for at in [40, 64] {
+            let mut crafted = image.clone();
+            crafted[at..at + 8].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+            std::fs::write(&path, crafted).unwrap();
+            assert!(matches!(
+                SquashFs::open_at(&path, 0),
+                Err(SquashfsError::Corrupt(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn reads_root_directory_and_files() {
+        let image = SquashfsBuilder::new()
+            .file("app.desktop", b"[Desktop Entry]\nName=App\n")
+            .file("usr/bin/app", b"#!/bin/sh\n")
+            .symlink("AppRun", "usr/bin/app")
+            .build();
+        let (_temp, fs) = open(image);
+
+        let mut entries: Vec<_> = fs
+            .read_dir("/")
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.name, e.kind))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            entries,
+            vec![
+                ("AppRun".to_string(), EntryKind::Symlink),
+                ("app.desktop".to_string(), EntryKind::File),
+                ("usr".to_string(), EntryKind::Directory),
+            ]
+        );
+        assert_eq!(
+            fs.read_file("app.desktop").unwrap(),
+            b"[Desktop Entry]\nName=App\n"
+        );
+        assert_eq!(fs.read_file("AppRun").unwrap(), b"#!/bin/sh\n");
+    }
+
+    #[test]
+    fn reads_multi_block_files_with_fragments() {
+        let content: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let image = SquashfsBuilder::new()
+            .fragments(true)
+            .file("big.bin", &content)
+            .file("small.txt", b"tail")
+            .build();
+        let (_temp, fs) = open(image);
+
+        assert_eq!(fs.read_file("big.bin").unwrap(), content);
+        assert_eq!(fs.read_file("small.txt").unwrap(), b"tail");
+    }
+
+    #[test]
+    fn reads_gzip_compressed_images() {
+        let content = b"Name=Compressed\n".repeat(600);
+        let image = SquashfsBuilder::new()
+            .compressed(true)
+            .fragments(true)
+            .file("share/app.desktop", &content)
+            .build();
+        let (_temp, fs) = open(image);
+
+        assert_eq!(fs.superblock.compression, Compression::Gzip);
+        assert_eq!(fs.read_file("share/app.desktop").unwrap(), content);
+    }
+
+    #[test]
+    fn relative_symlinks_resolve_through_parent_dirs() {
+        let image = SquashfsBuilder::new()
+            .file("usr/share/icons/app.png", b"png")
+            .symlink(".DirIcon", "usr/share/../share/icons/app.png")
+            .symlink("loop", "loop")
+            .symlink("host", "/etc/passwd")
+            .build();
+        let (_temp, fs) = open(image);
+
+        assert_eq!(fs.read_file(".DirIcon").unwrap(), b"png");
+        assert!(matches!(
+            fs.read_file("loop"),
+            Err(SquashfsError::SymlinkLoop(_))
+        ));
+        assert!(matches!(
+            fs.read_file("host"),
+            Err(SquashfsError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn missing_paths_are_reported() {
+        let image = SquashfsBuilder::new().file("a.txt", b"a").build();
+        let (_temp, fs) = open(image);
+
+        assert!(!fs.exists("b.txt"));
+        assert!(matches!(
+            fs.read_file("a.txt/child"),
+            Err(SquashfsError::NotADirectory(_))
+        ));
+        assert!(matches!(
+            fs.read_dir("a.txt"),
+            Err(SquashfsError::NotADirectory(_))
+        ));
+    }
+}
@@ -0,0 +1,466 @@
+//! Builders for synthetic ELF runtimes and squashfs images used by unit tests,
+//! and a config that keeps every directory inside a test's temporary root.
+//!
+//! Real AppImages are far too large to check in, so tests assemble minimal
+//! images that exercise the same on-disk structures.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::config::Config;
+
+const METADATA_SIZE: usize = 8192;
+const BLOCK_SIZE: usize = 4096;
+const BLOCK_LOG: u16 = 12;
+
+pub struct ElfBuilder {
+    magic: Option<[u8; 3]>,
+    sections: Vec<(String, Vec<u8>)>,
+}
+
+impl ElfBuilder {
+    pub fn new() -> Self {
+        ElfBuilder {
+            magic: Some(*b"AI\x02"),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Overrides the AppImage magic at `e_ident[8..11]`; `None` leaves it zeroed.
+    pub fn magic(mut self, magic: Option<[u8; 3]>) -> Self {
+        self.magic = magic;
+        self
+    }
+
+    pub fn section(mut self, name: &str, data: &[u8]) -> Self {
+        self.sections.push((name.to_string(), data.to_vec()));
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut names = vec![0u8];
+        let mut name_offsets = Vec::new();
+        for (name, _) in &self.sections {
+            name_offsets.push(names.len() as u32);
+            names.extend_from_slice(name.as_bytes());
+            names.push(0);
+        }
+        let shstrtab_name = names.len() as u32;
+        names.extend_from_slice(b".shstrtab\0");
+
+        let mut out = vec![0u8; 64];
+        let mut headers = vec![[0u8; 64]];
+
+        for ((_, data), name) in self.sections.iter().zip(&name_offsets) {
+            headers.push(section_header(
+                *name,
+                1,
+                out.len() as u64,
+                data.len() as u64,
+            ));
+            out.extend_from_slice(data);
+        }
+        headers.push(section_header(
+            shstrtab_name,
+            3,
+            out.len() as u64,
+            names.len() as u64,
+        ));
+        out.extend_from_slice(&names);
+
+        while !out.len().is_multiple_of(8) {
+            out.push(0);
+        }
+        let shoff = out.len() as u64;
+        for header in &headers {
+            out.extend_from_slice(header);
+        }
+
+        out[..4].copy_from_slice(b"\x7fELF");
+        out[4] = 2; // ELFCLASS64
+        out[5] = 1; // little endian
+        out[6] = 1; // EV_CURRENT
+        if let Some(magic) = self.magic {
+            out[8..11].copy_from_slice(&magic);
+        }
+        out[16..18].copy_from_slice(&2u16.to_le_bytes()); // ET_EXEC
+        out[18..20].copy_from_slice(&0x3eu16.to_le_bytes()); // x86_64
+        out[20..24].copy_from_slice(&1u32.to_le_bytes());
+        out[40..48].copy_from_slice(&shoff.to_le_bytes());
+        out[52..54].copy_from_slice(&64u16.to_le_bytes());
+        out[54..56].copy_from_slice(&56u16.to_le_bytes());
+        out[58..60].copy_from_slice(&64u16.to_le_bytes());
+        out[60..62].copy_from_slice(&(headers.len() as u16).to_le_bytes());
+        out[62..64].copy_from_slice(&((headers.len() - 1) as u16).to_le_bytes());
+        out
+    }
+}
+
+fn section_header(name: u32, kind: u32, offset: u64, size: u64) -> [u8; 64] {
+    let mut header = [0u8; 64];
+    header[0..4].copy_from_slice(&name.to_le_bytes());
+    header[4..8].copy_from_slice(&kind.to_le_bytes());
+    header[24..32].copy_from_slice(&offset.to_le_bytes());
+    header[32..40].copy_from_slice(&size.to_le_bytes());
+    header[48..56].copy_from_slice(&1u64.to_le_bytes());
+    header
+}
+
+enum Node {
+    Dir(BTreeMap<String, Node>),
+    File(Vec<u8>),
+    Symlink(String),
+}
+
+pub struct SquashfsBuilder {
+    root: BTreeMap<String, Node>,
+    compressed: bool,
+    fragments: bool,
+}
+
+struct Writer {
+    data: Vec<u8>,
+    inodes: Vec<u8>,
+    directories: Vec<u8>,
+    fragment: Vec<u8>,
+    fragment_entries: Vec<(u64, u32)>,
+    compressed: bool,
+    use_fragments: bool,
+    next_inode: u32,
+}
+
+impl SquashfsBuilder {
+    pub fn new() -> Self {
+        SquashfsBuilder {
+            root: BTreeMap::new(),
+            compressed: false,
+            fragments: false,
+        }
+    }
+
+    pub fn compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    pub fn fragments(mut self, fragments: bool) -> Self {
+        self.fragments = fragments;
+        self
+    }
+
+    pub fn file(mut self, path: &str, data: &[u8]) -> Self {
+        self.insert(path, Node::File(data.to_vec()));
+        self
+    }
+
+    pub fn symlink(mut self, path: &str, target: &str) -> Self {
+        self.insert(path, Node::Symlink(target.to_string()));
+        self
+    }
+
+    fn insert(&mut self, path: &str, node: Node) {
+        let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+        let mut dir = &mut self.root;
+        for part in &parts[..parts.len() - 1] {
+            let entry = dir
+                .entry(part.to_string())
+                .or_insert_with(|| Node::Dir(BTreeMap::new()));
+            dir = match entry {
+                Node::Dir(children) => children,
+                _ => panic!("{} is not a directory", part),
+            };
+        }
+        dir.insert(parts[parts.len() - 1].to_string(), node);
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut writer = Writer {
+            data: vec![0u8; 96],
+            inodes: Vec::new(),
+            directories: Vec::new(),
+            fragment: Vec::new(),
+            fragment_entries: Vec::new(),
+            compressed: self.compressed,
+            use_fragments: self.fragments,
+            next_inode: 1,
+        };
+
+        let (root_ref, _) = writer.write_dir(&self.root, 0);
+        writer.flush_fragment();
+
+        let mut out = std::mem::take(&mut writer.data);
+        let inode_table = out.len() as u64;
+        out.extend(writer.metadata(&writer.inodes));
+        let directory_table = out.len() as u64;
+        out.extend(writer.metadata(&writer.directories));
+
+        let mut entries = Vec::new();
+        for (start, size) in &writer.fragment_entries {
+            entries.extend_from_slice(&start.to_le_bytes());
+            entries.extend_from_slice(&size.to_le_bytes());
+            entries.extend_from_slice(&0u32.to_le_bytes());
+        }
+        let fragment_table = if entries.is_empty() {
+            u64::MAX
+        } else {
+            let fragment_meta = out.len() as u64;
+            out.extend(writer.metadata(&entries));
+            let table = out.len() as u64;
+            out.extend_from_slice(&fragment_meta.to_le_bytes());
+            table
+        };
+
+        let id_meta = out.len() as u64;
+        out.extend(writer.metadata(&0u32.to_le_bytes()));
+        let id_table = out.len() as u64;
+        out.extend_from_slice(&id_meta.to_le_bytes());
+
+        let mut flags: u16 = 0;
+        if !self.compressed {
+            flags |= 0x0001 | 0x0002 | 0x0008;
+        }
+        if !self.fragments {
+            flags |= 0x0010;
+        }
+
+        let bytes_used = out.len() as u64;
+        let sb = &mut out[..96];
+        sb[0..4].copy_from_slice(&0x7371_7368u32.to_le_bytes());
+        sb[4..8].copy_from_slice(&(writer.next_inode - 1).to_le_bytes());
+        sb[12..16].copy_from_slice(&(BLOCK_SIZE as u32).to_le_bytes());
+        sb[16..20].copy_from_slice(&(writer.fragment_entries.len() as u32).to_le_bytes());
+        sb[20..22].copy_from_slice(&1u16.to_le_bytes());
+        sb[22..24].copy_from_slice(&BLOCK_LOG.to_le_bytes());
+        sb[24..26].copy_from_slice(&flags.to_le_bytes());
+        sb[26..28].copy_from_slice(&1u16.to_le_bytes());
+        sb[28..30].copy_from_slice(&4u16.to_le_bytes());
+        sb[32..40].copy_from_slice(&root_ref.to_le_bytes());
+        sb[40..48].copy_from_slice(&bytes_used.to_le_bytes());
+        sb[48..56].copy_from_slice(&id_table.to_le_bytes());
+        sb[56..64].copy_from_slice(&u64::MAX.to_le_bytes());
+        sb[64..72].copy_from_slice(&inode_table.to_le_bytes());
+        sb[72..80].copy_from_slice(&directory_table.to_le_bytes());
+        sb[80..88].copy_from_slice(&fragment_table.to_le_bytes());
+        sb[88..96].copy_from_slice(&u64::MAX.to_le_bytes());
+        out
+    }
+}
+
+impl Writer {
+    /// Position of the next byte in a metadata stream, as a squashfs
+    /// reference. Uncompressed blocks have a fixed on-disk size, which keeps
+    /// references computable before the stream is written out.
+    fn reference(&self, stream: &[u8]) -> (u64, u16) {
+        let pos = stream.len();
+        let block = (pos / METADATA_SIZE) as u64;
+        if self.compressed {
+            // Compressed streams are emitted as one block per 8 KiB chunk, but
+            // the compressed size is not known yet, so tests keep them small.
+            assert!(
+                block == 0,
+                "compressed test images must fit one metadata block"
+            );
+        }
+        (
+            block * (METADATA_SIZE as u64 + 2),
+            (pos % METADATA_SIZE) as u16,
+        )
+    }
+
+    fn write_dir(&mut self, children: &BTreeMap<String, Node>, parent: u32) -> (u64, u32) {
+        let number = self.next_inode;
+        self.next_inode += 1;
+
+        let mut listing = Vec::new();
+        for (name, node) in children {
+            let (inode_ref, inode_number, kind) = match node {
+                Node::Dir(grandchildren) => {
+                    let (r, n) = self.write_dir(grandchildren, number);
+                    (r, n, 1u16)
+                }
+                Node::File(data) => {
+                    let (r, n) = self.write_file(data);
+                    (r, n, 2u16)
+                }
+                Node::Symlink(target) => {
+                    let (r, n) = self.write_symlink(target);
+                    (r, n, 3u16)
+                }
+            };
+            listing.extend_from_slice(&0u32.to_le_bytes());
+            listing.extend_from_slice(&((inode_ref >> 16) as u32).to_le_bytes());
+            listing.extend_from_slice(&inode_number.to_le_bytes());
+            listing.extend_from_slice(&((inode_ref & 0xFFFF) as u16).to_le_bytes());
+            listing.extend_from_slice(&0i16.to_le_bytes());
+            listing.extend_from_slice(&kind.to_le_bytes());
+            listing.extend_from_slice(&((name.len() - 1) as u16).to_le_bytes());
+            listing.extend_from_slice(name.as_bytes());
+        }
+
+        let (dir_block, dir_offset) = self.reference(&self.directories);
+        self.directories.extend_from_slice(&listing);
+
+        let (block, offset) = self.reference(&self.inodes);
+        let mut inode = inode_header(1, number);
+        inode.extend_from_slice(&(dir_block as u32).to_le_bytes());
+        inode.extend_from_slice(&2u32.to_le_bytes());
+        inode.extend_from_slice(&((listing.len() + 3) as u16).to_le_bytes());
+        inode.extend_from_slice(&dir_offset.to_le_bytes());
+        inode.extend_from_slice(&parent.max(number).to_le_bytes());
+        self.inodes.extend_from_slice(&inode);
+
+        ((block << 16) | u64::from(offset), number)
+    }
+
+    fn write_file(&mut self, data: &[u8]) -> (u64, u32) {
+        let number = self.next_inode;
+        self.next_inode += 1;
+
+        let blocks_start = self.data.len() as u32;
+        let full_blocks = if self.use_fragments {
+            data.len() / BLOCK_SIZE
+        } else {
+            data.len().div_ceil(BLOCK_SIZE)
+        };
+
+        let mut sizes = Vec::new();
+        for chunk in data.chunks(BLOCK_SIZE).take(full_blocks) {
+            let (bytes, uncompressed) = self.compress(chunk);
+            let flag = if uncompressed { 1u32 << 24 } else { 0 };
+            sizes.push(bytes.len() as u32 | flag);
+            self.data.extend_from_slice(&bytes);
+        }
+
+        let (fragment, fragment_offset) = if full_blocks * BLOCK_SIZE < data.len() {
+            let tail = &data[full_blocks * BLOCK_SIZE..];
+            if self.fragment.len() + tail.len() > BLOCK_SIZE {
+                self.flush_fragment();
+            }
+            let offset = self.fragment.len() as u32;
+            self.fragment.extend_from_slice(tail);
+            (self.fragment_entries.len() as u32, offset)
+        } else {
+            (u32::MAX, 0)
+        };
+
+        let (block, offset) = self.reference(&self.inodes);
+        let mut inode = inode_header(2, number);
+        inode.extend_from_slice(&blocks_start.to_le_bytes());
+        inode.extend_from_slice(&fragment.to_le_bytes());
+        inode.extend_from_slice(&fragment_offset.to_le_bytes());
+        inode.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        for size in sizes {
+            inode.extend_from_slice(&size.to_le_bytes());
+        }
+        self.inodes.extend_from_slice(&inode);
+
+        ((block << 16) | u64::from(offset), number)
+    }
+
+    fn write_symlink(&mut self, target: &str) -> (u64, u32) {
+        let number = self.next_inode;
+        self.next_inode += 1;
+
+        let (block, offset) = self.reference(&self.inodes);
+        let mut inode = inode_header(3, number);
+        inode.extend_from_slice(&1u32.to_le_bytes());
+        inode.extend_from_slice(&(target.len() as u32).to_le_bytes());
+        inode.extend_from_slice(target.as_bytes());
+        self.inodes.extend_from_slice(&inode);
+
+        ((block << 16) | u64::from(offset), number)
+    }
+
+    fn flush_fragment(&mut self) {
+        if self.fragment.is_empty() {
+            return;
+        }
+        let fragment = std::mem::take(&mut self.fragment);
+        let (bytes, uncompressed) = self.compress(&fragment);
+        let flag = if uncompressed { 1u32 << 24 } else { 0 };
+        self.fragment_entries
+            .push((self.data.len() as u64, bytes.len() as u32 | flag));
+        self.data.extend_from_slice(&bytes);
+    }
+
+    fn compress(&self, chunk: &[u8]) -> (Vec<u8>, bool) {
+        if !self.compressed {
+            return (chunk.to_vec(), true);
+        }
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(chunk).unwrap();
+        let compressed = encoder.finish().unwrap();
+        if compressed.len() < chunk.len() {
+            (compressed, false)
+        } else {
+            (chunk.to_vec(), true)
+        }
+    }
+
+    fn metadata(&self, stream: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for chunk in stream.chunks(METADATA_SIZE) {
+            let (bytes, uncompressed) = if self.compressed {
+                self.compress(chunk)
+            } else {
+                (chunk.to_vec(), true)
+            };
+            let header = bytes.len() as u16 | if uncompressed { 0x8000 } else { 0 };
+            out.extend_from_slice(&header.to_le_bytes());
+            out.extend_from_slice(&bytes);
+        }
+        out
+    }
+}
+
+fn inode_header(kind: u16, number: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(16);
+    header.extend_from_slice(&kind.to_le_bytes());
+    header.extend_from_slice(&0o755u16.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&number.to_le_bytes());
+    header
+}
+
+/// Concatenates a runtime and a filesystem image the way appimagetool does.
+pub fn appimage(runtime: &ElfBuilder, payload: &SquashfsBuilder) -> Vec<u8> {
+    let mut image = runtime.build();
+    image.extend(payload.build());
+    image
+}
+
+/// A file that passes AppImage type detection but carries no real payload.
+/// `tag` is appended after the runtime so tests can tell images apart.
+pub fn fake_appimage(tag: &[u8]) -> Vec<u8> {
+    let mut image = ElfBuilder::new().build();
+    image.extend_from_slice(tag);
+    image
+}
+
+/// A config with every directory below `root`, the directories a scan
+/// expects already created, and auditing off.
+pub fn test_config(root: &Path) -> Config {
+    let mut config = Config::default();
+    config.directories.raw = root.join("raw").display().to_string();
+    config.directories.bin = root.join("bin").display().to_string();
+    config.directories.icons = root.join("icons").display().to_string();
+    config.directories.desktop = root.join("desktop").display().to_string();
+    config.directories.symlink = root.join("symlinks").display().to_string();
+    config.directories.quarantine = root.join("quarantine").display().to_string();
+    config.audit.enabled = false;
+    for dir in [
+        config.raw_dir(),
+        config.bin_dir(),
+        config.icon_dir(),
+        config.desktop_dir(),
+        config.symlink_dir(),
+    ] {
+        fs::create_dir_all(dir).unwrap();
+    }
+    config
+}
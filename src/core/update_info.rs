@@ -0,0 +1,248 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum UpdateInfoError {
+    #[error("Update information is empty")]
+    Empty,
+
+    #[error("Unknown update transport: {0}")]
+    UnknownTransport(String),
+
+    #[error("Malformed {transport} update information: expected {expected} fields, got {found}")]
+    FieldCount {
+        transport: String,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("Empty field in update information: {0}")]
+    EmptyField(String),
+}
+
+/// Where an AppImage says newer versions of itself can be found, as stored in
+/// its `.upd_info` section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateInformation {
+    /// `zsync|<url>`: a fixed URL to a `.zsync` file.
+    Zsync { url: String },
+
+    /// `gh-releases-zsync|<owner>|<repo>|<tag>|<filename>`. The tag may be
+    /// `latest` and the filename may contain `*` wildcards.
+    GitHubReleases {
+        owner: String,
+        repo: String,
+        tag: String,
+        filename: String,
+    },
+
+    /// `pling-v1-zsync|<product id>|<filename>`.
+    Pling {
+        product_id: String,
+        filename: String,
+    },
+}
+
+impl UpdateInformation {
+    /// Parses the raw contents of an update information section, which the
+    /// AppImage tooling pads with NUL bytes. Returns `None` for an empty
+    /// section.
+    pub fn from_section(data: &[u8]) -> Result<Option<Self>, UpdateInfoError> {
+        let text = section_text(data);
+        if text.is_empty() {
+            return Ok(None);
+        }
+        text.parse().map(Some)
+    }
+}
+
+impl FromStr for UpdateInformation {
+    type Err = UpdateInfoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(UpdateInfoError::Empty);
+        }
+
+        let (transport, rest) = s.split_once('|').unwrap_or((s, ""));
+        let fields = |expected: usize| -> Result<Vec<String>, UpdateInfoError> {
+            let fields: Vec<String> = rest.split('|').map(|f| f.trim().to_string()).collect();
+            if fields.len() != expected {
+                return Err(UpdateInfoError::FieldCount {
+                    transport: transport.to_string(),
+                    expected,
+                    found: fields.len(),
+                });
+            }
+            if fields.iter().any(|f| f.is_empty()) {
+                return Err(UpdateInfoError::EmptyField(s.to_string()));
+            }
+            Ok(fields)
+        };
+
+        match transport {
+            "zsync" => {
+                // URLs may legitimately contain '|', so everything after the
+                // transport is taken verbatim.
+                let url = rest.trim();
+                if url.is_empty() {
+                    return Err(UpdateInfoError::EmptyField(s.to_string()));
+                }
+                Ok(UpdateInformation::Zsync {
+                    url: url.to_string(),
+                })
+            }
+            "gh-releases-zsync" => {
+                let mut f = fields(4)?.into_iter();
+                Ok(UpdateInformation::GitHubReleases {
+                    owner: f.next().unwrap(),
+                    repo: f.next().unwrap(),
+                    tag: f.next().unwrap(),
+                    filename: f.next().unwrap(),
+                })
+            }
+            "pling-v1-zsync" => {
+                let mut f = fields(2)?.into_iter();
+                Ok(UpdateInformation::Pling {
+                    product_id: f.next().unwrap(),
+                    filename: f.next().unwrap(),
+                })
+            }
+            other => Err(UpdateInfoError::UnknownTransport(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for UpdateInformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateInformation::Zsync { url } => write!(f, "zsync|{}", url),
+            UpdateInformation::GitHubReleases {
+                owner,
+                repo,
+                tag,
+                filename,
+            } => write!(
+                f,
+                "gh-releases-zsync|{}|{}|{}|{}",
+                owner, repo, tag, filename
+            ),
+            UpdateInformation::Pling {
+                product_id,
+                filename,
+            } => write!(f, "pling-v1-zsync|{}|{}", product_id, filename),
+        }
+    }
+}
+
+/// Serialized in its embedded string form, e.g. `zsync|https://…`.
+impl Serialize for UpdateInformation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The detached signature and public key embedded by `appimagetool --sign`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedSignature {
+    /// ASCII-armored OpenPGP signature from `.sha256_sig`.
+    pub signature: String,
+    /// ASCII-armored public key from `.sig_key`, if the signer embedded one.
+    pub key: Option<String>,
+}
+
+impl EmbeddedSignature {
+    /// Builds the signature from the raw section contents. Unsigned images
+    /// still carry the sections, zero-filled, so empty contents mean `None`.
+    pub fn from_sections(signature: &[u8], key: Option<&[u8]>) -> Option<Self> {
+        let signature = section_text(signature);
+        if signature.is_empty() {
+            return None;
+        }
+        let key = key.map(section_text).filter(|k| !k.is_empty());
+        Some(EmbeddedSignature { signature, key })
+    }
+}
+
+fn section_text(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_zsync_url() {
+        let info: UpdateInformation = "zsync|https://example.com/App-latest.AppImage.zsync"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            info,
+            UpdateInformation::Zsync {
+                url: "https://example.com/App-latest.AppImage.zsync".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_github_releases_and_round_trips() {
+        let raw = "gh-releases-zsync|owner|repo|latest|App-*x86_64.AppImage.zsync";
+        let info: UpdateInformation = raw.parse().unwrap();
+        assert_eq!(
+            info,
+            UpdateInformation::GitHubReleases {
+                owner: "owner".to_string(),
+                repo: "repo".to_string(),
+                tag: "latest".to_string(),
+                filename: "App-*x86_64.AppImage.zsync".to_string(),
+            }
+        );
+        assert_eq!(info.to_string(), raw);
+    }
+
+    #[test]
+    fn rejects_malformed_information() {
+        assert_eq!(
+            "gh-releases-zsync|owner|repo".parse::<UpdateInformation>(),
+            Err(UpdateInfoError::FieldCount {
+                transport: "gh-releases-zsync".to_string(),
+                expected: 4,
+                found: 2,
+            })
+        );
+        assert!(matches!(
+            "bintray-zsync|a|b|c|d".parse::<UpdateInformation>(),
+            Err(UpdateInfoError::UnknownTransport(_))
+        ));
+        assert!(matches!(
+            "pling-v1-zsync||App.zsync".parse::<UpdateInformation>(),
+            Err(UpdateInfoError::EmptyField(_))
+        ));
+    }
+
+    #[test]
+    fn from_section_ignores_nul_padding() {
+        let mut section = b"zsync|https://example.com/a.zsync".to_vec();
+        section.resize(1024, 0);
+        assert!(UpdateInformation::from_section(&section).unwrap().is_some());
+        assert_eq!(UpdateInformation::from_section(&[0u8; 1024]), Ok(None));
+    }
+
+    #[test]
+    fn embedded_signature_is_none_when_zero_filled() {
+        assert!(EmbeddedSignature::from_sections(&[0u8; 1024], Some(&[0u8; 8192])).is_none());
+
+        let signature = EmbeddedSignature::from_sections(
+            b"-----BEGIN PGP SIGNATURE-----\0\0",
+            Some(&[0u8; 16]),
+        )
+        .unwrap();
+        assert_eq!(signature.signature, "-----BEGIN PGP SIGNATURE-----");
+        assert_eq!(signature.key, None);
+    }
+}
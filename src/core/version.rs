@@ -0,0 +1,232 @@
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+
+lazy_static::lazy_static! {
+    /// A file name part that is a version, like `1.2.3`, `v2`, `2024.03.1`
+    /// or `1.0rc2`.
+    static ref VERSION_PART: Regex = Regex::new(
+        r"(?i)^v?\d+(\.\d+)*(\.?(alpha|beta|rc|pre|preview|dev|nightly|snapshot|a|b)\.?\d*)?$"
+    ).unwrap();
+
+    /// A file name part that marks the version before it as a prerelease,
+    /// as in `App-2.0.0-rc2-x86_64`.
+    static ref PRERELEASE_PART: Regex = Regex::new(
+        r"(?i)^(alpha|beta|rc|pre|preview|dev|nightly|snapshot)(\.?\d+)?$"
+    ).unwrap();
+}
+
+/// A version string as found in AppImage file names: semver (`1.2.3`),
+/// date-based (`2024.03.1`) or suffixed (`2.0.0-beta`, `1.0rc2`).
+///
+/// Release numbers compare numerically, with missing components counting as
+/// zero, so `1.2` equals `1.2.0`. A prerelease sorts before its release, and
+/// prerelease parts compare like semver, with `rc2` before `rc10`. Strings
+/// that are not versions at all, such as `latest` or the timestamped names
+/// given to unversioned images, sort before every version and by text among
+/// themselves.
+#[derive(Debug, Clone)]
+pub struct Version {
+    raw: String,
+    release: Vec<u64>,
+    prerelease: Vec<Identifier>,
+}
+
+/// Numeric identifiers sort before alphanumeric ones, as in semver.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Identifier {
+    Numeric(u64),
+    Alpha(String),
+}
+
+impl Version {
+    pub fn parse(raw: &str) -> Self {
+        let unparsed = || Version {
+            raw: raw.to_string(),
+            release: Vec::new(),
+            prerelease: Vec::new(),
+        };
+
+        let s = raw.trim();
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        // Build metadata does not affect ordering.
+        let s = s.split('+').next().unwrap_or_default();
+        let end = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (release, suffix) = s.split_at(end);
+
+        let release: Option<Vec<u64>> = release
+            .strip_suffix('.')
+            .unwrap_or(release)
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect();
+        let Some(release) = release else {
+            return unparsed();
+        };
+
+        let mut prerelease = Vec::new();
+        for part in suffix.split(['.', '-', '_']).filter(|p| !p.is_empty()) {
+            // `rc2` is `rc` then 2, so that it sorts before `rc10`.
+            let mut rest = part;
+            while !rest.is_empty() {
+                let numeric = rest.starts_with(|c: char| c.is_ascii_digit());
+                let end = rest
+                    .find(|c: char| c.is_ascii_digit() != numeric)
+                    .unwrap_or(rest.len());
+                let (token, tail) = rest.split_at(end);
+                prerelease.push(match token.parse() {
+                    Ok(n) if numeric => Identifier::Numeric(n),
+                    _ => Identifier::Alpha(token.to_ascii_lowercase()),
+                });
+                rest = tail;
+            }
+        }
+
+        Version {
+            raw: raw.to_string(),
+            release,
+            prerelease,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// False for strings that are not versions, like `latest`.
+    pub fn is_parsed(&self) -> bool {
+        !self.release.is_empty()
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.prerelease.is_empty()
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_parsed(), other.is_parsed()) {
+            (false, false) => return self.raw.cmp(&other.raw),
+            (false, true) => return Ordering::Less,
+            (true, false) => return Ordering::Greater,
+            (true, true) => {}
+        }
+
+        let len = self.release.len().max(other.release.len());
+        for i in 0..len {
+            let a = self.release.get(i).copied().unwrap_or(0);
+            let b = other.release.get(i).copied().unwrap_or(0);
+            match a.cmp(&b) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+
+        match (self.is_prerelease(), other.is_prerelease()) {
+            (false, false) => Ordering::Equal,
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (true, true) => self.prerelease.cmp(&other.prerelease),
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// The version in an AppImage file name such as `App-1.2.3-x86_64` or
+/// `Tool-2.0.0-rc2`: the last dash-separated part after the name that looks
+/// like a version, together with a prerelease part right after it.
+pub fn version_from_file_name(stem: &str) -> Option<String> {
+    let (_, rest) = stem.split_once('-')?;
+    let parts: Vec<&str> = rest.split('-').collect();
+    let i = parts.iter().rposition(|part| VERSION_PART.is_match(part))?;
+    let version = parts[i].strip_prefix(['v', 'V']).unwrap_or(parts[i]);
+    match parts.get(i + 1) {
+        Some(tag) if PRERELEASE_PART.is_match(tag) => Some(format!("{}-{}", version, tag)),
+        _ => Some(version.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        Version::parse(s)
+    }
+
+    #[test]
+    fn orders_releases_numerically() {
+        assert!(v("1.10.0") > v("1.9.9"));
+        assert!(v("2024.03.1") < v("2024.10"));
+        assert_eq!(v("1.2"), v("1.2.0"));
+        assert_eq!(v("v1.2.3"), v("1.2.3"));
+        assert_eq!(v("1.2.3+build.5"), v("1.2.3"));
+    }
+
+    #[test]
+    fn orders_prereleases_before_release() {
+        let mut versions: Vec<Version> = [
+            "2.0.0", "2.0.0-rc10", "2.0.0-beta", "2.0.0-rc2", "1.9", "2.0.0-alpha.1", "2.0.0-alpha",
+        ]
+        .iter()
+        .map(|s| v(s))
+        .collect();
+        versions.sort();
+        let sorted: Vec<&str> = versions.iter().map(Version::as_str).collect();
+        assert_eq!(
+            sorted,
+            [
+                "1.9", "2.0.0-alpha", "2.0.0-alpha.1", "2.0.0-beta", "2.0.0-rc2", "2.0.0-rc10", "2.0.0"
+            ]
+        );
+        assert!(v("1.0rc2").is_prerelease());
+        assert_eq!(v("1.0rc2"), v("1.0-rc.2"));
+        assert!(!v("2024.03.1").is_prerelease());
+    }
+
+    #[test]
+    fn unversioned_strings_sort_first() {
+        assert!(!v("latest").is_parsed());
+        assert!(v("latest") < v("0.0.1"));
+        assert!(v("app-20240101000000") < v("app-20240201000000"));
+        assert!(!v("latest").is_prerelease());
+    }
+
+    #[test]
+    fn finds_version_in_file_name() {
+        let cases = [
+            ("App-1.2.3", Some("1.2.3")),
+            ("App-v2.1.0-x86_64", Some("2.1.0")),
+            ("Tool-2.0.0-rc2-x86_64", Some("2.0.0-rc2")),
+            ("Tool-1.0beta1", Some("1.0beta1")),
+            ("Cad-2024.03.1-linux", Some("2024.03.1")),
+            ("App-x86_64", None),
+            ("App", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(version_from_file_name(name).as_deref(), expected, "{}", name);
+        }
+    }
+}
@@ -1,10 +1,18 @@
+use chrono::{DateTime, Utc};
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use tracing::{info, warn};
 
+use crate::audit::{AuditEvent, AuditLog, AuditOperation, AuditOutcome};
 use crate::config::Config;
-use crate::core::{AppImage, AppImageError, AppMetadata, VersionInfo};
+use crate::core::journal::{Journal, Transaction, atomic_symlink, atomic_write};
+use crate::core::metadata::{DEFAULT_CHANNEL, MetadataError};
+use crate::core::files::{dir_size, list_dir};
+use crate::core::retention::{RetentionPolicy, Retained};
+use crate::core::{AppImage, AppImageError, AppMetadata, BlobStore, VersionInfo};
+use crate::lock::{Lock, LockError, LockGuard};
+use crate::security::SecurityReport;
 
 #[derive(Debug, Error)]
 pub enum VersionError {
@@ -28,15 +36,34 @@ pub enum VersionError {
 
     #[error("App not found: {0}")]
     AppNotFound(String),
+
+    #[error("{0} is pinned to version {1}; unpin it first")]
+    Pinned(String, String),
+
+    #[error("Invalid channel name: {0}")]
+    InvalidChannel(String),
+
+    #[error("Lock error: {0}")]
+    Lock(#[from] LockError),
 }
 
 pub struct VersionManager {
     config: Config,
+    audit: Option<AuditLog>,
 }
 
 impl VersionManager {
     pub fn new(config: Config) -> Self {
-        VersionManager { config }
+        VersionManager {
+            config,
+            audit: None,
+        }
+    }
+
+    /// Records installs, switches and removals in `audit`.
+    pub fn with_audit(mut self, audit: Option<AuditLog>) -> Self {
+        self.audit = audit;
+        self
     }
 
     pub fn get_app_dir(&self, app_name: &str) -> PathBuf {
@@ -74,6 +101,27 @@ impl VersionManager {
         Ok(AppMetadata::from_json(&content)?)
     }
 
+    pub fn get_security_report_path(&self, app_name: &str, version: &str) -> PathBuf {
+        self.get_version_dir(app_name, version).join("security.json")
+    }
+
+    /// Stores the security report of the image installed as `version`.
+    pub fn save_security_report(
+        &self,
+        app_name: &str,
+        version: &str,
+        report: &SecurityReport,
+    ) -> Result<(), VersionError> {
+        let json = serde_json::to_string_pretty(report).map_err(MetadataError::from)?;
+        atomic_write(&self.get_security_report_path(app_name, version), json.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load_security_report(&self, app_name: &str, version: &str) -> Option<SecurityReport> {
+        let content = fs::read_to_string(self.get_security_report_path(app_name, version)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
     pub fn save_app_metadata(&self, metadata: &AppMetadata) -> Result<(), VersionError> {
         let metadata_path = self.get_metadata_path(&metadata.name);
         let app_dir = metadata_path.parent().unwrap();
@@ -83,21 +131,58 @@ impl VersionManager {
         }
 
         let json = metadata.to_json()?;
-        fs::write(&metadata_path, json)?;
+        atomic_write(&metadata_path, json.as_bytes())?;
         Ok(())
     }
 
+    /// Loads the app's metadata for a change, first recovering from an
+    /// operation on the app that was interrupted.
+    fn load_for_update(&self, app_name: &str) -> Result<AppMetadata, VersionError> {
+        self.recover_unlocked(app_name)?;
+        self.load_app_metadata(app_name)
+    }
+
     pub fn install_version(
         &self,
         app_name: &str,
         version: &str,
         appimage_path: &Path,
+    ) -> Result<(), VersionError> {
+        self.install_update(app_name, version, appimage_path, None)
+    }
+
+    /// Installs `version` like [`install_version`](Self::install_version),
+    /// first linking the active image to `backup` if given. Both happen in
+    /// one transaction, so an interrupted update leaves no stray backup.
+    pub fn install_update(
+        &self,
+        app_name: &str,
+        version: &str,
+        appimage_path: &Path,
+        backup: Option<&Path>,
+    ) -> Result<(), VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        self.audited(
+            AuditOperation::Install,
+            app_name,
+            version,
+            Some(appimage_path),
+            || self.install_version_unaudited(app_name, version, appimage_path, backup),
+        )
+    }
+
+    fn install_version_unaudited(
+        &self,
+        app_name: &str,
+        version: &str,
+        appimage_path: &Path,
+        backup: Option<&Path>,
     ) -> Result<(), VersionError> {
         let app = AppImage::new(appimage_path.to_path_buf())?;
         let checksum = app.get_checksum()?;
 
         // Load or create app metadata
-        let mut metadata = match self.load_app_metadata(app_name) {
+        let metadata = match self.load_for_update(app_name) {
             Ok(m) => m,
             Err(VersionError::AppNotFound(_)) => {
                 // First version for this app
@@ -111,47 +196,83 @@ impl VersionManager {
             return Err(VersionError::VersionExists(version.to_string()));
         }
 
-        // Create version directory
+        // A new app's directory goes away entirely if the install fails
+        let app_dir = self.get_app_dir(app_name);
         let version_dir = self.get_version_dir(app_name, version);
-        fs::create_dir_all(&version_dir)?;
+        let mut created = if !app_dir.exists() {
+            vec![app_dir]
+        } else if !version_dir.exists() {
+            vec![version_dir.clone()]
+        } else {
+            Vec::new()
+        };
+        created.extend(backup.map(Path::to_path_buf));
 
-        // Copy AppImage
-        let target_path = self.get_appimage_path(app_name, version);
-        fs::copy(appimage_path, &target_path)?;
+        self.transact("install", metadata, created, Vec::new(), |metadata| {
+            if let Some(backup) = backup
+                && let Some(active) = metadata.get_active_version()
+            {
+                let active_path = self.get_appimage_path(app_name, &active.version);
+                if let Some(dir) = backup.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                self.blobs().store(&active_path, &active.checksum, backup)?;
+            }
 
-        // Make executable
-        self.make_executable(&target_path)?;
+            fs::create_dir_all(&version_dir)?;
 
-        // Add version to metadata
-        metadata.add_version(version.to_string(), checksum);
-        self.save_app_metadata(&metadata)?;
+            // Link the AppImage to its blob
+            let target_path = self.get_appimage_path(app_name, version);
+            self.blobs().store(appimage_path, &checksum, &target_path)?;
+            self.make_executable(&target_path)?;
 
-        // Update current symlink
-        self.update_current_link(app_name)?;
+            metadata.add_version(version.to_string(), checksum.clone());
+            Ok(())
+        })?;
 
         // Cleanup old versions
-        self.cleanup_old_versions(app_name)?;
+        self.cleanup_unlocked(app_name)?;
 
-        info!("Installed {} version {}", app_name, version);
+        info!(app = app_name, version, operation = "install", "Installed {} version {}", app_name, version);
         Ok(())
     }
 
     pub fn switch_version(&self, app_name: &str, version: &str) -> Result<(), VersionError> {
-        let mut metadata = self.load_app_metadata(app_name)?;
+        let _lock = self.lock_app(app_name)?;
+        self.audited(AuditOperation::Switch, app_name, version, None, || {
+            self.switch_version_unaudited(app_name, version)
+        })
+    }
+
+    fn switch_version_unaudited(&self, app_name: &str, version: &str) -> Result<(), VersionError> {
+        let metadata = self.load_for_update(app_name)?;
 
-        if !metadata.set_active_version(version) {
-            return Err(VersionError::VersionNotFound(version.to_string()));
+        if let Some(pinned) = &metadata.pinned_version
+            && pinned != version
+        {
+            return Err(VersionError::Pinned(app_name.to_string(), pinned.clone()));
         }
 
-        self.save_app_metadata(&metadata)?;
-        self.update_current_link(app_name)?;
+        self.transact("switch", metadata, Vec::new(), Vec::new(), |metadata| {
+            if !metadata.set_active_version(version) {
+                return Err(VersionError::VersionNotFound(version.to_string()));
+            }
+            Ok(())
+        })?;
 
-        info!("Switched {} to version {}", app_name, version);
+        info!(app = app_name, version, operation = "switch", "Switched {} to version {}", app_name, version);
         Ok(())
     }
 
     pub fn remove_version(&self, app_name: &str, version: &str) -> Result<(), VersionError> {
-        let mut metadata = self.load_app_metadata(app_name)?;
+        let _lock = self.lock_app(app_name)?;
+        self.audited(AuditOperation::Remove, app_name, version, None, || {
+            self.remove_version_unaudited(app_name, version)
+        })
+    }
+
+    fn remove_version_unaudited(&self, app_name: &str, version: &str) -> Result<(), VersionError> {
+        let metadata = self.load_for_update(app_name)?;
 
         if metadata.versions.len() <= 1 {
             return Err(VersionError::InvalidVersion(
@@ -165,23 +286,234 @@ impl VersionManager {
             ));
         }
 
-        // Remove version directory
+        // The version directory is only removed once the metadata no
+        // longer lists it
         let version_dir = self.get_version_dir(app_name, version);
-        if version_dir.exists() {
-            fs::remove_dir_all(&version_dir)?;
+        let checksum = metadata.get_version(version).map(|v| v.checksum.clone());
+        self.transact("remove", metadata, Vec::new(), vec![version_dir], |metadata| {
+            metadata.remove_version(version);
+            Ok(())
+        })?;
+        if let Some(checksum) = checksum {
+            self.release_blob(&checksum);
         }
 
-        // Remove from metadata
-        metadata.remove_version(version);
-        self.save_app_metadata(&metadata)?;
+        info!(app = app_name, version, operation = "remove", "Removed {} version {}", app_name, version);
+        Ok(())
+    }
+
+    /// Removes `version` even if it is active or its directory is gone,
+    /// activating the newest remaining version in its place. Repairs state
+    /// that [`remove_version`](Self::remove_version) refuses to touch.
+    pub fn discard_version(&self, app_name: &str, version: &str) -> Result<(), VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        let metadata = self.load_for_update(app_name)?;
+
+        if metadata.versions.len() <= 1 {
+            return Err(VersionError::InvalidVersion(
+                "Cannot remove the last version".to_string(),
+            ));
+        }
+
+        let version_dir = self.get_version_dir(app_name, version);
+        let checksum = metadata.get_version(version).map(|v| v.checksum.clone());
+        self.transact("discard", metadata, Vec::new(), vec![version_dir], |metadata| {
+            metadata.remove_version(version);
+            if metadata.pinned_version.as_deref() == Some(version) {
+                metadata.pinned_version = None;
+            }
+            if metadata.get_active_version().is_none() {
+                metadata.activate_newest();
+            }
+            Ok(())
+        })?;
+        if let Some(checksum) = checksum {
+            self.release_blob(&checksum);
+        }
+
+        info!(app = app_name, version, operation = "discard", "Discarded {} version {}", app_name, version);
+        Ok(())
+    }
 
-        info!("Removed {} version {}", app_name, version);
+    /// Lists a version directory the metadata does not know as an inactive
+    /// version, or removes the directory if it holds no image.
+    pub fn adopt_version(&self, app_name: &str, version: &str) -> Result<(), VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        let metadata = self.load_for_update(app_name)?;
+        if metadata.get_version(version).is_some() {
+            return Ok(());
+        }
+
+        let version_dir = self.get_version_dir(app_name, version);
+        let image = self.get_appimage_path(app_name, version);
+        if !image.is_file() {
+            return self.transact("adopt", metadata, Vec::new(), vec![version_dir], |_| Ok(()));
+        }
+
+        let checksum = AppImage::new(image.clone())?.get_checksum()?;
+        let installed_at: DateTime<Utc> = fs::metadata(&image)?.modified()?.into();
+        self.transact("adopt", metadata, Vec::new(), Vec::new(), |metadata| {
+            // Share the blob of identical images like installed versions do
+            self.blobs().store(&image, &checksum, &image)?;
+            metadata.versions.push(VersionInfo {
+                version: version.to_string(),
+                checksum: checksum.clone(),
+                installed_at,
+                is_active: false,
+            });
+            metadata.updated_at = Utc::now();
+            Ok(())
+        })?;
+
+        info!(app = app_name, version, operation = "adopt", "Adopted {} version {}", app_name, version);
         Ok(())
     }
 
+    /// Points `current` at the active version, first activating the newest
+    /// version if none is active.
+    pub fn repair_current_link(&self, app_name: &str) -> Result<(), VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        let metadata = self.load_for_update(app_name)?;
+        if metadata.get_active_version().is_some() {
+            return self.update_current_link(app_name);
+        }
+        self.transact("repair", metadata, Vec::new(), Vec::new(), |metadata| {
+            metadata.activate_newest();
+            Ok(())
+        })
+    }
+
+    /// Removes the app's versions and metadata, except the paths in `keep`,
+    /// and frees their blobs. Returns the removed paths.
+    pub fn remove_app(&self, app_name: &str, keep: &[PathBuf]) -> Result<Vec<PathBuf>, VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        let metadata = self.load_for_update(app_name)?;
+
+        let app_dir = self.get_app_dir(app_name);
+        let removed = if keep.is_empty() {
+            vec![app_dir]
+        } else {
+            list_dir(&app_dir, |path| !keep.iter().any(|kept| kept == path))?
+        };
+        // Removing the metadata commits the removal; the rest can follow
+        self.run_transaction("uninstall", app_name, Vec::new(), removed.clone(), || {
+            fs::remove_file(self.get_metadata_path(app_name))?;
+            Ok(())
+        })?;
+        for version in &metadata.versions {
+            self.release_blob(&version.checksum);
+        }
+
+        info!(app = app_name, operation = "uninstall", "Removed {}", app_name);
+        Ok(removed)
+    }
+
+    /// Holds the app at `version`, or at the active version if `None`,
+    /// switching to it first if needed. Returns the pinned version.
+    pub fn pin_version(&self, app_name: &str, version: Option<&str>) -> Result<String, VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => self
+                .get_current_version(app_name)?
+                .ok_or_else(|| VersionError::VersionNotFound("no active version".to_string()))?,
+        };
+        self.audited(AuditOperation::Pin, app_name, &version, None, || {
+            self.pin_version_unaudited(app_name, &version)
+        })?;
+        Ok(version)
+    }
+
+    fn pin_version_unaudited(&self, app_name: &str, version: &str) -> Result<(), VersionError> {
+        let metadata = self.load_for_update(app_name)?;
+
+        self.transact("pin", metadata, Vec::new(), Vec::new(), |metadata| {
+            if metadata.get_active_version().map(|v| v.version.as_str()) != Some(version)
+                && !metadata.set_active_version(version)
+            {
+                return Err(VersionError::VersionNotFound(version.to_string()));
+            }
+            metadata.pinned_version = Some(version.to_string());
+            Ok(())
+        })?;
+
+        info!(app = app_name, version, operation = "pin", "Pinned {} to version {}", app_name, version);
+        Ok(())
+    }
+
+    /// Releases the pin and returns the version the app was pinned to.
+    pub fn unpin_version(&self, app_name: &str) -> Result<Option<String>, VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        let metadata = self.load_for_update(app_name)?;
+        let Some(pinned) = metadata.pinned_version.clone() else {
+            return Ok(None);
+        };
+        self.audited(AuditOperation::Unpin, app_name, &pinned, None, || {
+            self.transact("unpin", metadata, Vec::new(), Vec::new(), |metadata| {
+                metadata.pinned_version = None;
+                Ok(())
+            })
+        })?;
+
+        info!(app = app_name, version = %pinned, operation = "unpin", "Unpinned {} from version {}", app_name, pinned);
+        Ok(Some(pinned))
+    }
+
+    pub fn pinned_version(&self, app_name: &str) -> Option<String> {
+        self.load_app_metadata(app_name).ok()?.pinned_version
+    }
+
+    /// Makes updates of the app follow `channel`, or the release named by
+    /// its update information if `None`. Returns the previous channel.
+    pub fn set_channel(
+        &self,
+        app_name: &str,
+        channel: Option<&str>,
+    ) -> Result<Option<String>, VersionError> {
+        let channel = channel.map(str::to_ascii_lowercase);
+        if let Some(name) = &channel
+            && (name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        {
+            return Err(VersionError::InvalidChannel(name.clone()));
+        }
+
+        let _lock = self.lock_app(app_name)?;
+        let metadata = self.load_for_update(app_name)?;
+        let previous = metadata.channel.clone();
+        let result = self.transact("channel", metadata, Vec::new(), Vec::new(), |metadata| {
+            metadata.channel = channel.clone();
+            Ok(())
+        });
+
+        if let Some(audit) = &self.audit {
+            let change = format!(
+                "{} -> {}",
+                previous.as_deref().unwrap_or(DEFAULT_CHANNEL),
+                channel.as_deref().unwrap_or(DEFAULT_CHANNEL)
+            );
+            let event = AuditEvent::new(AuditOperation::Channel).with_app(app_name);
+            audit.record(match &result {
+                Ok(()) => event.with_outcome(AuditOutcome::Success, Some(change)),
+                Err(_) => event.with_result(&result),
+            });
+        }
+        result?;
+
+        info!(app = app_name, channel = ?channel, operation = "channel", "Set update channel of {} to {}", app_name, channel.as_deref().unwrap_or(DEFAULT_CHANNEL));
+        Ok(previous)
+    }
+
+    pub fn channel(&self, app_name: &str) -> Option<String> {
+        self.load_app_metadata(app_name).ok()?.channel
+    }
+
+    /// Installed versions, oldest first.
     pub fn list_versions(&self, app_name: &str) -> Result<Vec<VersionInfo>, VersionError> {
         let metadata = self.load_app_metadata(app_name)?;
-        Ok(metadata.versions.clone())
+        Ok(metadata.sorted_versions().into_iter().cloned().collect())
     }
 
     pub fn get_current_version(&self, app_name: &str) -> Result<Option<String>, VersionError> {
@@ -210,43 +542,59 @@ impl VersionManager {
         Ok(apps)
     }
 
-    pub fn cleanup_old_versions(&self, app_name: &str) -> Result<(), VersionError> {
+    /// Removes the versions the retention policy does not keep, leaving
+    /// update backups and the global size cap to `appiman gc`.
+    fn cleanup_unlocked(&self, app_name: &str) -> Result<(), VersionError> {
         if !self.config.versions.auto_cleanup_enabled {
             return Ok(());
         }
 
-        let mut metadata = self.load_app_metadata(app_name)?;
-        let max_versions = self.config.versions.max_versions_per_app;
-
-        if metadata.versions.len() <= max_versions {
+        let metadata = self.load_for_update(app_name)?;
+        let verdicts = RetentionPolicy::from_config(&self.config).plan_app(
+            &metadata,
+            |version| dir_size(&self.get_version_dir(app_name, version)).unwrap_or(0),
+            &[],
+            Utc::now(),
+        );
+
+        let removed: Vec<String> = verdicts
+            .into_iter()
+            .filter(|v| v.keep.is_none())
+            .filter_map(|v| match v.item {
+                Retained::Version(version) => Some(version),
+                Retained::Backup(_) => None,
+            })
+            .collect();
+        if removed.is_empty() {
             return Ok(());
         }
 
-        // Sort versions by installation date, keep newest
-        metadata
-            .versions
-            .sort_by(|a, b| b.installed_at.cmp(&a.installed_at));
-
-        // Remove old versions
-        let to_remove: Vec<String> = metadata
-            .versions
-            .iter()
-            .skip(max_versions)
-            .map(|v| v.version.clone())
-            .collect();
-
-        for version in to_remove {
-            if metadata.get_active_version().map(|v| v.version.as_str()) != Some(&version) {
-                warn!("Removing old version {} of {}", version, app_name);
-                let version_dir = self.get_version_dir(app_name, &version);
-                if version_dir.exists() {
-                    fs::remove_dir_all(&version_dir)?;
-                }
-                metadata.remove_version(&version);
+        let mut released = Vec::new();
+        let mut obsolete = Vec::new();
+        for version in &removed {
+            warn!(
+                app = app_name,
+                version = %version,
+                operation = "remove",
+                "Removing old version {} of {}",
+                version,
+                app_name
+            );
+            obsolete.push(self.get_version_dir(app_name, version));
+            if let Some(v) = metadata.get_version(version) {
+                released.push(v.checksum.clone());
             }
         }
 
-        self.save_app_metadata(&metadata)?;
+        self.transact("cleanup", metadata, Vec::new(), obsolete, |metadata| {
+            for version in &removed {
+                metadata.remove_version(version);
+            }
+            Ok(())
+        })?;
+        for checksum in released {
+            self.release_blob(&checksum);
+        }
         Ok(())
     }
 
@@ -301,23 +649,208 @@ impl VersionManager {
         Ok(())
     }
 
-    fn update_current_link(&self, app_name: &str) -> Result<(), VersionError> {
+    /// Checksum of the active version, if the app has one.
+    pub fn active_checksum(&self, app_name: &str) -> Option<String> {
+        self.load_app_metadata(app_name)
+            .ok()?
+            .get_active_version()
+            .map(|v| v.checksum.clone())
+    }
+
+    /// Runs `operation` and records it with the active checksum before and
+    /// after.
+    fn audited(
+        &self,
+        operation: AuditOperation,
+        app_name: &str,
+        version: &str,
+        path: Option<&Path>,
+        run: impl FnOnce() -> Result<(), VersionError>,
+    ) -> Result<(), VersionError> {
+        let Some(audit) = &self.audit else {
+            return run();
+        };
+
+        let before = self.active_checksum(app_name);
+        let result = run();
+        let mut event = AuditEvent::new(operation)
+            .with_app(app_name)
+            .with_version(version)
+            .with_checksums(before, self.active_checksum(app_name))
+            .with_result(&result);
+        if let Some(path) = path {
+            event = event.with_path(path);
+        }
+        audit.record(event);
+        result
+    }
+
+    /// Points the `current` link at the active version.
+    pub fn update_current_link(&self, app_name: &str) -> Result<(), VersionError> {
         let metadata = self.load_app_metadata(app_name)?;
         if let Some(active_version) = metadata.get_active_version() {
             let current_link = self.get_current_link(app_name);
             let version_dir = self.get_version_dir(app_name, &active_version.version);
+            atomic_symlink(&version_dir, &current_link)?;
+        }
+        Ok(())
+    }
+
+    /// Keeps other processes and threads from changing the app until the
+    /// guard is dropped.
+    fn lock_app(&self, app_name: &str) -> Result<LockGuard, VersionError> {
+        Ok(Lock::app(&self.config, app_name).acquire()?)
+    }
+
+    pub fn journal(&self) -> Journal {
+        Journal::new(self.config.bin_dir().join(".journal"))
+    }
+
+    /// Applies `change` to `metadata` as one transaction, see [`Transaction`].
+    /// `change` may create the paths in `created`, which are removed if it
+    /// fails; the paths in `obsolete` are removed once the new metadata is
+    /// saved.
+    fn transact(
+        &self,
+        operation: &str,
+        mut metadata: AppMetadata,
+        created: Vec<PathBuf>,
+        obsolete: Vec<PathBuf>,
+        change: impl FnOnce(&mut AppMetadata) -> Result<(), VersionError>,
+    ) -> Result<(), VersionError> {
+        let app_name = metadata.name.clone();
+        self.run_transaction(operation, &app_name, created, obsolete, || {
+            change(&mut metadata)?;
+            self.save_app_metadata(&metadata)
+        })
+    }
+
+    /// Journals `commit`, rolling back if it fails and removing `obsolete`
+    /// once it succeeds.
+    fn run_transaction(
+        &self,
+        operation: &str,
+        app_name: &str,
+        created: Vec<PathBuf>,
+        obsolete: Vec<PathBuf>,
+        commit: impl FnOnce() -> Result<(), VersionError>,
+    ) -> Result<(), VersionError> {
+        let metadata_before = match fs::read_to_string(self.get_metadata_path(app_name)) {
+            Ok(json) => Some(json),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let mut transaction = Transaction {
+            operation: operation.to_string(),
+            app: app_name.to_string(),
+            started_at: Utc::now(),
+            metadata_before,
+            created,
+            obsolete,
+            committed: false,
+        };
+        let journal = self.journal();
+        journal.write(&transaction)?;
 
-            // Remove existing link
-            if current_link.exists() {
-                fs::remove_file(&current_link)?;
+        if let Err(e) = commit() {
+            if let Err(rollback) = self.roll_back(&transaction) {
+                warn!("Failed to roll back {} of {}: {}", operation, app_name, rollback);
             }
+            return Err(e);
+        }
+
+        transaction.committed = true;
+        journal.write(&transaction)?;
+        self.roll_forward(&transaction)
+    }
+
+    /// Undoes an uncommitted transaction.
+    fn roll_back(&self, transaction: &Transaction) -> Result<(), VersionError> {
+        for path in &transaction.created {
+            remove_path(path)?;
+        }
+        let metadata_path = self.get_metadata_path(&transaction.app);
+        match &transaction.metadata_before {
+            Some(json) => {
+                fs::create_dir_all(self.get_app_dir(&transaction.app))?;
+                atomic_write(&metadata_path, json.as_bytes())?;
+            }
+            None => remove_path(&metadata_path)?,
+        }
+        self.finish(transaction)
+    }
+
+    /// Completes a committed transaction.
+    fn roll_forward(&self, transaction: &Transaction) -> Result<(), VersionError> {
+        for path in &transaction.obsolete {
+            remove_path(path)?;
+        }
+        self.finish(transaction)
+    }
 
-            // Create new symlink
-            std::os::unix::fs::symlink(&version_dir, &current_link)?;
+    fn finish(&self, transaction: &Transaction) -> Result<(), VersionError> {
+        if self.get_metadata_path(&transaction.app).exists() {
+            self.update_current_link(&transaction.app)?;
         }
+        self.journal().finish(&transaction.app)?;
         Ok(())
     }
 
+    /// Rolls an operation on the app that was interrupted back or forward,
+    /// depending on whether it got to save the new metadata. Returns the
+    /// recovered transaction.
+    pub fn recover_app(&self, app_name: &str) -> Result<Option<Transaction>, VersionError> {
+        let _lock = self.lock_app(app_name)?;
+        self.recover_unlocked(app_name)
+    }
+
+    fn recover_unlocked(&self, app_name: &str) -> Result<Option<Transaction>, VersionError> {
+        let Some(transaction) = self.journal().pending(app_name)? else {
+            return Ok(None);
+        };
+        if transaction.committed {
+            warn!(
+                app = app_name,
+                operation = %transaction.operation,
+                "Completing interrupted {} of {}",
+                transaction.operation,
+                app_name
+            );
+            self.roll_forward(&transaction)?;
+        } else {
+            warn!(
+                app = app_name,
+                operation = %transaction.operation,
+                "Rolling back interrupted {} of {}",
+                transaction.operation,
+                app_name
+            );
+            self.roll_back(&transaction)?;
+        }
+        Ok(Some(transaction))
+    }
+
+    /// Recovers every app with an interrupted operation.
+    pub fn recover(&self) -> Result<Vec<Transaction>, VersionError> {
+        let mut recovered = Vec::new();
+        for app in self.journal().pending_apps()? {
+            recovered.extend(self.recover_app(&app)?);
+        }
+        Ok(recovered)
+    }
+
+    pub fn blobs(&self) -> BlobStore {
+        BlobStore::from_config(&self.config)
+    }
+
+    /// Frees the blob for `checksum` if no version or backup uses it any
+    /// more. Failing to is not an error; `appiman gc` prunes leftovers.
+    fn release_blob(&self, checksum: &str) {
+        if let Err(e) = self.blobs().release(checksum) {
+            warn!("Failed to release blob {}: {}", checksum, e);
+        }
+    }
+
     fn make_executable(&self, path: &Path) -> Result<(), VersionError> {
         #[cfg(unix)]
         {
@@ -329,3 +862,16 @@ impl VersionManager {
         Ok(())
     }
 }
+
+/// Removes a file, link or directory tree; a missing path is not an error.
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    let result = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) => Err(e),
+    };
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
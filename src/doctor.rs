@@ -0,0 +1,815 @@
+// src/doctor.rs
+
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+
+use crate::audit::{AuditEvent, AuditLog, AuditOperation, AuditOutcome};
+use crate::cli::say;
+use crate::config::{Config, ConfigError};
+use crate::core::cache::CacheError;
+use crate::core::files::{list_dir, sha256_file};
+use crate::core::journal::atomic_symlink;
+use crate::core::{MetadataCache, VersionError, VersionManager};
+use crate::output::{Outcome, Report};
+use crate::policy::PolicyAction;
+use crate::privileges::require_root;
+use crate::quarantine::{Quarantine, QuarantineDetails, QuarantineError};
+
+#[derive(Debug, Error)]
+pub enum DoctorError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Config error: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error("Version error: {0}")]
+    Version(#[from] VersionError),
+
+    #[error("Cache error: {0}")]
+    Cache(#[from] CacheError),
+
+    #[error("Quarantine error: {0}")]
+    Quarantine(#[from] QuarantineError),
+
+    #[error("{0}")]
+    Unfixable(&'static str),
+}
+
+/// A disagreement between the pieces of registration state.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// An install, switch or removal that did not run to completion.
+    InterruptedTransaction {
+        app: String,
+        operation: String,
+        path: PathBuf,
+    },
+    /// `current` is missing, dangling or not pointing at the active version.
+    DanglingCurrentLink {
+        app: String,
+        path: PathBuf,
+    },
+    /// `metadata.json` lists a version whose image is gone.
+    MissingVersionDir {
+        app: String,
+        version: String,
+        path: PathBuf,
+    },
+    /// A version directory `metadata.json` does not list.
+    OrphanVersionDir {
+        app: String,
+        version: String,
+        path: PathBuf,
+    },
+    ChecksumDrift {
+        app: String,
+        version: String,
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    /// A desktop entry appiman wrote whose `Exec` does not exist.
+    BrokenDesktopEntry {
+        path: PathBuf,
+        exec: PathBuf,
+    },
+    /// A link into the bin dir whose target does not exist.
+    BrokenSymlink {
+        path: PathBuf,
+        target: PathBuf,
+    },
+    OrphanIcon {
+        path: PathBuf,
+    },
+    /// A cache entry for an image that is gone or an app that is not
+    /// registered.
+    StaleCacheEntry {
+        path: PathBuf,
+        app: String,
+    },
+}
+
+impl Problem {
+    fn app(&self) -> Option<&str> {
+        match self {
+            Problem::InterruptedTransaction { app, .. }
+            | Problem::DanglingCurrentLink { app, .. }
+            | Problem::MissingVersionDir { app, .. }
+            | Problem::OrphanVersionDir { app, .. }
+            | Problem::ChecksumDrift { app, .. }
+            | Problem::StaleCacheEntry { app, .. } => Some(app),
+            _ => None,
+        }
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            Problem::InterruptedTransaction { path, .. }
+            | Problem::DanglingCurrentLink { path, .. }
+            | Problem::MissingVersionDir { path, .. }
+            | Problem::OrphanVersionDir { path, .. }
+            | Problem::ChecksumDrift { path, .. }
+            | Problem::BrokenDesktopEntry { path, .. }
+            | Problem::BrokenSymlink { path, .. }
+            | Problem::OrphanIcon { path }
+            | Problem::StaleCacheEntry { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::InterruptedTransaction { app, operation, .. } => {
+                write!(f, "{}: {} was interrupted", app, operation)
+            }
+            Problem::DanglingCurrentLink { app, path } => write!(
+                f,
+                "{}: {} does not point at the active version",
+                app,
+                path.display()
+            ),
+            Problem::MissingVersionDir { app, version, path } => write!(
+                f,
+                "{}: version {} is missing from {}",
+                app,
+                version,
+                path.display()
+            ),
+            Problem::OrphanVersionDir { app, path, .. } => {
+                write!(f, "{}: {} is not in metadata.json", app, path.display())
+            }
+            Problem::ChecksumDrift {
+                app,
+                version,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "{}: version {} has checksum {}, expected {}",
+                app, version, actual, expected
+            ),
+            Problem::BrokenDesktopEntry { path, exec } => write!(
+                f,
+                "{}: Exec {} does not exist",
+                path.display(),
+                exec.display()
+            ),
+            Problem::BrokenSymlink { path, target } => write!(
+                f,
+                "{}: target {} does not exist",
+                path.display(),
+                target.display()
+            ),
+            Problem::OrphanIcon { path } => {
+                write!(f, "{}: icon of no registered app", path.display())
+            }
+            Problem::StaleCacheEntry { path, app } => {
+                write!(f, "{}: stale cache entry for {}", path.display(), app)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    #[serde(flatten)]
+    pub problem: Problem,
+    pub fixed: bool,
+    pub error: Option<String>,
+}
+
+/// Result of `doctor`.
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub apps_checked: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl Report for DoctorReport {
+    fn outcome(&self) -> Outcome {
+        if self.findings.iter().all(|f| f.fixed) {
+            Outcome::Success
+        } else {
+            Outcome::PartialFailure
+        }
+    }
+}
+
+/// Checks that versions, `current` links, metadata, desktop entries, icons,
+/// symlinks and the metadata cache agree, and repairs them.
+pub struct Doctor {
+    config: Config,
+    version_manager: VersionManager,
+    quarantine: Quarantine,
+    audit: Option<AuditLog>,
+    dry_run: bool,
+}
+
+impl Doctor {
+    pub fn new(config: Config) -> Self {
+        Doctor {
+            version_manager: VersionManager::new(config.clone()),
+            quarantine: Quarantine::new(config.quarantine_dir()),
+            config,
+            audit: None,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_audit(mut self, audit: Option<AuditLog>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Checks everything and, with `fix`, repairs what it finds.
+    pub fn diagnose(&self, fix: bool) -> Result<DoctorReport, DoctorError> {
+        let apps_checked = self.version_manager.list_apps()?.len();
+        let mut findings: Vec<Finding> = Vec::new();
+
+        if !fix || self.dry_run {
+            findings = self
+                .check()?
+                .into_iter()
+                .map(|problem| Finding {
+                    problem,
+                    fixed: false,
+                    error: None,
+                })
+                .collect();
+            return Ok(DoctorReport {
+                apps_checked,
+                findings,
+            });
+        }
+
+        // A repair can uncover further problems, such as a symlink left
+        // pointing at a dropped version, so check again until nothing new
+        // turns up.
+        loop {
+            let problems = self.check()?;
+            let new: Vec<Problem> = problems
+                .iter()
+                .filter(|p| !findings.iter().any(|f| &f.problem == *p))
+                .cloned()
+                .collect();
+            if new.is_empty() {
+                for finding in &mut findings {
+                    if finding.fixed && problems.contains(&finding.problem) {
+                        finding.fixed = false;
+                        finding.error = Some("Still present after repair".to_string());
+                    }
+                }
+                break;
+            }
+
+            for problem in new {
+                let result = self.fix(&problem);
+                self.record(&problem, &result);
+                findings.push(Finding {
+                    fixed: result.is_ok(),
+                    error: result.err().map(|e| e.to_string()),
+                    problem,
+                });
+            }
+        }
+
+        Ok(DoctorReport {
+            apps_checked,
+            findings,
+        })
+    }
+
+    /// Every problem found, app state first.
+    pub fn check(&self) -> Result<Vec<Problem>, DoctorError> {
+        let mut apps = self.version_manager.list_apps()?;
+        apps.sort();
+
+        let mut problems = Vec::new();
+        let journal = self.version_manager.journal();
+        for app in journal.pending_apps()? {
+            if let Some(transaction) = journal.pending(&app)? {
+                problems.push(Problem::InterruptedTransaction {
+                    path: journal.path(&app),
+                    app,
+                    operation: transaction.operation,
+                });
+            }
+        }
+        for app in &apps {
+            // The state of an app is in flux until its transaction is
+            // recovered.
+            if problems.iter().any(|p| p.app() == Some(app.as_str())) {
+                continue;
+            }
+            self.check_app(app, &mut problems)?;
+        }
+        self.check_symlinks(&mut problems)?;
+        self.check_desktop_entries(&mut problems)?;
+        self.check_icons(&apps, &mut problems)?;
+        self.check_cache(&apps, &mut problems);
+        Ok(problems)
+    }
+
+    fn check_app(&self, app: &str, problems: &mut Vec<Problem>) -> Result<(), DoctorError> {
+        let metadata = self.version_manager.load_app_metadata(app)?;
+
+        for version in &metadata.versions {
+            let image = self
+                .version_manager
+                .get_appimage_path(app, &version.version);
+            if !image.is_file() {
+                problems.push(Problem::MissingVersionDir {
+                    app: app.to_string(),
+                    version: version.version.clone(),
+                    path: self.version_manager.get_version_dir(app, &version.version),
+                });
+                continue;
+            }
+            let actual = sha256_file(&image)?;
+            if actual != version.checksum {
+                problems.push(Problem::ChecksumDrift {
+                    app: app.to_string(),
+                    version: version.version.clone(),
+                    path: image,
+                    expected: version.checksum.clone(),
+                    actual,
+                });
+            }
+        }
+
+        for path in list_dir(&self.version_manager.get_versions_dir(app), |_| true)? {
+            let version = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            if metadata.get_version(&version).is_none() {
+                problems.push(Problem::OrphanVersionDir {
+                    app: app.to_string(),
+                    version,
+                    path,
+                });
+            }
+        }
+
+        if !metadata.versions.is_empty() {
+            let link = self.version_manager.get_current_link(app);
+            let expected = metadata
+                .get_active_version()
+                .map(|v| self.version_manager.get_version_dir(app, &v.version));
+            let ok = expected.is_some_and(|expected| {
+                fs::read_link(&link).is_ok_and(|target| target == expected) && expected.is_dir()
+            });
+            if !ok {
+                problems.push(Problem::DanglingCurrentLink {
+                    app: app.to_string(),
+                    path: link,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_symlinks(&self, problems: &mut Vec<Problem>) -> Result<(), DoctorError> {
+        let bin_dir = self.config.bin_dir();
+        for path in list_dir(&self.config.symlink_dir(), |_| true)? {
+            // The symlink dir is shared, so only links into the bin dir are ours.
+            if let Ok(target) = fs::read_link(&path)
+                && target.starts_with(&bin_dir)
+                && !path.exists()
+            {
+                problems.push(Problem::BrokenSymlink { path, target });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_desktop_entries(&self, problems: &mut Vec<Problem>) -> Result<(), DoctorError> {
+        let bin_dir = self.config.bin_dir();
+        let symlink_dir = self.config.symlink_dir();
+        for path in list_dir(&self.config.desktop_dir(), |_| true)? {
+            if path.extension().is_none_or(|ext| ext != "desktop") {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let Some(exec) = desktop_exec(&content) else {
+                continue;
+            };
+            // The desktop dir is shared too; entries appiman wrote run the
+            // app's symlink or an image in the bin dir.
+            let ours = exec.starts_with(&bin_dir)
+                || path
+                    .file_stem()
+                    .is_some_and(|stem| exec == symlink_dir.join(stem));
+            if ours && !exec.exists() {
+                problems.push(Problem::BrokenDesktopEntry { path, exec });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_icons(&self, apps: &[String], problems: &mut Vec<Problem>) -> Result<(), DoctorError> {
+        for path in list_dir(&self.config.icon_dir(), |_| true)? {
+            let registered = path
+                .file_stem()
+                .is_some_and(|stem| apps.iter().any(|app| stem == app.as_str()));
+            if path.is_file() && !registered {
+                problems.push(Problem::OrphanIcon { path });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_cache(&self, apps: &[String], problems: &mut Vec<Problem>) {
+        let cache = MetadataCache::new(&self.cache_dir());
+        let mut stale: Vec<Problem> = cache
+            .entries()
+            .filter(|(path, entry)| !path.exists() || !apps.contains(&entry.normalized_name))
+            .map(|(path, entry)| Problem::StaleCacheEntry {
+                path: path.to_path_buf(),
+                app: entry.normalized_name.clone(),
+            })
+            .collect();
+        stale.sort_by(|a, b| a.path().cmp(b.path()));
+        problems.extend(stale);
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.config.raw_dir().join(".cache")
+    }
+
+    fn fix(&self, problem: &Problem) -> Result<(), DoctorError> {
+        match problem {
+            Problem::InterruptedTransaction { app, .. } => {
+                self.version_manager.recover_app(app)?;
+            }
+            Problem::DanglingCurrentLink { app, .. } => {
+                self.version_manager.repair_current_link(app)?;
+            }
+            Problem::MissingVersionDir { app, version, .. } => self.drop_version(app, version)?,
+            Problem::OrphanVersionDir { app, version, .. } => {
+                self.version_manager.adopt_version(app, version)?
+            }
+            Problem::ChecksumDrift {
+                app,
+                version,
+                path,
+                expected,
+                actual,
+            } => {
+                // Check first, so the only copy is not quarantined for nothing.
+                if self.version_manager.list_versions(app)?.len() <= 1 {
+                    return Err(last_version());
+                }
+                let reason = format!("Checksum changed after install, expected {}", expected);
+                self.quarantine.add(
+                    path,
+                    QuarantineDetails {
+                        action: PolicyAction::Quarantine,
+                        reason: &reason,
+                        sha256: actual,
+                        user: None,
+                        security_report: None,
+                    },
+                )?;
+                self.drop_version(app, version)?;
+            }
+            Problem::BrokenDesktopEntry { path, .. } => self.repair_desktop_entry(path)?,
+            Problem::BrokenSymlink { path, .. } => {
+                let app = path.file_name().unwrap_or_default().to_string_lossy();
+                match self.active_image(&app) {
+                    Some(image) => atomic_symlink(&image, path)?,
+                    None => fs::remove_file(path)?,
+                }
+            }
+            Problem::OrphanIcon { path } => fs::remove_file(path)?,
+            Problem::StaleCacheEntry { path, .. } => {
+                let mut cache = MetadataCache::new(&self.cache_dir());
+                cache.remove_entry(path);
+                cache.save()?;
+            }
+        }
+        info!(operation = "repair", "Repaired {}", problem);
+        Ok(())
+    }
+
+    /// Removes `version` from the app, activating the newest remaining
+    /// version if it was the active one.
+    fn drop_version(&self, app: &str, version: &str) -> Result<(), DoctorError> {
+        if self.version_manager.list_versions(app)?.len() <= 1 {
+            return Err(last_version());
+        }
+        self.version_manager.discard_version(app, version)?;
+        Ok(())
+    }
+
+    /// Points the entry back at the app's symlink, recreating the symlink if
+    /// needed, or removes it if the app is gone.
+    fn repair_desktop_entry(&self, path: &Path) -> Result<(), DoctorError> {
+        let app = path.file_stem().unwrap_or_default().to_string_lossy();
+        let Some(image) = self.active_image(&app) else {
+            fs::remove_file(path)?;
+            return Ok(());
+        };
+
+        let link = self.config.symlink_dir().join(app.as_ref());
+        if !link.exists() {
+            atomic_symlink(&image, &link)?;
+        }
+        let content = fs::read_to_string(path)?;
+        fs::write(path, with_desktop_exec(&content, &link))?;
+        Ok(())
+    }
+
+    /// Image of the app's active version, if it exists.
+    fn active_image(&self, app: &str) -> Option<PathBuf> {
+        let metadata = self.version_manager.load_app_metadata(app).ok()?;
+        let active = metadata.get_active_version()?;
+        let image = self.version_manager.get_appimage_path(app, &active.version);
+        image.is_file().then_some(image)
+    }
+
+    fn record(&self, problem: &Problem, result: &Result<(), DoctorError>) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let (outcome, detail) = match result {
+            Ok(()) => (AuditOutcome::Success, problem.to_string()),
+            Err(e) => (AuditOutcome::Failure, format!("{}: {}", problem, e)),
+        };
+        let mut event = AuditEvent::new(AuditOperation::Repair)
+            .with_path(problem.path())
+            .with_outcome(outcome, Some(detail));
+        if let Some(app) = problem.app() {
+            event = event.with_app(app);
+        }
+        audit.record(event);
+    }
+}
+
+fn last_version() -> DoctorError {
+    DoctorError::Unfixable("It is the only version; reinstall or uninstall the app")
+}
+
+/// Program `Exec` of the main group runs, without arguments.
+fn desktop_exec(content: &str) -> Option<PathBuf> {
+    let mut in_main_group = true;
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+        } else if in_main_group && let Some(exec) = line.strip_prefix("Exec=") {
+            return exec.split_whitespace().next().map(PathBuf::from);
+        }
+    }
+    None
+}
+
+fn with_desktop_exec(content: &str, exec: &Path) -> String {
+    let mut in_main_group = true;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        if line.starts_with('[') {
+            in_main_group = line.trim_end() == "[Desktop Entry]";
+        }
+        if in_main_group && line.starts_with("Exec=") {
+            lines.push(format!("Exec={}", exec.display()));
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+pub fn run_doctor(fix: bool, dry_run: bool) -> Result<DoctorReport, DoctorError> {
+    if fix {
+        require_root()?;
+    }
+    let config = Config::load()?;
+
+    say!("🩺 Checking registered AppImages...");
+
+    let audit = AuditLog::from_config(&config);
+    let report = Doctor::new(config)
+        .with_audit(audit)
+        .with_dry_run(dry_run)
+        .diagnose(fix)?;
+
+    if report.findings.is_empty() {
+        say!("✅ No problems found in {} apps", report.apps_checked);
+        return Ok(report);
+    }
+    for finding in &report.findings {
+        match &finding.error {
+            _ if finding.fixed => say!("🔧 Fixed {}", finding.problem),
+            Some(e) => say!("❌ Could not fix {} ({})", finding.problem, e),
+            None if fix => say!("[DRY RUN] Would fix {}", finding.problem),
+            None => say!("⚠️  {}", finding.problem),
+        }
+    }
+    if !fix {
+        say!(
+            "\n{} problems found; run `appiman doctor --fix` to repair them",
+            report.findings.len()
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{fake_appimage, test_config};
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    /// Registers `demo` 1.0 and 2.0 (active) with symlink, desktop entry and
+    /// icon, as the registrar would.
+    fn register_demo(config: &Config) -> VersionManager {
+        let manager = VersionManager::new(config.clone());
+        for (version, content) in [("1.0", b"one"), ("2.0", b"two")] {
+            let image = config.raw_dir().join(format!("demo-{}.AppImage", version));
+            fs::write(&image, fake_appimage(content)).unwrap();
+            manager.install_version("demo", version, &image).unwrap();
+        }
+        let link = config.symlink_dir().join("demo");
+        symlink(manager.get_appimage_path("demo", "2.0"), &link).unwrap();
+        fs::write(
+            config.desktop_dir().join("demo.desktop"),
+            format!("[Desktop Entry]\nName=Demo\nExec={}\n", link.display()),
+        )
+        .unwrap();
+        fs::write(config.icon_dir().join("demo.png"), "png").unwrap();
+        manager
+    }
+
+    #[test]
+    fn healthy_registration_has_no_problems() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        register_demo(&config);
+
+        assert_eq!(Doctor::new(config).check().unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn finds_each_class_of_problem() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let manager = register_demo(&config);
+
+        fs::remove_dir_all(manager.get_version_dir("demo", "1.0")).unwrap();
+        fs::write(manager.get_appimage_path("demo", "2.0"), "tampered").unwrap();
+        fs::create_dir_all(manager.get_version_dir("demo", "3.0")).unwrap();
+        fs::remove_file(manager.get_current_link("demo")).unwrap();
+        symlink(
+            config.bin_dir().join("gone/current/gone.AppImage"),
+            config.symlink_dir().join("gone"),
+        )
+        .unwrap();
+        fs::write(
+            config.desktop_dir().join("gone.desktop"),
+            format!(
+                "[Desktop Entry]\nExec={}\n",
+                config.symlink_dir().join("gone").display()
+            ),
+        )
+        .unwrap();
+        // Not written by appiman
+        fs::write(
+            config.desktop_dir().join("other.desktop"),
+            "[Desktop Entry]\nExec=/nonexistent/other\n",
+        )
+        .unwrap();
+        fs::write(config.icon_dir().join("gone.png"), "png").unwrap();
+        let mut cache = MetadataCache::new(&config.raw_dir().join(".cache"));
+        cache.add_entry(
+            &config.raw_dir().join("gone.AppImage"),
+            "abc".to_string(),
+            0,
+            "gone".to_string(),
+            "1.0".to_string(),
+        );
+        cache.save().unwrap();
+
+        let problems = Doctor::new(config).check().unwrap();
+        let kinds: Vec<&str> = problems
+            .iter()
+            .map(|p| match p {
+                Problem::InterruptedTransaction { .. } => "interrupted",
+                Problem::DanglingCurrentLink { .. } => "current",
+                Problem::MissingVersionDir { .. } => "missing",
+                Problem::OrphanVersionDir { .. } => "orphan",
+                Problem::ChecksumDrift { .. } => "drift",
+                Problem::BrokenDesktopEntry { .. } => "desktop",
+                Problem::BrokenSymlink { .. } => "symlink",
+                Problem::OrphanIcon { .. } => "icon",
+                Problem::StaleCacheEntry { .. } => "cache",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "missing", "drift", "orphan", "current", "symlink", "desktop", "icon", "cache"
+            ]
+        );
+    }
+
+    #[test]
+    fn fix_repairs_and_rechecks() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let manager = register_demo(&config);
+
+        // Drift in the active version strands the symlink, which is only
+        // found after the drifted version is dropped.
+        fs::write(manager.get_appimage_path("demo", "2.0"), "tampered").unwrap();
+        let orphan = manager.get_appimage_path("demo", "3.0");
+        fs::create_dir_all(orphan.parent().unwrap()).unwrap();
+        fs::write(&orphan, fake_appimage(b"three")).unwrap();
+        fs::write(config.icon_dir().join("gone.png"), "png").unwrap();
+
+        let doctor = Doctor::new(config.clone());
+        let report = doctor.diagnose(true).unwrap();
+
+        assert!(
+            report.findings.iter().all(|f| f.fixed),
+            "{:?}",
+            report.findings
+        );
+        assert!(
+            report
+                .findings
+                .iter()
+                .any(|f| matches!(f.problem, Problem::BrokenSymlink { .. }))
+        );
+        assert_eq!(report.outcome(), Outcome::Success);
+        let json = serde_json::to_value(&report.findings[0]).unwrap();
+        assert_eq!(json["kind"], "checksum_drift");
+        assert_eq!(json["fixed"], true);
+        assert_eq!(doctor.check().unwrap(), Vec::new());
+
+        assert_eq!(
+            manager.get_current_version("demo").unwrap().as_deref(),
+            Some("1.0")
+        );
+        let metadata = manager.load_app_metadata("demo").unwrap();
+        assert!(!metadata.get_version("3.0").unwrap().is_active);
+        assert!(metadata.get_version("2.0").is_none());
+        assert_eq!(
+            fs::read_link(config.symlink_dir().join("demo")).unwrap(),
+            manager.get_appimage_path("demo", "1.0")
+        );
+        assert_eq!(
+            Quarantine::new(config.quarantine_dir())
+                .list()
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(!config.icon_dir().join("gone.png").exists());
+        // The dropped version's blob is released, the adopted one shared.
+        assert!(manager.blobs().unreferenced().unwrap().is_empty());
+        assert!(manager.journal().pending_apps().unwrap().is_empty());
+    }
+
+    #[test]
+    fn last_version_is_not_dropped() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let manager = register_demo(&config);
+        manager.remove_version("demo", "1.0").unwrap();
+        fs::write(manager.get_appimage_path("demo", "2.0"), "tampered").unwrap();
+
+        let report = Doctor::new(config).diagnose(true).unwrap();
+
+        assert_eq!(report.outcome(), Outcome::PartialFailure);
+        assert!(manager.get_appimage_path("demo", "2.0").exists());
+    }
+
+    #[test]
+    fn rewrites_exec_of_main_group_only() {
+        let content = "[Desktop Entry]\nExec=/old %U\n[Desktop Action new]\nExec=/other\n";
+        assert_eq!(desktop_exec(content), Some(PathBuf::from("/old")));
+        assert_eq!(
+            with_desktop_exec(content, Path::new("/new")),
+            "[Desktop Entry]\nExec=/new\n[Desktop Action new]\nExec=/other\n"
+        );
+    }
+}
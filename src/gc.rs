@@ -0,0 +1,393 @@
+// src/gc.rs
+
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+
+use crate::audit::{AuditEvent, AuditLog, AuditOperation};
+use crate::cli::say;
+use crate::config::{Config, ConfigError};
+use crate::core::files::dir_size;
+use crate::core::retention::{Retained, RetentionPolicy, Verdict, list_backups};
+use crate::core::{AppMetadata, VersionError, VersionManager};
+use crate::output::{Outcome, Report, serialize_path_reasons};
+use crate::privileges::require_root;
+use crate::status::StatusReporter;
+
+#[derive(Debug, Error)]
+pub enum GcError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Config error: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error("Version error: {0}")]
+    Version(#[from] VersionError),
+}
+
+/// A version or update backup removed by `gc`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reclaimed {
+    pub app: String,
+    /// `None` for update backups.
+    pub version: Option<String>,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    pub removed: Vec<Reclaimed>,
+    /// Blobs no version or backup linked to any more.
+    pub pruned_blobs: Vec<PathBuf>,
+    /// Bytes freed, or that would be freed in a dry run. Files that are
+    /// still linked from elsewhere do not count.
+    pub reclaimed_bytes: u64,
+    #[serde(serialize_with = "serialize_path_reasons")]
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl Report for GcReport {
+    fn outcome(&self) -> Outcome {
+        Outcome::of_batch(
+            self.removed.len() + self.pruned_blobs.len(),
+            self.errors.len(),
+        )
+    }
+}
+
+/// Applies the retention policy to every installed version and update
+/// backup.
+pub struct Collector {
+    config: Config,
+    version_manager: VersionManager,
+    audit: Option<AuditLog>,
+    dry_run: bool,
+}
+
+impl Collector {
+    pub fn new(config: Config) -> Self {
+        Collector {
+            version_manager: VersionManager::new(config.clone()),
+            config,
+            audit: None,
+            dry_run: false,
+        }
+    }
+
+    /// Records removals in `audit`.
+    pub fn with_audit(mut self, audit: Option<AuditLog>) -> Self {
+        self.version_manager = VersionManager::new(self.config.clone()).with_audit(audit.clone());
+        self.audit = audit;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Versions and backups the policy does not keep.
+    pub fn plan(&self) -> Result<Vec<Reclaimed>, GcError> {
+        let policy = RetentionPolicy::from_config(&self.config);
+        let now = Utc::now();
+        let mut backups = list_backups(&self.config)?;
+        let mut verdicts: Vec<Verdict> = Vec::new();
+        for app in self.version_manager.list_apps()? {
+            let metadata = self.version_manager.load_app_metadata(&app)?;
+            verdicts.extend(policy.plan_app(
+                &metadata,
+                |version| {
+                    dir_size(&self.version_manager.get_version_dir(&app, version)).unwrap_or(0)
+                },
+                &backups.remove(&app).unwrap_or_default(),
+                now,
+            ));
+        }
+        // Backups of uninstalled apps are judged on their own.
+        for (app, backups) in backups {
+            let metadata = AppMetadata::new(app.clone(), app);
+            verdicts.extend(policy.plan_app(&metadata, |_| 0, &backups, now));
+        }
+        policy.apply_total_cap(&mut verdicts);
+
+        let plan = verdicts
+            .into_iter()
+            .filter(|verdict| verdict.keep.is_none())
+            .map(|verdict| {
+                let (version, path) = match verdict.item {
+                    Retained::Version(version) => {
+                        let path = self.version_manager.get_version_dir(&verdict.app, &version);
+                        (Some(version), path)
+                    }
+                    Retained::Backup(path) => (None, path),
+                };
+                Reclaimed {
+                    app: verdict.app,
+                    version,
+                    path,
+                    size_bytes: verdict.size_bytes,
+                }
+            })
+            .collect();
+        Ok(plan)
+    }
+
+    pub fn collect(&self) -> Result<GcReport, GcError> {
+        let mut report = GcReport::default();
+        let blobs = self.version_manager.blobs();
+        let store_inodes = blobs.inodes()?;
+        let orphans = blobs.unreferenced()?;
+
+        // Link counts must be read before anything is removed.
+        let mut removed_files = Vec::new();
+        for item in self.plan()? {
+            let files = file_links(&item.path)?;
+            let result = if self.dry_run {
+                Ok(())
+            } else {
+                self.remove(&item)
+            };
+            match result {
+                Ok(()) => {
+                    removed_files.extend(files);
+                    report.removed.push(item);
+                }
+                Err(e) => report.errors.push((item.path, e.to_string())),
+            }
+        }
+        report.reclaimed_bytes = freed_bytes(&removed_files, &store_inodes);
+
+        for (blob, size) in orphans {
+            let result = if self.dry_run {
+                Ok(())
+            } else {
+                fs::remove_file(&blob)
+            };
+            match result {
+                Ok(()) => {
+                    report.reclaimed_bytes += size;
+                    report.pruned_blobs.push(blob);
+                }
+                Err(e) => report.errors.push((blob, e.to_string())),
+            }
+        }
+        Ok(report)
+    }
+
+    fn remove(&self, item: &Reclaimed) -> Result<(), GcError> {
+        if let Some(version) = &item.version {
+            self.version_manager.remove_version(&item.app, version)?;
+            return Ok(());
+        }
+
+        let result = fs::remove_file(&item.path);
+        if let Some(audit) = &self.audit {
+            audit.record(
+                AuditEvent::new(AuditOperation::Clean)
+                    .with_app(&item.app)
+                    .with_path(&item.path)
+                    .with_result(&result),
+            );
+        }
+        result?;
+        info!(app = %item.app, path = ?item.path, operation = "gc", "Removed update backup {:?}", item.path);
+        Ok(())
+    }
+}
+
+/// A file below a path that `gc` removes.
+struct FileLink {
+    /// Device and inode number.
+    id: (u64, u64),
+    links: u64,
+    size: u64,
+}
+
+fn file_links(path: &Path) -> io::Result<Vec<FileLink>> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    if metadata.is_dir() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            files.extend(file_links(&entry?.path())?);
+        }
+        return Ok(files);
+    }
+    Ok(vec![FileLink {
+        id: (metadata.dev(), metadata.ino()),
+        links: metadata.nlink(),
+        size: metadata.len(),
+    }])
+}
+
+/// Bytes freed by removing `files`: a file's data is only freed once all of
+/// its links are gone. A blob's own link is released along with the last
+/// version or backup using it.
+fn freed_bytes(files: &[FileLink], store_inodes: &HashSet<(u64, u64)>) -> u64 {
+    let mut removed: HashMap<(u64, u64), (u64, u64, u64)> = HashMap::new();
+    for file in files {
+        removed
+            .entry(file.id)
+            .or_insert((file.links, file.size, 0))
+            .2 += 1;
+    }
+    removed
+        .into_iter()
+        .filter(|(id, (links, _, count))| count + store_inodes.contains(id) as u64 >= *links)
+        .map(|(_, (_, size, _))| size)
+        .sum()
+}
+
+pub fn run_gc(dry_run: bool) -> Result<GcReport, GcError> {
+    if !dry_run {
+        require_root()?;
+    }
+    let config = Config::load()?;
+    let audit = AuditLog::from_config(&config);
+    let report = Collector::new(config)
+        .with_audit(audit)
+        .with_dry_run(dry_run)
+        .collect()?;
+
+    for item in &report.removed {
+        let what = match &item.version {
+            Some(version) => format!("{} version {}", item.app, version),
+            None => format!("{} backup {}", item.app, item.path.display()),
+        };
+        let size = StatusReporter::format_size(item.size_bytes);
+        if dry_run {
+            say!("[DRY RUN] Would remove {} ({})", what, size);
+        } else {
+            say!("🗑️  Removed {} ({})", what, size);
+        }
+    }
+    if !report.pruned_blobs.is_empty() {
+        let verb = if dry_run { "Would prune" } else { "Pruned" };
+        say!("{} {} unreferenced blobs", verb, report.pruned_blobs.len());
+    }
+    for (path, error) in &report.errors {
+        say!("❌ Could not remove {}: {}", path.display(), error);
+    }
+
+    let reclaimed = StatusReporter::format_size(report.reclaimed_bytes);
+    if report.removed.is_empty() && report.pruned_blobs.is_empty() && report.errors.is_empty() {
+        say!("✅ Nothing to collect");
+    } else if dry_run {
+        say!("\n[DRY RUN] Would reclaim {}", reclaimed);
+    } else {
+        say!("\n♻️  Reclaimed {}", reclaimed);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{fake_appimage, test_config};
+    use tempfile::TempDir;
+
+    fn install(config: &Config, app: &str, version: &str, body: &[u8]) {
+        let temp = TempDir::new().unwrap();
+        let image = temp.path().join("App.AppImage");
+        fs::write(&image, fake_appimage(body)).unwrap();
+        VersionManager::new(config.clone())
+            .install_version(app, version, &image)
+            .unwrap();
+    }
+
+    #[test]
+    fn gc_removes_versions_and_backups_beyond_policy() {
+        let temp = TempDir::new().unwrap();
+        let mut config = test_config(temp.path());
+        config.versions.max_versions_per_app = 1;
+        config.updates.max_backups = 1;
+        for version in ["1.0", "2.0", "3.0"] {
+            install(&config, "tool", version, version.as_bytes());
+        }
+        let backups = config.bin_dir().join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        for (i, stamp) in ["20260101_000000", "20260201_000000"].iter().enumerate() {
+            let path = backups.join(format!("tool_backup_{}.AppImage", stamp));
+            fs::write(&path, vec![0u8; 10]).unwrap();
+            let time =
+                std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(i as u64 * 60);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        }
+
+        let dry = Collector::new(config.clone())
+            .with_dry_run(true)
+            .collect()
+            .unwrap();
+        assert_eq!(dry.removed.len(), 2);
+        assert!(config.bin_dir().join("tool/versions/1.0").exists());
+
+        let report = Collector::new(config.clone()).collect().unwrap();
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.removed, dry.removed);
+        assert_eq!(
+            report.reclaimed_bytes,
+            report.removed.iter().map(|r| r.size_bytes).sum::<u64>()
+        );
+        assert_eq!(report.removed[0].version.as_deref(), Some("1.0"));
+        assert_eq!(
+            report.removed[1].path,
+            backups.join("tool_backup_20260101_000000.AppImage")
+        );
+
+        // 3.0 is active and 2.0 was active before it.
+        let versions = VersionManager::new(config.clone())
+            .list_versions("tool")
+            .unwrap();
+        let versions: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(versions, ["2.0", "3.0"]);
+        assert!(
+            backups
+                .join("tool_backup_20260201_000000.AppImage")
+                .exists()
+        );
+
+        let again = Collector::new(config).collect().unwrap();
+        assert_eq!(again.outcome(), Outcome::NothingToDo);
+    }
+
+    #[test]
+    fn versions_sharing_a_blob_free_nothing_until_the_last_goes() {
+        let temp = TempDir::new().unwrap();
+        let mut config = test_config(temp.path());
+        config.versions.max_versions_per_app = 1;
+        config.updates.max_backups = 1;
+        install(&config, "tool", "1.0", b"same");
+        install(&config, "tool", "1.1", b"same");
+        install(&config, "tool", "2.0", b"new");
+        let manager = VersionManager::new(config.clone());
+        let image = |version| fs::metadata(manager.get_appimage_path("tool", version)).unwrap();
+        assert_eq!(image("1.0").ino(), image("1.1").ino());
+
+        // 1.1 was active before 2.0 and keeps the shared blob alive.
+        let report = Collector::new(config.clone()).collect().unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(report.removed[0].size_bytes > 0);
+        assert_eq!(report.reclaimed_bytes, 0);
+        assert_eq!(image("1.1").nlink(), 2);
+
+        manager.switch_version("tool", "1.1").unwrap();
+        manager.remove_version("tool", "2.0").unwrap();
+        assert_eq!(manager.blobs().inodes().unwrap().len(), 1);
+    }
+}
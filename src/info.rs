@@ -0,0 +1,371 @@
+// src/info.rs
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::debug;
+
+use crate::audit::{AuditFilter, AuditLog, AuditOperation};
+use crate::cli::say;
+use crate::config::{Config, ConfigError};
+use crate::core::files::dir_size;
+use crate::core::{
+    AppImage, AppImageType, DEFAULT_CHANNEL, Metadata, VersionError, VersionManager,
+};
+use crate::output::Report;
+use crate::security::{SecurityReport, SecurityStatus};
+use crate::status::StatusReporter;
+
+#[derive(Debug, Error)]
+pub enum InfoError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Config error: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error("Version error: {0}")]
+    Version(#[from] VersionError),
+
+    #[error("App not found: {0}")]
+    AppNotFound(String),
+}
+
+/// Everything appiman knows about one registered app.
+#[derive(Debug, Serialize)]
+pub struct AppInfo {
+    pub app: String,
+    pub display_name: String,
+    pub active_version: Option<String>,
+    pub pinned_version: Option<String>,
+    pub channel: Option<String>,
+    pub versions: Vec<VersionDetails>,
+    /// Fields of the desktop entry embedded in the active image.
+    pub desktop_entry: Option<DesktopFields>,
+    pub icon: Option<PathBuf>,
+    /// `None` when the symlink or desktop file is missing.
+    pub symlink: Option<PathBuf>,
+    pub desktop_file: Option<PathBuf>,
+    /// Report of the checks run when the active image was registered.
+    pub security_report: Option<SecurityReport>,
+    pub update_information: Option<String>,
+    /// User whose Downloads the app was first ingested from.
+    pub source_user: Option<String>,
+    pub total_size_bytes: u64,
+}
+
+impl Report for AppInfo {}
+
+#[derive(Debug, Serialize)]
+pub struct VersionDetails {
+    pub version: String,
+    pub checksum: String,
+    pub installed_at: DateTime<Utc>,
+    pub is_active: bool,
+    pub prerelease: bool,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DesktopFields {
+    pub name: String,
+    pub categories: Vec<String>,
+    pub icon: Option<String>,
+}
+
+/// Collects [`AppInfo`] for `app`.
+pub fn app_info(config: &Config, app: &str) -> Result<AppInfo, InfoError> {
+    let version_manager = VersionManager::new(config.clone());
+    // Only names of registered apps, so `app` cannot point outside bin_dir.
+    if !version_manager.list_apps()?.iter().any(|a| a == app) {
+        return Err(InfoError::AppNotFound(app.to_string()));
+    }
+    let metadata = version_manager.load_app_metadata(app)?;
+
+    let mut versions = Vec::new();
+    for version in metadata.sorted_versions() {
+        let dir = version_manager.get_version_dir(app, &version.version);
+        versions.push(VersionDetails {
+            version: version.version.clone(),
+            checksum: version.checksum.clone(),
+            installed_at: version.installed_at,
+            is_active: version.is_active,
+            prerelease: version.parsed().is_prerelease(),
+            path: version_manager.get_appimage_path(app, &version.version),
+            size_bytes: dir_size(&dir)?,
+        });
+    }
+    let active = versions.iter().find(|v| v.is_active);
+
+    let (desktop_entry, update_information) = match active {
+        Some(version) => inspect_image(&version.path),
+        None => (None, None),
+    };
+
+    let symlink = config.symlink_dir().join(app);
+    let desktop_file = config.desktop_dir().join(format!("{}.desktop", app));
+    let icon = fs::read_dir(config.icon_dir()).ok().and_then(|entries| {
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| path.file_stem().is_some_and(|stem| stem == app))
+    });
+
+    let checksums: Vec<&str> = versions.iter().map(|v| v.checksum.as_str()).collect();
+    Ok(AppInfo {
+        app: app.to_string(),
+        display_name: metadata.display_name.clone(),
+        active_version: active.map(|v| v.version.clone()),
+        pinned_version: metadata.pinned_version.clone(),
+        channel: metadata.channel.clone(),
+        desktop_entry,
+        icon,
+        symlink: symlink.symlink_metadata().is_ok().then_some(symlink),
+        desktop_file: desktop_file.exists().then_some(desktop_file),
+        security_report: active
+            .and_then(|v| version_manager.load_security_report(app, &v.version)),
+        update_information,
+        source_user: AuditLog::from_config(config)
+            .and_then(|audit| source_user(&audit, &checksums)),
+        total_size_bytes: dir_size(&version_manager.get_app_dir(app))?,
+        versions,
+    })
+}
+
+/// Reads the embedded desktop entry and update information of an image.
+/// Unreadable images yield neither, since the rest of the info is still
+/// useful.
+fn inspect_image(path: &Path) -> (Option<DesktopFields>, Option<String>) {
+    let Ok(image) = AppImage::new(path.to_path_buf()) else {
+        return (None, None);
+    };
+
+    let update_information = match image.update_information() {
+        Ok(info) => info.map(|info| info.to_string()),
+        Err(e) => {
+            debug!("Cannot read update information of {:?}: {}", path, e);
+            None
+        }
+    };
+
+    // Type 1 payloads are ISO 9660 and are not read in-process.
+    let desktop_entry = match image.image_type {
+        AppImageType::Type2 => image
+            .payload()
+            .ok()
+            .and_then(|payload| payload.desktop_entry().ok().flatten())
+            .map(|(_, content)| {
+                let metadata = Metadata::from_desktop_entry_content(&content);
+                DesktopFields {
+                    name: metadata.name,
+                    categories: metadata.categories,
+                    icon: metadata.icon_path,
+                }
+            }),
+        AppImageType::Type1 => None,
+    };
+    (desktop_entry, update_information)
+}
+
+/// Source user of the earliest successful ingest of any of `checksums`.
+fn source_user(audit: &AuditLog, checksums: &[&str]) -> Option<String> {
+    let filter = AuditFilter {
+        operation: Some(AuditOperation::Ingest),
+        ..Default::default()
+    };
+    audit
+        .read(&filter)
+        .ok()?
+        .into_iter()
+        .filter(|event| {
+            event
+                .after_checksum
+                .as_deref()
+                .is_some_and(|checksum| checksums.contains(&checksum))
+        })
+        .find_map(|event| event.source_user)
+}
+
+fn or_missing(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "(missing)".to_string())
+}
+
+pub fn run_info(app: &str) -> Result<AppInfo, InfoError> {
+    let config = Config::load()?;
+    let info = app_info(&config, app)?;
+
+    say!("📦 {} ({})", info.display_name, info.app);
+    say!(
+        "   Active version: {}",
+        info.active_version.as_deref().unwrap_or("none")
+    );
+    if let Some(version) = &info.pinned_version {
+        say!("   Pinned to:      {}", version);
+    }
+    say!(
+        "   Channel:        {}",
+        info.channel.as_deref().unwrap_or(DEFAULT_CHANNEL)
+    );
+    if let Some(user) = &info.source_user {
+        say!("   Downloaded by:  {}", user);
+    }
+    say!(
+        "   Disk usage:     {}",
+        StatusReporter::format_size(info.total_size_bytes)
+    );
+
+    say!("\n🗂️  Versions:");
+    for version in &info.versions {
+        say!(
+            "  {} {:<20} {:>10}  installed {}{}",
+            if version.is_active { "*" } else { " " },
+            version.version,
+            StatusReporter::format_size(version.size_bytes),
+            version.installed_at.format("%Y-%m-%d %H:%M:%S"),
+            if version.prerelease { " (prerelease)" } else { "" }
+        );
+        say!("    sha256 {}", version.checksum);
+    }
+
+    say!("\n🔗 Files:");
+    say!("  Symlink:       {}", or_missing(&info.symlink));
+    say!("  Desktop file:  {}", or_missing(&info.desktop_file));
+    say!("  Icon:          {}", or_missing(&info.icon));
+
+    if let Some(entry) = &info.desktop_entry {
+        say!("\n🖥️  Embedded desktop entry:");
+        say!("  Name:       {}", entry.name);
+        say!("  Categories: {}", entry.categories.join(";"));
+        if let Some(icon) = &entry.icon {
+            say!("  Icon:       {}", icon);
+        }
+    }
+
+    say!("\n🔄 Updates:");
+    say!(
+        "  {}",
+        info.update_information
+            .as_deref()
+            .unwrap_or("No update information embedded")
+    );
+
+    say!("\n🔒 Security:");
+    match &info.security_report {
+        Some(report) => {
+            let status = match &report.overall_status {
+                SecurityStatus::Secure => "✅ Secure".to_string(),
+                SecurityStatus::Warning(msg) => format!("⚠️ {}", msg),
+                SecurityStatus::Error(msg) => format!("❌ {}", msg),
+                SecurityStatus::KeyChanged(msg) => format!("🔑 {}", msg),
+            };
+            say!("  Status:    {}", status);
+            say!(
+                "  Signature: {}",
+                match report.signature_verified {
+                    Some(true) => "verified",
+                    Some(false) => "invalid",
+                    None if report.signature_present => "present, not verified",
+                    None => "none",
+                }
+            );
+            if let Some(signer) = &report.signer {
+                say!("  Signer:    {}", signer);
+            }
+        }
+        None => say!("  No security report recorded"),
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditEvent, AuditOutcome};
+    use crate::core::testing::{ElfBuilder, SquashfsBuilder, appimage};
+    use tempfile::TempDir;
+
+    #[test]
+    fn info_combines_metadata_files_and_audit() {
+        let temp = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.directories.bin = temp.path().join("bin").display().to_string();
+        config.directories.icons = temp.path().join("icons").display().to_string();
+        config.directories.desktop = temp.path().join("desktop").display().to_string();
+        config.directories.symlink = temp.path().join("symlinks").display().to_string();
+        config.audit.path = temp.path().join("audit.jsonl").display().to_string();
+        for dir in [
+            config.icon_dir(),
+            config.desktop_dir(),
+            config.symlink_dir(),
+        ] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        let image = temp.path().join("Demo.AppImage");
+        fs::write(
+            &image,
+            appimage(
+                &ElfBuilder::new(),
+                &SquashfsBuilder::new().file(
+                    "demo.desktop",
+                    b"[Desktop Entry]\nName=Demo Tool\nCategories=Development;\n",
+                ),
+            ),
+        )
+        .unwrap();
+        let manager = VersionManager::new(config.clone());
+        manager.install_version("demo", "1.0", &image).unwrap();
+        manager
+            .save_security_report("demo", "1.0", &SecurityReport::new())
+            .unwrap();
+        fs::write(config.icon_dir().join("demo.svg"), "<svg/>").unwrap();
+
+        let checksum = manager.active_checksum("demo");
+        AuditLog::new(temp.path().join("audit.jsonl"))
+            .append(
+                &AuditEvent::new(AuditOperation::Ingest)
+                    .with_source_user(Some("alice"))
+                    .with_checksums(None, checksum.clone())
+                    .with_outcome(AuditOutcome::Success, None),
+            )
+            .unwrap();
+
+        let info = app_info(&config, "demo").unwrap();
+        assert_eq!(info.active_version.as_deref(), Some("1.0"));
+        assert_eq!(info.versions[0].checksum, checksum.unwrap());
+        assert!(info.versions[0].size_bytes > 0);
+        assert!(info.total_size_bytes >= info.versions[0].size_bytes);
+        assert_eq!(info.desktop_entry.unwrap().name, "Demo Tool");
+        assert_eq!(info.icon, Some(config.icon_dir().join("demo.svg")));
+        assert!(info.symlink.is_none());
+        assert!(info.desktop_file.is_none());
+        assert_eq!(info.security_report, Some(SecurityReport::new()));
+        assert_eq!(info.source_user.as_deref(), Some("alice"));
+
+        // The report shown follows the active version.
+        let newer = temp.path().join("Demo-2.0.AppImage");
+        fs::write(&newer, crate::core::testing::fake_appimage(b"two")).unwrap();
+        manager.install_version("demo", "2.0", &newer).unwrap();
+        let unsigned = SecurityReport::new().with_warning("No signature found".to_string());
+        manager
+            .save_security_report("demo", "2.0", &unsigned)
+            .unwrap();
+        assert_eq!(app_info(&config, "demo").unwrap().security_report, Some(unsigned));
+        manager.switch_version("demo", "1.0").unwrap();
+        assert_eq!(
+            app_info(&config, "demo").unwrap().security_report,
+            Some(SecurityReport::new())
+        );
+
+        assert!(matches!(
+            app_info(&config, "missing"),
+            Err(InfoError::AppNotFound(_))
+        ));
+    }
+}
@@ -1,11 +1,14 @@
 // src/ingest.rs
 
+use crate::audit::AuditLog;
+use crate::cli::say;
 use crate::config::Config;
-use crate::mover::{Mover, Scanner};
+use crate::mover::{MoveReport, Mover, Scanner};
+use crate::policy::PolicyEnforcer;
 use crate::privileges::require_root;
 use std::io;
 
-pub fn run_ingest() -> io::Result<()> {
+pub fn run_ingest(dry_run: bool) -> io::Result<MoveReport> {
     require_root()?;
 
     let config = Config::load().map_err(|e| {
@@ -14,9 +17,9 @@ pub fn run_ingest() -> io::Result<()> {
         )
     })?;
 
-    println!("📥 Ingesting user-downloaded AppImages...");
+    say!("📥 Ingesting user-downloaded AppImages...");
 
-    let scanner = Scanner::new(config.home_root());
+    let scanner = Scanner::new(config.home_root()).with_ingest(config.ingest.clone());
     let appimages = scanner.find_appimages().map_err(|e| {
         io::Error::other(
             format!("Failed to scan for AppImages: {}", e),
@@ -24,24 +27,42 @@ pub fn run_ingest() -> io::Result<()> {
     })?;
 
     if appimages.is_empty() {
-        println!("ℹ️  No AppImages found to ingest.");
-        return Ok(());
+        say!("ℹ️  No AppImages found to ingest.");
+        return Ok(MoveReport::new());
     }
 
-    let mover = Mover::new(config.home_root(), config.raw_dir());
+    let mover = Mover::new(config.home_root(), config.raw_dir())
+        .with_dry_run(dry_run)
+        .with_policy(PolicyEnforcer::from_config(&config))
+        .with_audit(AuditLog::from_config(&config))
+        .with_settle_window(config.ingest.settle_window());
     let report = mover.move_appimages(&appimages).map_err(|e| {
         io::Error::other(
             format!("Failed to move AppImages: {}", e),
         )
     })?;
 
-    println!("✅ Ingest complete: {} moved.", report.success_count());
+    say!("✅ Ingest complete: {} moved.", report.success_count());
+
+    if !report.skipped.is_empty() {
+        say!(
+            "⏳ {} AppImages still downloading or changing, left for the next run.",
+            report.skipped.len()
+        );
+    }
+
+    if !report.quarantined.is_empty() {
+        say!(
+            "🚫 {} AppImages quarantined by policy.",
+            report.quarantined.len()
+        );
+    }
 
     if !report.errors.is_empty() {
-        println!("⚠️  {} errors occurred.", report.error_count());
+        say!("⚠️  {} errors occurred.", report.error_count());
     }
 
-    Ok(())
+    Ok(report)
 }
 
 #[cfg(test)]
@@ -49,6 +70,7 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    use crate::core::testing::fake_appimage;
     use crate::mover::{Mover, Scanner};
 
     #[test]
@@ -63,7 +85,7 @@ mod tests {
         fs::create_dir_all(&raw_dir).unwrap();
 
         let appimage_path = downloads.join("TestApp.AppImage");
-        fs::write(&appimage_path, b"fake appimage").unwrap();
+        fs::write(&appimage_path, fake_appimage(b"")).unwrap();
 
         let scanner = Scanner::new(home_root.clone());
         let found_apps = scanner.find_appimages().unwrap();
@@ -94,7 +116,7 @@ mod tests {
             fs::create_dir_all(&downloads).unwrap();
 
             let appimage = downloads.join(format!("{}.AppImage", user));
-            fs::write(&appimage, b"fake appimage").unwrap();
+            fs::write(&appimage, fake_appimage(b"")).unwrap();
         }
 
         let scanner = Scanner::new(home_root.clone());
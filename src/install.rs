@@ -0,0 +1,276 @@
+// src/install.rs
+
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use thiserror::Error;
+use tracing::info;
+
+use crate::cli::say;
+use crate::config::{Config, ConfigError};
+use crate::output::Report;
+use crate::privileges::require_root;
+use crate::registrar::{InstallOptions, ProcessError, Processor};
+use crate::update::{DefaultTransport, Transport, UpdateError};
+
+#[derive(Debug, Error)]
+pub enum InstallError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Config error: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error("Download failed: {0}")]
+    Download(#[from] UpdateError),
+
+    #[error("{0}")]
+    Process(#[from] ProcessError),
+
+    #[error("No such file: {0}")]
+    NotFound(PathBuf),
+
+    #[error("Invalid {0}: {1:?}")]
+    InvalidName(&'static str, String),
+}
+
+/// Result of `install`.
+#[derive(Debug, Serialize)]
+pub struct InstallReport {
+    pub app: String,
+    pub version: String,
+    pub source: String,
+    pub path: PathBuf,
+    pub symlink: PathBuf,
+    pub desktop_entry: Option<PathBuf>,
+}
+
+impl Report for InstallReport {}
+
+/// Installs a single AppImage from a local path or URL, bypassing the
+/// ingest/scan pipeline but running the same checks.
+pub struct Installer {
+    processor: Processor,
+    transport: Box<dyn Transport>,
+}
+
+impl Installer {
+    pub fn new(processor: Processor) -> Self {
+        Installer {
+            processor,
+            transport: Box::new(DefaultTransport::new()),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_transport(mut self, transport: Box<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Checks, installs and registers `source`. The image is staged in a
+    /// temporary directory first, so a rejected image is quarantined without
+    /// touching the original file.
+    pub fn install(
+        &self,
+        source: &str,
+        options: &InstallOptions,
+    ) -> Result<InstallReport, InstallError> {
+        if let Some(name) = &options.name
+            && !is_valid_app_name(name)
+        {
+            return Err(InstallError::InvalidName("name", name.clone()));
+        }
+        if let Some(version) = &options.version
+            && !is_valid_component(version)
+        {
+            return Err(InstallError::InvalidName("version", version.clone()));
+        }
+
+        let staging = TempDir::new()?;
+        let staged = self.stage(source, staging.path())?;
+        info!("Installing {} from {:?}", source, staged);
+
+        let processed = self.processor.install_appimage(&staged, options)?;
+        let app = processed.normalized_name;
+        let desktop_entry = options
+            .desktop_entry
+            .then(|| self.processor.desktop_dir.join(format!("{}.desktop", app)));
+
+        Ok(InstallReport {
+            path: self
+                .processor
+                .version_manager
+                .get_appimage_path(&app, &processed.version),
+            symlink: self.processor.symlink_dir.join(&app),
+            desktop_entry,
+            version: processed.version,
+            source: source.to_string(),
+            app,
+        })
+    }
+
+    /// Copies or downloads `source` into `dir`, keeping its file name so the
+    /// app name and version can be guessed from it.
+    fn stage(&self, source: &str, dir: &Path) -> Result<PathBuf, InstallError> {
+        if source.contains("://") {
+            let mut file_name = source
+                .split(['?', '#'])
+                .next()
+                .and_then(|url| url.rsplit('/').next())
+                .filter(|name| is_valid_component(name))
+                .unwrap_or("download")
+                .to_string();
+            if !file_name.to_lowercase().ends_with(".appimage") {
+                file_name.push_str(".AppImage");
+            }
+            let staged = dir.join(file_name);
+            fs::write(&staged, self.transport.fetch(source)?)?;
+            return Ok(staged);
+        }
+
+        let path = Path::new(source);
+        if !path.is_file() {
+            return Err(InstallError::NotFound(path.to_path_buf()));
+        }
+        let staged = dir.join(path.file_name().unwrap_or_default());
+        fs::copy(path, &staged)?;
+        Ok(staged)
+    }
+}
+
+/// Names and versions become directory names below the bin dir.
+fn is_valid_component(value: &str) -> bool {
+    !value.is_empty() && !value.starts_with('.') && !value.contains('/')
+}
+
+/// App names must also look like the names `scan` derives from file names,
+/// so an app installed under a chosen name is recognised by later scans.
+fn is_valid_app_name(name: &str) -> bool {
+    is_valid_component(name)
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"._-".contains(&b))
+}
+
+pub fn run_install(
+    source: &str,
+    options: &InstallOptions,
+    dry_run: bool,
+) -> Result<InstallReport, InstallError> {
+    require_root()?;
+    let config = Config::load()?;
+
+    say!("📦 Installing {}...", source);
+
+    let processor = crate::scan::processor_from_config(&config).with_dry_run(dry_run);
+    let report = Installer::new(processor).install(source, options)?;
+
+    if dry_run {
+        say!(
+            "[DRY RUN] Would install {} version {} to {}",
+            report.app,
+            report.version,
+            report.path.display()
+        );
+    } else {
+        say!("✅ Installed {} version {}", report.app, report.version);
+        say!("   Run it with: {}", report.symlink.display());
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{ElfBuilder, SquashfsBuilder, appimage, fake_appimage, test_config};
+    use std::ops::Range;
+
+    struct StaticTransport(Vec<u8>);
+
+    impl Transport for StaticTransport {
+        fn fetch(&self, _url: &str) -> Result<Vec<u8>, UpdateError> {
+            Ok(self.0.clone())
+        }
+
+        fn fetch_range(&self, _url: &str, range: Range<u64>) -> Result<Vec<u8>, UpdateError> {
+            Ok(self.0[range.start as usize..range.end as usize].to_vec())
+        }
+    }
+
+    #[test]
+    fn installs_local_file_without_moving_it() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let source = temp.path().join("Demo-1.2.0.AppImage");
+        let image = appimage(
+            &ElfBuilder::new(),
+            &SquashfsBuilder::new().file("demo.desktop", b"[Desktop Entry]\nName=Demo\n"),
+        );
+        fs::write(&source, image).unwrap();
+
+        let installer = Installer::new(crate::scan::processor_from_config(&config));
+        let report = installer
+            .install(source.to_str().unwrap(), &InstallOptions::default())
+            .unwrap();
+
+        assert_eq!(report.app, "demo");
+        assert_eq!(report.version, "1.2.0");
+        assert!(source.exists());
+        assert!(report.path.is_file());
+        assert!(report.symlink.exists());
+        assert!(report.desktop_entry.unwrap().is_file());
+    }
+
+    #[test]
+    fn overrides_name_and_version_and_skips_desktop_entry() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let installer = Installer::new(crate::scan::processor_from_config(&config))
+            .with_transport(Box::new(StaticTransport(fake_appimage(b"tool"))));
+
+        let options = InstallOptions {
+            name: Some("tool".to_string()),
+            version: Some("2026.1".to_string()),
+            desktop_entry: false,
+        };
+        let report = installer
+            .install("https://example.com/download?id=1", &options)
+            .unwrap();
+
+        assert_eq!(
+            report.path,
+            config.bin_dir().join("tool/versions/2026.1/tool.AppImage")
+        );
+        assert!(report.path.is_file());
+        assert!(report.desktop_entry.is_none());
+        assert!(!config.desktop_dir().join("tool.desktop").exists());
+    }
+
+    #[test]
+    fn rejects_bad_names_and_missing_files() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let installer = Installer::new(crate::scan::processor_from_config(&config));
+
+        for name in ["../etc", "My App", "éditeur"] {
+            let options = InstallOptions {
+                name: Some(name.to_string()),
+                ..InstallOptions::default()
+            };
+            assert!(matches!(
+                installer.install("whatever.AppImage", &options),
+                Err(InstallError::InvalidName("name", _))
+            ));
+        }
+        assert!(matches!(
+            installer.install(
+                temp.path().join("missing.AppImage").to_str().unwrap(),
+                &InstallOptions::default()
+            ),
+            Err(InstallError::NotFound(_))
+        ));
+    }
+}
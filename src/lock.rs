@@ -0,0 +1,213 @@
+// src/lock.rs
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::config::Config;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Error)]
+pub enum LockError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("{} is held by {}; gave up after {}s", .path.display(), describe(.holder), .waited.as_secs())]
+    Timeout {
+        path: PathBuf,
+        holder: Option<Holder>,
+        waited: Duration,
+    },
+}
+
+/// The process holding a lock, as recorded in the lock file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Holder {
+    pub pid: u32,
+    pub command: String,
+    pub since: DateTime<Utc>,
+}
+
+impl Holder {
+    pub fn current() -> Self {
+        let mut args: Vec<String> = std::env::args().collect();
+        if let Some(program) = args.first_mut()
+            && let Some(name) = Path::new(program).file_name()
+        {
+            *program = name.to_string_lossy().into_owned();
+        }
+        Holder {
+            pid: std::process::id(),
+            command: args.join(" "),
+            since: Utc::now(),
+        }
+    }
+}
+
+impl fmt::Display for Holder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pid {} ({}) since {}",
+            self.pid,
+            self.command,
+            self.since.format("%Y-%m-%d %H:%M:%S")
+        )
+    }
+}
+
+/// An advisory lock on a file below the bin directory.
+///
+/// Commands that change state hold the global lock for their whole run, so
+/// the timer, path units and users take turns. Version operations also hold
+/// a lock per app, which keeps parallel workers of one run off the same app.
+/// The holder writes its pid and command line into the file, where `status`
+/// reads them. Locks are released when the guard is dropped or the process
+/// exits.
+pub struct Lock {
+    path: PathBuf,
+    timeout: Duration,
+}
+
+/// Holds a [`Lock`] until dropped.
+#[derive(Debug)]
+pub struct LockGuard {
+    _file: File,
+}
+
+impl Lock {
+    pub fn new(path: PathBuf, timeout: Duration) -> Self {
+        Lock { path, timeout }
+    }
+
+    pub fn global(config: &Config) -> Self {
+        Self::new(config.bin_dir().join(".lock"), config.locking.timeout())
+    }
+
+    pub fn app(config: &Config, app: &str) -> Self {
+        Self::new(
+            config
+                .bin_dir()
+                .join(".locks")
+                .join(format!("{}.lock", app)),
+            config.locking.timeout(),
+        )
+    }
+
+    /// Takes the lock, waiting up to the timeout for another holder.
+    pub fn acquire(&self) -> Result<LockGuard, LockError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+
+        let started = Instant::now();
+        let mut waiting = false;
+        loop {
+            match file.try_lock() {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) => {
+                    let waited = started.elapsed();
+                    if waited >= self.timeout {
+                        return Err(LockError::Timeout {
+                            path: self.path.clone(),
+                            holder: read_holder(&mut file),
+                            waited,
+                        });
+                    }
+                    if !waiting {
+                        waiting = true;
+                        info!(
+                            "Waiting for {} held by {}",
+                            self.path.display(),
+                            describe(&read_holder(&mut file))
+                        );
+                    }
+                    thread::sleep(POLL_INTERVAL.min(self.timeout - waited));
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+
+        let record = serde_json::to_vec(&Holder::current()).map_err(io::Error::other)?;
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(&record)?;
+        debug!("Acquired {}", self.path.display());
+        Ok(LockGuard { _file: file })
+    }
+
+    /// The process holding the lock, or `None` if it is free.
+    pub fn holder(&self) -> io::Result<Option<Holder>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match file.try_lock_shared() {
+            Ok(()) => Ok(None),
+            Err(TryLockError::WouldBlock) => Ok(read_holder(&mut file)),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+}
+
+fn describe(holder: &Option<Holder>) -> String {
+    holder
+        .as_ref()
+        .map_or_else(|| "another process".to_string(), Holder::to_string)
+}
+
+/// The holder record of a lock file; `None` if it is not written yet.
+fn read_holder(file: &mut File) -> Option<Holder> {
+    let mut record = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut record).ok()?;
+    serde_json::from_str(&record).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn second_holder_times_out_and_sees_the_first() {
+        let temp = TempDir::new().unwrap();
+        let lock = Lock::new(temp.path().join("bin/.lock"), Duration::from_millis(200));
+        assert_eq!(lock.holder().unwrap(), None);
+
+        let guard = lock.acquire().unwrap();
+        let holder = lock.holder().unwrap().unwrap();
+        assert_eq!(holder.pid, std::process::id());
+
+        match lock.acquire() {
+            Err(LockError::Timeout {
+                holder: Some(other),
+                waited,
+                ..
+            }) => {
+                assert_eq!(other, holder);
+                assert!(waited >= Duration::from_millis(200));
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+
+        drop(guard);
+        assert_eq!(lock.holder().unwrap(), None);
+        lock.acquire().unwrap();
+    }
+}
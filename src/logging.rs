@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config::Config;
+
+/// Prefix of every journal field taken from a tracing field, so `app` can be
+/// queried as `journalctl APPIMAN_APP=firefox`.
+const FIELD_PREFIX: &str = "APPIMAN_";
+const SYSLOG_IDENTIFIER: &str = "appiman";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTarget {
+    #[default]
+    Stderr,
+    Journald,
+}
+
+/// Installs the global subscriber described by the `[logging]` section.
+/// Falls back to stderr when the journal socket cannot be opened.
+pub fn init(config: &Config) {
+    let filter =
+        EnvFilter::try_new(&config.logging.level).unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry().with(filter);
+
+    let journald = match config.logging.target {
+        LogTarget::Journald => match JournaldLayer::connect(&config.journald_socket()) {
+            Ok(layer) => Some(layer),
+            Err(e) => {
+                eprintln!(
+                    "⚠️ Cannot open journald socket {}: {}, logging to stderr",
+                    config.logging.journald_socket, e
+                );
+                None
+            }
+        },
+        LogTarget::Stderr => None,
+    };
+
+    let result = if let Some(layer) = journald {
+        registry.with(layer).try_init()
+    } else if config.logging.json_output {
+        registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_writer(io::stderr),
+            )
+            .try_init()
+    } else {
+        registry
+            .with(tracing_subscriber::fmt::layer().with_writer(io::stderr))
+            .try_init()
+    };
+    // Only fails if a subscriber is already set, which is fine.
+    let _ = result;
+}
+
+/// Sends events to journald over its native datagram protocol. Tracing
+/// fields of the event and its spans become `APPIMAN_*` journal fields.
+pub struct JournaldLayer {
+    socket: UnixDatagram,
+}
+
+impl JournaldLayer {
+    pub fn connect(path: &Path) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        // Fail early if nothing is listening, rather than on the first event.
+        socket.connect(path)?;
+        Ok(JournaldLayer { socket })
+    }
+}
+
+/// Fields recorded on a span, kept in its extensions.
+struct SpanFields(Vec<(String, String)>);
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record(field, format!("{:?}", value));
+    }
+}
+
+impl FieldVisitor {
+    fn record(&mut self, field: &Field, value: String) {
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.fields.push((journal_field_name(field.name()), value));
+        }
+    }
+}
+
+impl<S> Layer<S> for JournaldLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanFields(visitor.fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        let mut extensions = span.extensions_mut();
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            fields.0.extend(visitor.fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut buf = Vec::new();
+
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    for (name, value) in &fields.0 {
+                        put_field(&mut buf, name, value.as_bytes());
+                    }
+                }
+            }
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        for (name, value) in &visitor.fields {
+            put_field(&mut buf, name, value.as_bytes());
+        }
+
+        put_field(
+            &mut buf,
+            "MESSAGE",
+            visitor.message.unwrap_or_default().as_bytes(),
+        );
+        put_field(&mut buf, "PRIORITY", priority(metadata.level()).as_bytes());
+        put_field(&mut buf, "SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER.as_bytes());
+        put_field(&mut buf, "TARGET", metadata.target().as_bytes());
+        if let Some(file) = metadata.file() {
+            put_field(&mut buf, "CODE_FILE", file.as_bytes());
+        }
+        if let Some(line) = metadata.line() {
+            put_field(&mut buf, "CODE_LINE", line.to_string().as_bytes());
+        }
+
+        // Nowhere to report a failed send; the event is dropped.
+        let _ = self.socket.send(&buf);
+    }
+}
+
+/// Syslog priority for a tracing level.
+fn priority(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "3",
+        Level::WARN => "4",
+        Level::INFO => "6",
+        Level::DEBUG | Level::TRACE => "7",
+    }
+}
+
+/// Journal field names are uppercase ASCII letters, digits and underscores.
+fn journal_field_name(name: &str) -> String {
+    let mut out = String::with_capacity(FIELD_PREFIX.len() + name.len());
+    out.push_str(FIELD_PREFIX);
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_uppercase());
+        } else {
+            out.push('_');
+        }
+    }
+    out
+}
+
+/// Appends one field in journald's native format. Values containing a
+/// newline are length-prefixed.
+fn put_field(buf: &mut Vec<u8>, name: &str, value: &[u8]) {
+    buf.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        buf.push(b'\n');
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        buf.push(b'=');
+    }
+    buf.extend_from_slice(value);
+    buf.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    /// Parses a native protocol datagram back into fields.
+    fn parse(mut buf: &[u8]) -> HashMap<String, String> {
+        let mut fields = HashMap::new();
+        while !buf.is_empty() {
+            let end = buf.iter().position(|&b| b == b'\n' || b == b'=').unwrap();
+            let name = String::from_utf8(buf[..end].to_vec()).unwrap();
+            let value;
+            if buf[end] == b'=' {
+                let len = buf[end + 1..].iter().position(|&b| b == b'\n').unwrap();
+                value = buf[end + 1..end + 1 + len].to_vec();
+                buf = &buf[end + 2 + len..];
+            } else {
+                let len = u64::from_le_bytes(buf[end + 1..end + 9].try_into().unwrap()) as usize;
+                value = buf[end + 9..end + 9 + len].to_vec();
+                buf = &buf[end + 10 + len..];
+            }
+            fields.insert(name, String::from_utf8(value).unwrap());
+        }
+        fields
+    }
+
+    #[test]
+    fn events_are_sent_with_structured_fields() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("journal.socket");
+        let journal = UnixDatagram::bind(&path).unwrap();
+
+        let layer = JournaldLayer::connect(&path).unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("update", app = "firefox", operation = "update");
+            let _guard = span.enter();
+            tracing::warn!(version = "2.0", "Download failed\nretrying");
+        });
+
+        let mut buf = vec![0; 4096];
+        let len = journal.recv(&mut buf).unwrap();
+        let fields = parse(&buf[..len]);
+
+        assert_eq!(fields["APPIMAN_APP"], "firefox");
+        assert_eq!(fields["APPIMAN_OPERATION"], "update");
+        assert_eq!(fields["APPIMAN_VERSION"], "2.0");
+        assert_eq!(fields["MESSAGE"], "Download failed\nretrying");
+        assert_eq!(fields["PRIORITY"], "4");
+        assert_eq!(fields["SYSLOG_IDENTIFIER"], "appiman");
+    }
+
+    #[test]
+    fn connect_fails_without_listener() {
+        let temp = TempDir::new().unwrap();
+        assert!(JournaldLayer::connect(&temp.path().join("missing.socket")).is_err());
+    }
+
+    #[test]
+    fn field_names_are_sanitized() {
+        assert_eq!(journal_field_name("app.name"), "APPIMAN_APP_NAME");
+        assert_eq!(priority(&Level::INFO), "6");
+    }
+}
@@ -1,9 +1,20 @@
+mod audit;
+mod cli;
  mod clean;
  mod config;
  mod core;
+mod doctor;
+mod gc;
  mod ingest;
+mod info;
+mod install;
+mod lock;
+mod logging;
  mod mover;
+mod output;
+mod policy;
  mod privileges;
+mod quarantine;
  mod registrar;
  mod scan;
  mod security;
@@ -11,246 +22,468 @@
  mod status;
  mod sync;
  mod systemd;
+mod trust;
+mod uninstall;
  mod update;
+mod zsync;
 
-use std::env;
+use clap::Parser;
+use serde::Serialize;
 use std::process::ExitCode;
 
+use crate::cli::{
+    Cli, Command, QuarantineCommand, TrustCommand, UpdateCommand, VersionsCommand, say,
+};
+use crate::core::{DEFAULT_CHANNEL, VersionInfo, VersionManager};
+use crate::output::finish;
+
 fn main() -> ExitCode {
-    let args: Vec<String> = env::args().collect();
-    let command = args.get(1).map(|s| s.as_str());
-    let json_output = args.iter().any(|a| a == "--json");
+    let cli = Cli::parse();
+    let global = &cli.global;
 
-    match command {
-        None | Some("help") | Some("-h") | Some("--help") => {
-            print_help();
-            ExitCode::SUCCESS
+    if let Some(path) = &global.config {
+        // SAFETY: no other threads exist yet.
+        unsafe {
+            std::env::set_var("APPIMAN_CONFIG", path);
         }
-        Some("init") => run_and_report(setup::initialize),
-        Some("enable") => run_and_report(systemd::enable_all),
-        Some("disable") => run_and_report(systemd::disable_all),
-        Some("status") => {
-            if json_output {
-                match status::StatusReporter::new() {
-                    Ok(reporter) => match reporter.print_status(true) {
-                        Ok(()) => ExitCode::SUCCESS,
-                        Err(e) => {
-                            eprintln!("❌ {}", e);
-                            ExitCode::FAILURE
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("❌ Failed to create status reporter: {}", e);
-                        ExitCode::FAILURE
-                    }
-                }
+    }
+    cli::set_quiet(global.quiet);
+
+    let mut config = config::Config::load().unwrap_or_default();
+    if let Some(level) = global.log_level() {
+        config.logging.level = level.to_string();
+    }
+    logging::init(&config);
+
+    let dry_run = global.dry_run;
+    let json = global.json;
+    if json {
+        // Human-readable output would corrupt the JSON on stdout.
+        cli::set_quiet(true);
+    }
+    // Timer and path units run ingest and scan while a user may be running
+    // appiman too, so commands that change state take turns.
+    let global_lock = if cli.command.changes_state() && !dry_run {
+        match acquire_global_lock(&config) {
+            Ok(lock) => lock,
+            Err(e) => return finish::<(), _>("lock", "Another appiman run is busy", json, Err(e)),
+        }
+    } else {
+        None
+    };
+    if global_lock.is_some() {
+        recover_interrupted(&config);
+    }
+
+    match cli.command {
+        Command::Init => finish("init", "Initialization failed", json, setup::initialize(dry_run)),
+        Command::Enable => finish("enable", "Enable failed", json, systemd::enable_all(dry_run)),
+        Command::Disable => finish("disable", "Disable failed", json, systemd::disable_all(dry_run)),
+        Command::Status => {
+            if json {
+                finish(
+                    "status",
+                    "Status failed",
+                    json,
+                    status::StatusReporter::new().and_then(|reporter| reporter.get_status()),
+                )
             } else {
-                run_and_report(systemd::print_status)
+                finish("status", "Status failed", json, status::print_plain_status())
             }
         }
-        Some("clean") => run_and_report(clean::run_cleanup),
-        Some("ingest") => run_and_report(ingest::run_ingest),
-        Some("scan") => run_and_report(scan::run_scan),
-        Some("sync") => run_and_report(sync::run_sync),
-        Some("update") => run_update(),
-        Some("versions") => run_versions(),
-        Some(other) => {
-            eprintln!("❌ Unknown command: {}", other);
-            print_help();
-            ExitCode::from(2)
+        Command::Clean => finish("clean", "Cleanup failed", json, clean::run_cleanup(dry_run)),
+        Command::Ingest => finish("ingest", "Ingest failed", json, ingest::run_ingest(dry_run)),
+        Command::Scan => finish("scan", "Scan failed", json, scan::run_scan(dry_run)),
+        Command::Sync => finish("sync", "Sync failed", json, sync::run_sync(dry_run)),
+        Command::Install(args) => finish(
+            "install",
+            "Install failed",
+            json,
+            install::run_install(&args.source, &args.options(), dry_run),
+        ),
+        Command::Uninstall(args) => finish(
+            "uninstall",
+            "Uninstall failed",
+            json,
+            uninstall::run_uninstall(&args.app, args.options(), dry_run),
+        ),
+        Command::Info { app } => finish("info", "Info failed", json, info::run_info(&app)),
+        Command::Update { action } => match action.unwrap_or(UpdateCommand::Check) {
+            UpdateCommand::Check => finish(
+                "update check",
+                "Update check failed",
+                json,
+                update::run_update_check(),
+            ),
+            UpdateCommand::Apply => finish(
+                "update apply",
+                "Update failed",
+                json,
+                update::run_update_apply(dry_run),
+            ),
+            UpdateCommand::Rollback { app } => finish(
+                "update rollback",
+                "Rollback failed",
+                json,
+                update::run_rollback(&app, dry_run),
+            ),
+        },
+        Command::Versions { action } => match action {
+            VersionsCommand::List { app } => finish(
+                "versions list",
+                "Failed to list versions",
+                json,
+                run_list_versions(app.as_deref()),
+            ),
+            VersionsCommand::Switch { app, version } => finish(
+                "versions switch",
+                "Version switch failed",
+                json,
+                run_switch_version(&app, &version, dry_run),
+            ),
+            VersionsCommand::Remove { app, version } => finish(
+                "versions remove",
+                "Version removal failed",
+                json,
+                run_remove_version(&app, &version, dry_run),
+            ),
+            VersionsCommand::Pin { app, version } => finish(
+                "versions pin",
+                "Version pin failed",
+                json,
+                run_pin_version(&app, version.as_deref(), dry_run),
+            ),
+            VersionsCommand::Unpin { app } => finish(
+                "versions unpin",
+                "Version unpin failed",
+                json,
+                run_unpin_version(&app, dry_run),
+            ),
+            VersionsCommand::Channel { app, channel } => finish(
+                "versions channel",
+                "Channel change failed",
+                json,
+                run_set_channel(&app, &channel, dry_run),
+            ),
+        },
+        Command::Trust { action } => match action {
+            TrustCommand::List => finish(
+                "trust list",
+                "Trust command failed",
+                json,
+                trust::run_trust_list(),
+            ),
+            TrustCommand::Pin { app, fingerprint } => finish(
+                "trust pin",
+                "Trust command failed",
+                json,
+                trust::run_trust_pin(&app, fingerprint.as_deref(), dry_run),
+            ),
+            TrustCommand::Revoke { app } => finish(
+                "trust revoke",
+                "Trust command failed",
+                json,
+                trust::run_trust_revoke(&app, dry_run),
+            ),
+        },
+        Command::Quarantine { action } => match action {
+            QuarantineCommand::List => finish(
+                "quarantine list",
+                "Quarantine command failed",
+                json,
+                quarantine::run_quarantine_list(),
+            ),
+            QuarantineCommand::Show { id } => finish(
+                "quarantine show",
+                "Quarantine command failed",
+                json,
+                quarantine::run_quarantine_show(&id),
+            ),
+            QuarantineCommand::Release { id } => finish(
+                "quarantine release",
+                "Quarantine command failed",
+                json,
+                quarantine::run_quarantine_release(&id, dry_run),
+            ),
+            QuarantineCommand::Purge { id, .. } => finish(
+                "quarantine purge",
+                "Quarantine command failed",
+                json,
+                quarantine::run_quarantine_purge(id.as_deref(), dry_run),
+            ),
+        },
+        Command::Doctor { fix } => finish("doctor", "Doctor failed", json, doctor::run_doctor(fix, dry_run)),
+        Command::Gc => finish("gc", "Garbage collection failed", json, gc::run_gc(dry_run)),
+        Command::Audit(args) => finish(
+            "audit",
+            "Failed to read audit log",
+            json,
+            audit::run_audit(&args.filter()),
+        ),
+        // Completion scripts are not data; `--json` does not apply.
+        Command::Completions { shell } => {
+            cli::print_completions(shell);
+            ExitCode::SUCCESS
         }
     }
 }
 
-fn run_and_report(f: fn() -> std::io::Result<()>) -> ExitCode {
-    match f() {
-        Ok(()) => ExitCode::SUCCESS,
-        Err(err) => {
-            eprintln!("❌ {}", err);
-            ExitCode::FAILURE
+/// Takes the global lock, or returns `None` if the lock file cannot be
+/// created, as for users who may not change anything anyway.
+fn acquire_global_lock(
+    config: &config::Config,
+) -> Result<Option<lock::LockGuard>, lock::LockError> {
+    match lock::Lock::global(config).acquire() {
+        Ok(guard) => Ok(Some(guard)),
+        Err(lock::LockError::Io(e)) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            tracing::debug!("Not taking the global lock: {}", e);
+            Ok(None)
         }
+        Err(e) => Err(e),
     }
 }
 
-fn run_update() -> ExitCode {
-    let args: Vec<String> = env::args().collect();
-    let apply = args.iter().any(|a| a == "--apply");
-    let dry_run = args.iter().any(|a| a == "--dry-run");
-    let rollback = args.iter().find(|a| a.starts_with("--rollback="));
-    let switch = args.iter().find(|a| a.starts_with("--switch="));
-
-    if let Some(rollback_arg) = rollback {
-        if let Some(app_name) = rollback_arg.strip_prefix("--rollback=") {
-            match update::run_rollback(app_name) {
-                Ok(()) => ExitCode::SUCCESS,
-                Err(e) => {
-                    eprintln!("❌ Rollback failed: {}", e);
-                    ExitCode::FAILURE
-                }
-            }
-        } else {
-            eprintln!("❌ Invalid rollback syntax. Use --rollback=<app_name>");
-            ExitCode::from(2)
+/// Completes or undoes version operations a crashed run left in the
+/// journal, before this run changes anything.
+fn recover_interrupted(config: &config::Config) {
+    match VersionManager::new(config.clone()).recover() {
+        Ok(recovered) if !recovered.is_empty() => {
+            tracing::info!("Recovered {} interrupted operation(s)", recovered.len());
         }
-    } else if let Some(switch_arg) = switch {
-        if let Some(app_version) = switch_arg.strip_prefix("--switch=") {
-            if let Some((app_name, version)) = app_version.split_once(':') {
-                match run_switch_version(app_name, version) {
-                    Ok(()) => ExitCode::SUCCESS,
-                    Err(e) => {
-                        eprintln!("❌ Version switch failed: {}", e);
-                        ExitCode::FAILURE
-                    }
-                }
-            } else {
-                eprintln!("❌ Invalid switch syntax. Use --switch=<app_name>:<version>");
-                ExitCode::from(2)
-            }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to recover interrupted operations: {}", e),
+    }
+}
+
+/// Versions of one app, as listed by `versions list`.
+#[derive(Serialize)]
+struct AppVersions {
+    app: String,
+    active_version: Option<String>,
+    pinned_version: Option<String>,
+    /// Every installed version; only filled in when a single app is listed.
+    versions: Vec<VersionInfo>,
+}
+
+/// Result of `versions switch` and `versions remove`.
+#[derive(Serialize)]
+struct VersionChange {
+    app: String,
+    version: String,
+}
+
+impl output::Report for VersionChange {}
+
+/// Result of `versions unpin`.
+#[derive(Serialize)]
+struct VersionUnpin {
+    app: String,
+    /// Version the app was pinned to; `None` if it was not pinned.
+    version: Option<String>,
+}
+
+impl output::Report for VersionUnpin {
+    fn outcome(&self) -> output::Outcome {
+        if self.version.is_some() {
+            output::Outcome::Success
         } else {
-            eprintln!("❌ Invalid switch syntax. Use --switch=<app_name>:<version>");
-            ExitCode::from(2)
-        }
-    } else if apply {
-        match update::run_update_apply(dry_run) {
-            Ok(()) => ExitCode::SUCCESS,
-            Err(e) => {
-                eprintln!("❌ Update failed: {}", e);
-                ExitCode::FAILURE
-            }
-        }
-    } else {
-        match update::run_update_check() {
-            Ok(()) => ExitCode::SUCCESS,
-            Err(e) => {
-                eprintln!("❌ Update check failed: {}", e);
-                ExitCode::FAILURE
-            }
+            output::Outcome::NothingToDo
         }
     }
 }
 
-fn run_versions() -> ExitCode {
-    let args: Vec<String> = env::args().collect();
-    let subcommand = args.get(2).map(|s| s.as_str());
+/// Result of `versions channel`.
+#[derive(Serialize)]
+struct ChannelChange {
+    app: String,
+    previous: Option<String>,
+    channel: Option<String>,
+}
 
-    match subcommand {
-        Some("list") => {
-            let app_name = args.get(3).map(|s| s.as_str());
-            match run_list_versions(app_name) {
-                Ok(()) => ExitCode::SUCCESS,
-                Err(e) => {
-                    eprintln!("❌ Failed to list versions: {}", e);
-                    ExitCode::FAILURE
-                }
-            }
-        }
-        Some("switch") => {
-            if let (Some(app_name), Some(version)) = (args.get(3), args.get(4)) {
-                match run_switch_version(app_name, version) {
-                    Ok(()) => ExitCode::SUCCESS,
-                    Err(e) => {
-                        eprintln!("❌ Version switch failed: {}", e);
-                        ExitCode::FAILURE
-                    }
-                }
-            } else {
-                eprintln!("❌ Usage: appiman versions switch <app_name> <version>");
-                ExitCode::from(2)
-            }
-        }
-        Some("remove") => {
-            if let (Some(app_name), Some(version)) = (args.get(3), args.get(4)) {
-                match run_remove_version(app_name, version) {
-                    Ok(()) => ExitCode::SUCCESS,
-                    Err(e) => {
-                        eprintln!("❌ Version removal failed: {}", e);
-                        ExitCode::FAILURE
-                    }
-                }
-            } else {
-                eprintln!("❌ Usage: appiman versions remove <app_name> <version>");
-                ExitCode::from(2)
-            }
-        }
-        _ => {
-            println!("Usage: appiman versions <subcommand>");
-            println!();
-            println!("Subcommands:");
-            println!("  list [app_name]    - List versions for an app (or all apps)");
-            println!("  switch <app> <ver> - Switch app to specified version");
-            println!("  remove <app> <ver> - Remove a specific version");
-            ExitCode::from(2)
+impl output::Report for ChannelChange {
+    fn outcome(&self) -> output::Outcome {
+        if self.previous == self.channel {
+            output::Outcome::NothingToDo
+        } else {
+            output::Outcome::Success
         }
     }
 }
 
-fn run_list_versions(app_name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::core::VersionManager;
-
+fn run_list_versions(app_name: Option<&str>) -> Result<Vec<AppVersions>, Box<dyn std::error::Error>> {
     let config = crate::config::Config::load()?;
     let version_manager = VersionManager::new(config);
 
     if let Some(app) = app_name {
         let versions = version_manager.list_versions(app)?;
-        println!("Versions for {}:", app);
-        for version in versions {
+        let pinned = version_manager.pinned_version(app);
+        say!("Versions for {}:", app);
+        for version in &versions {
             let active = if version.is_active { " (active)" } else { "" };
-            println!("  {}{} - installed {}", version.version, active, version.installed_at.format("%Y-%m-%d %H:%M:%S"));
+            let pin = if pinned.as_deref() == Some(version.version.as_str()) { " 📌 pinned" } else { "" };
+            let pre = if version.parsed().is_prerelease() { " (prerelease)" } else { "" };
+            say!("  {}{}{}{} - installed {}", version.version, pre, active, pin, version.installed_at.format("%Y-%m-%d %H:%M:%S"));
         }
+        Ok(vec![AppVersions {
+            app: app.to_string(),
+            active_version: version_manager.get_current_version(app)?,
+            pinned_version: pinned,
+            versions,
+        }])
     } else {
-        let apps = version_manager.list_apps()?;
-        println!("Registered applications:");
-        for app in apps {
-            let current = version_manager.get_current_version(&app)?.unwrap_or_else(|| "none".to_string());
-            println!("  {} -> {}", app, current);
+        let mut listed = Vec::new();
+        say!("Registered applications:");
+        for app in version_manager.list_apps()? {
+            let current = version_manager.get_current_version(&app)?;
+            let pinned = version_manager.pinned_version(&app);
+            let pin = if pinned.is_some() { " 📌" } else { "" };
+            say!("  {} -> {}{}", app, current.as_deref().unwrap_or("none"), pin);
+            listed.push(AppVersions {
+                app,
+                active_version: current,
+                pinned_version: pinned,
+                versions: Vec::new(),
+            });
         }
+        Ok(listed)
     }
-
-    Ok(())
 }
 
-fn run_switch_version(app_name: &str, version: &str) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::core::VersionManager;
-
+fn run_switch_version(
+    app_name: &str,
+    version: &str,
+    dry_run: bool,
+) -> Result<VersionChange, Box<dyn std::error::Error>> {
     let config = crate::config::Config::load()?;
-    let version_manager = VersionManager::new(config);
+    let audit = audit::AuditLog::from_config(&config);
+    let version_manager = VersionManager::new(config).with_audit(audit);
+    let change = VersionChange {
+        app: app_name.to_string(),
+        version: version.to_string(),
+    };
+    if dry_run {
+        if !version_manager.list_versions(app_name)?.iter().any(|v| v.version == version) {
+            return Err(format!("Version not found: {}", version).into());
+        }
+        say!("[DRY RUN] Would switch {} to version {}", app_name, version);
+        return Ok(change);
+    }
     version_manager.switch_version(app_name, version)?;
-    println!("✅ Switched {} to version {}", app_name, version);
-    Ok(())
+    say!("✅ Switched {} to version {}", app_name, version);
+    Ok(change)
 }
 
-fn run_remove_version(app_name: &str, version: &str) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::core::VersionManager;
-
+fn run_remove_version(
+    app_name: &str,
+    version: &str,
+    dry_run: bool,
+) -> Result<VersionChange, Box<dyn std::error::Error>> {
     let config = crate::config::Config::load()?;
-    let version_manager = VersionManager::new(config);
+    let audit = audit::AuditLog::from_config(&config);
+    let version_manager = VersionManager::new(config).with_audit(audit);
+    let change = VersionChange {
+        app: app_name.to_string(),
+        version: version.to_string(),
+    };
+    if dry_run {
+        if !version_manager.list_versions(app_name)?.iter().any(|v| v.version == version) {
+            return Err(format!("Version not found: {}", version).into());
+        }
+        say!("[DRY RUN] Would remove {} version {}", app_name, version);
+        return Ok(change);
+    }
     version_manager.remove_version(app_name, version)?;
-    println!("✅ Removed {} version {}", app_name, version);
-    Ok(())
+    say!("✅ Removed {} version {}", app_name, version);
+    Ok(change)
+}
+
+fn run_pin_version(
+    app_name: &str,
+    version: Option<&str>,
+    dry_run: bool,
+) -> Result<VersionChange, Box<dyn std::error::Error>> {
+    let config = crate::config::Config::load()?;
+    let audit = audit::AuditLog::from_config(&config);
+    let version_manager = VersionManager::new(config).with_audit(audit);
+    if dry_run {
+        let version = match version {
+            Some(version) => version.to_string(),
+            None => version_manager
+                .get_current_version(app_name)?
+                .ok_or_else(|| format!("{} has no active version", app_name))?,
+        };
+        if !version_manager.list_versions(app_name)?.iter().any(|v| v.version == version) {
+            return Err(format!("Version not found: {}", version).into());
+        }
+        say!("[DRY RUN] Would pin {} to version {}", app_name, version);
+        return Ok(VersionChange {
+            app: app_name.to_string(),
+            version,
+        });
+    }
+    let version = version_manager.pin_version(app_name, version)?;
+    say!("📌 Pinned {} to version {}", app_name, version);
+    Ok(VersionChange {
+        app: app_name.to_string(),
+        version,
+    })
+}
+
+fn run_unpin_version(app_name: &str, dry_run: bool) -> Result<VersionUnpin, Box<dyn std::error::Error>> {
+    let config = crate::config::Config::load()?;
+    let audit = audit::AuditLog::from_config(&config);
+    let version_manager = VersionManager::new(config).with_audit(audit);
+    let version = if dry_run {
+        let pinned = version_manager.load_app_metadata(app_name)?.pinned_version;
+        if let Some(version) = &pinned {
+            say!("[DRY RUN] Would unpin {} from version {}", app_name, version);
+        }
+        pinned
+    } else {
+        let unpinned = version_manager.unpin_version(app_name)?;
+        if let Some(version) = &unpinned {
+            say!("✅ Unpinned {} from version {}", app_name, version);
+        }
+        unpinned
+    };
+    if version.is_none() {
+        say!("{} is not pinned", app_name);
+    }
+    Ok(VersionUnpin {
+        app: app_name.to_string(),
+        version,
+    })
 }
 
-fn print_help() {
-    println!("Usage: appiman <command> [options]");
-    println!();
-    println!("Commands:");
-    println!("  init     - Create dir structure and install units/scripts");
-    println!("  enable   - Enable and start systemd .path units");
-    println!("  disable  - Disable and stop systemd .path units");
-    println!("  status   - Show systemd status of watchers and AppImage inventory");
-    println!("  ingest   - Move user-downloaded AppImages into staging");
-    println!("  scan     - Run AppImage re-index manually");
-    println!("  sync     - Ingest then register AppImages");
-    println!("  update   - Check for and apply AppImage updates");
-    println!("  versions - Manage AppImage versions");
-    println!("  clean    - Remove legacy AppImages and artifacts");
-    println!("  help     - Show this help message");
-    println!();
-    println!("Options:");
-    println!("  --json        - Output status in JSON format");
-    println!("  --apply       - Apply available updates (with update command)");
-    println!("  --dry-run     - Show what would be done without making changes");
-    println!("  --rollback=<name> - Rollback specified AppImage to previous version");
-    println!("  --switch=<app>:<version> - Switch specified AppImage to a different version");
-    println!();
-    println!("For more information, visit: https://github.com/derungo/appiman");
+fn run_set_channel(
+    app_name: &str,
+    channel: &str,
+    dry_run: bool,
+) -> Result<ChannelChange, Box<dyn std::error::Error>> {
+    let config = crate::config::Config::load()?;
+    let audit = audit::AuditLog::from_config(&config);
+    let version_manager = VersionManager::new(config).with_audit(audit);
+    let channel = Some(channel.to_ascii_lowercase()).filter(|c| c != DEFAULT_CHANNEL);
+    let previous = if dry_run {
+        let previous = version_manager.load_app_metadata(app_name)?.channel;
+        say!(
+            "[DRY RUN] Would switch {} to the {} channel",
+            app_name,
+            channel.as_deref().unwrap_or(DEFAULT_CHANNEL)
+        );
+        previous
+    } else {
+        let previous = version_manager.set_channel(app_name, channel.as_deref())?;
+        say!(
+            "✅ {} now follows the {} channel",
+            app_name,
+            channel.as_deref().unwrap_or(DEFAULT_CHANNEL)
+        );
+        previous
+    };
+    Ok(ChannelChange {
+        app: app_name.to_string(),
+        previous,
+        channel,
+    })
 }
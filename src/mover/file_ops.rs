@@ -1,10 +1,16 @@
+use serde::Serialize;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
 
+use crate::audit::{AuditEvent, AuditLog, AuditOperation, AuditOutcome};
 use crate::core::AppImage;
 use crate::mover::conflict::handle_collision;
 use crate::mover::scanner::Scanner;
+use crate::mover::settle::{Settle, SettleError};
+use crate::output::{Outcome, Report, serialize_path_reasons};
+use crate::policy::{PolicyAction, PolicyEnforcer, PolicyError};
 
 impl From<crate::mover::conflict::CollisionError> for MoveError {
     fn from(err: crate::mover::conflict::CollisionError) -> Self {
@@ -25,12 +31,20 @@ pub enum MoveError {
 
     #[error("Collision resolution failed for {path}: {reason}")]
     CollisionFailed { path: PathBuf, reason: String },
+
+    #[error("Policy error: {0}")]
+    Policy(#[from] PolicyError),
 }
 
+#[derive(Serialize)]
 pub struct MoveReport {
     pub moved: Vec<PathBuf>,
-    #[allow(dead_code)]
-    pub skipped: Vec<PathBuf>,
+    /// Images left for a later run, such as downloads still in progress.
+    #[serde(serialize_with = "serialize_path_reasons")]
+    pub skipped: Vec<(PathBuf, String)>,
+    #[serde(serialize_with = "serialize_path_reasons")]
+    pub quarantined: Vec<(PathBuf, String)>,
+    #[serde(serialize_with = "serialize_path_reasons")]
     pub errors: Vec<(PathBuf, String)>,
 }
 
@@ -39,6 +53,7 @@ impl MoveReport {
         MoveReport {
             moved: Vec::new(),
             skipped: Vec::new(),
+            quarantined: Vec::new(),
             errors: Vec::new(),
         }
     }
@@ -56,10 +71,22 @@ impl MoveReport {
     }
 }
 
+impl Report for MoveReport {
+    fn outcome(&self) -> Outcome {
+        Outcome::of_batch(
+            self.moved.len() + self.quarantined.len(),
+            self.errors.len(),
+        )
+    }
+}
+
 pub struct Mover {
     pub source_dir: PathBuf,
     pub dest_dir: PathBuf,
     pub dry_run: bool,
+    pub policy: Option<PolicyEnforcer>,
+    pub audit: Option<AuditLog>,
+    pub settle_window: Option<Duration>,
 }
 
 impl Mover {
@@ -68,15 +95,34 @@ impl Mover {
             source_dir,
             dest_dir,
             dry_run: false,
+            policy: None,
+            audit: None,
+            settle_window: None,
         }
     }
 
-    #[allow(dead_code)]
     pub fn with_dry_run(mut self, dry_run: bool) -> Self {
         self.dry_run = dry_run;
         self
     }
 
+    pub fn with_policy(mut self, policy: PolicyEnforcer) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn with_audit(mut self, audit: Option<AuditLog>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Only moves images that are not being downloaded or written and have
+    /// not changed for `window`.
+    pub fn with_settle_window(mut self, window: Duration) -> Self {
+        self.settle_window = Some(window);
+        self
+    }
+
     pub fn move_appimages(&self, appimages: &[AppImage]) -> Result<MoveReport, MoveError> {
         info!(
             "Moving {} AppImages from {:?} to {:?}",
@@ -92,14 +138,67 @@ impl Mover {
             std::fs::create_dir_all(&self.dest_dir)?;
         }
 
+        let settle = self.settle_window.map(Settle::new);
         for app in appimages {
+            let snapshot = match settle.as_ref().map(|settle| settle.check(&app.path)) {
+                Some(Ok(snapshot)) => Some(snapshot),
+                Some(Err(SettleError::Io(e))) => {
+                    warn!("Failed to check {:?}: {}", app.path, e);
+                    report.errors.push((app.path.clone(), e.to_string()));
+                    continue;
+                }
+                Some(Err(e)) => {
+                    info!("Skipping {:?}: {}", app.path, e);
+                    report.skipped.push((app.path.clone(), e.to_string()));
+                    continue;
+                }
+                None => None,
+            };
+
+            // Hash before moving: afterwards the source path is gone.
+            let checksum = self
+                .audit
+                .as_ref()
+                .filter(|_| !self.dry_run)
+                .and_then(|_| app.get_checksum().ok());
+
+            match self.apply_policy(app) {
+                Ok(Some(reason)) => {
+                    self.audit_ingest(app, checksum, AuditOutcome::Quarantined, reason.clone());
+                    report.quarantined.push((app.path.clone(), reason));
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Policy check failed for {:?}: {}", app.path, e);
+                    self.audit_ingest(app, checksum, AuditOutcome::Failure, e.to_string());
+                    report.errors.push((app.path.clone(), e.to_string()));
+                    continue;
+                }
+            }
+
+            // Hashing and policy checks take a while; the file must still be
+            // the one that was checked.
+            if let Some(Err(e)) = snapshot.map(|snapshot| snapshot.verify(&app.path)) {
+                info!("Skipping {:?}: {}", app.path, e);
+                report.skipped.push((app.path.clone(), e.to_string()));
+                continue;
+            }
+
             match self.move_single_appimage(app) {
                 Ok(dest) => {
-                    info!("Moved {:?} to {:?}", app.path, dest);
+                    info!(operation = "ingest", "Moved {:?} to {:?}", app.path, dest);
+                    self.audit_ingest(
+                        app,
+                        checksum,
+                        AuditOutcome::Success,
+                        format!("moved to {}", dest.display()),
+                    );
                     report.moved.push(dest);
                 }
                 Err(e) => {
-                    warn!("Failed to move {:?}: {}", app.path, e);
+                    warn!(operation = "ingest", "Failed to move {:?}: {}", app.path, e);
+                    self.audit_ingest(app, checksum, AuditOutcome::Failure, e.to_string());
                     report.errors.push((app.path.clone(), e.to_string()));
                 }
             }
@@ -122,6 +221,58 @@ impl Mover {
         self.move_appimages(&appimages)
     }
 
+    /// Quarantines `app` if the policy does not allow it, returning the reason.
+    fn apply_policy(&self, app: &AppImage) -> Result<Option<String>, MoveError> {
+        let Some(policy) = &self.policy else {
+            return Ok(None);
+        };
+        let user = self.source_user(app);
+
+        if self.dry_run {
+            let (decision, _) = policy.evaluate(app, user.as_deref(), None)?;
+            if decision.action != PolicyAction::Allow {
+                info!("[DRY RUN] Would quarantine {:?}: {}", app.path, decision.reason);
+                return Ok(Some(decision.reason));
+            }
+            return Ok(None);
+        }
+
+        Ok(policy
+            .enforce(app, user.as_deref(), None)?
+            .map(|record| record.reason))
+    }
+
+    fn audit_ingest(
+        &self,
+        app: &AppImage,
+        checksum: Option<String>,
+        outcome: AuditOutcome,
+        detail: String,
+    ) {
+        let Some(audit) = self.audit.as_ref().filter(|_| !self.dry_run) else {
+            return;
+        };
+        let user = self.source_user(app);
+        audit.record(
+            AuditEvent::new(AuditOperation::Ingest)
+                .with_path(&app.path)
+                .with_source_user(user.as_deref())
+                .with_checksums(None, checksum)
+                .with_outcome(outcome, Some(detail)),
+        );
+    }
+
+    /// The user whose home directory `app` was found in.
+    fn source_user(&self, app: &AppImage) -> Option<String> {
+        app.path
+            .strip_prefix(&self.source_dir)
+            .ok()?
+            .components()
+            .next()
+            .filter(|_| app.path.parent() != Some(self.source_dir.as_path()))
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+    }
+
     fn move_single_appimage(&self, app: &AppImage) -> Result<PathBuf, MoveError> {
         let dest = self.determine_destination(app)?;
 
@@ -189,7 +340,9 @@ impl Mover {
     }
 }
 
-fn is_cross_device_error(err: &std::io::Error) -> bool {
+/// Whether a rename failed because `source` and `dest` are on different
+/// filesystems.
+pub(crate) fn is_cross_device_error(err: &std::io::Error) -> bool {
     #[cfg(unix)]
     {
         err.raw_os_error() == Some(18)
@@ -205,11 +358,12 @@ fn is_cross_device_error(err: &std::io::Error) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::testing::fake_appimage;
     use std::fs;
     use tempfile::TempDir;
 
     fn create_appimage(path: &Path) {
-        fs::write(path, b"fake appimage").unwrap();
+        fs::write(path, fake_appimage(b"")).unwrap();
     }
 
     #[test]
@@ -234,6 +388,94 @@ mod tests {
         assert!(dest.join("Test.AppImage").exists());
     }
 
+    #[test]
+    fn mover_quarantines_images_denied_by_policy() {
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("home");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(home.join("alice")).unwrap();
+        fs::create_dir_all(home.join("guest")).unwrap();
+
+        let allowed = home.join("alice/Test.AppImage");
+        let denied = home.join("guest/Test.AppImage");
+        create_appimage(&allowed);
+        create_appimage(&denied);
+
+        let mut config = crate::config::Config::default();
+        config.directories.quarantine = temp.path().join("quarantine").display().to_string();
+        config.policy = toml::from_str(
+            "[[rules]]\naction = \"deny\"\nuser = \"guest\"\nreason = \"guest downloads\"\n",
+        )
+        .unwrap();
+
+        let audit = AuditLog::new(temp.path().join("audit.jsonl"));
+        let mover = Mover::new(home.clone(), dest.clone())
+            .with_policy(PolicyEnforcer::from_config(&config))
+            .with_audit(Some(audit.clone()));
+        let report = mover
+            .move_appimages(&[
+                AppImage::new(allowed.clone()).unwrap(),
+                AppImage::new(denied.clone()).unwrap(),
+            ])
+            .unwrap();
+
+        assert_eq!(report.moved, vec![dest.join("Test.AppImage")]);
+        assert_eq!(report.quarantined, vec![(denied.clone(), "guest downloads".to_string())]);
+        assert!(!denied.exists());
+
+        let records = crate::quarantine::Quarantine::new(config.quarantine_dir())
+            .list()
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].user.as_deref(), Some("guest"));
+        assert_eq!(records[0].action, PolicyAction::Deny);
+
+        let events = audit
+            .read(&crate::audit::AuditFilter::default())
+            .unwrap();
+        let outcomes: Vec<_> = events
+            .iter()
+            .map(|e| (e.source_user.as_deref(), e.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (Some("alice"), AuditOutcome::Success),
+                (Some("guest"), AuditOutcome::Quarantined)
+            ]
+        );
+        assert_eq!(events[1].after_checksum.as_deref(), Some(records[0].sha256.as_str()));
+    }
+
+    #[test]
+    fn mover_leaves_downloads_in_progress() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("source");
+        let dest = temp.path().join("dest");
+        fs::create_dir_all(&source).unwrap();
+
+        let app = source.join("Test.AppImage");
+        create_appimage(&app);
+        fs::write(source.join("Test.AppImage.part"), b"").unwrap();
+
+        let mover =
+            Mover::new(source.clone(), dest.clone()).with_settle_window(Duration::ZERO);
+        let report = mover
+            .move_appimages(&[AppImage::new(app.clone()).unwrap()])
+            .unwrap();
+
+        assert!(report.moved.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.outcome(), Outcome::NothingToDo);
+        assert!(app.exists());
+
+        fs::remove_file(source.join("Test.AppImage.part")).unwrap();
+        let report = mover
+            .move_appimages(&[AppImage::new(app.clone()).unwrap()])
+            .unwrap();
+        assert_eq!(report.moved, vec![dest.join("Test.AppImage")]);
+    }
+
     #[test]
     fn mover_handles_collisions() {
         let temp = TempDir::new().unwrap();
@@ -307,4 +549,22 @@ mod tests {
         let err = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
         assert!(!is_cross_device_error(&err));
     }
+
+    #[test]
+    fn report_outcome_and_json() {
+        let mut report = MoveReport::new();
+        assert_eq!(report.outcome(), Outcome::NothingToDo);
+
+        report.moved.push(PathBuf::from("/raw/a.AppImage"));
+        assert_eq!(report.outcome(), Outcome::Success);
+
+        report
+            .errors
+            .push((PathBuf::from("/home/u/b.AppImage"), "denied".to_string()));
+        assert_eq!(report.outcome(), Outcome::PartialFailure);
+        assert_eq!(
+            serde_json::to_value(&report).unwrap()["errors"],
+            serde_json::json!([{"path": "/home/u/b.AppImage", "reason": "denied"}])
+        );
+    }
 }
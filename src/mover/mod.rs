@@ -1,6 +1,8 @@
 pub mod conflict;
 pub mod file_ops;
 pub mod scanner;
+pub mod settle;
+pub mod watch;
 
-pub use file_ops::Mover;
+pub use file_ops::{MoveReport, Mover};
 pub use scanner::Scanner;
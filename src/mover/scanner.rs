@@ -1,8 +1,12 @@
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 use walkdir::{DirEntry, WalkDir};
 
+use crate::config::Ingest;
 use crate::core::AppImage;
+use crate::mover::watch::WatchPlan;
+use tracing::{debug, warn};
 
 #[derive(Debug, Error)]
 pub enum ScanError {
@@ -19,6 +23,9 @@ pub enum ScanError {
 pub struct Scanner {
     pub home_root: PathBuf,
     pub exclude_dirs: Vec<PathBuf>,
+    /// Which folders of each home to sweep; the whole of every home if
+    /// `None`.
+    pub ingest: Option<Ingest>,
 }
 
 impl Scanner {
@@ -28,14 +35,22 @@ impl Scanner {
         Scanner {
             home_root,
             exclude_dirs,
+            ingest: None,
         }
     }
 
+    /// Sweeps only the watched folders of users who did not opt out.
+    pub fn with_ingest(mut self, ingest: Ingest) -> Self {
+        self.ingest = Some(ingest);
+        self
+    }
+
     #[allow(dead_code)]
     pub fn with_excludes(home_root: PathBuf, exclude_dirs: Vec<PathBuf>) -> Self {
         Scanner {
             home_root,
             exclude_dirs,
+            ingest: None,
         }
     }
 
@@ -48,24 +63,56 @@ impl Scanner {
             ));
         }
 
-        for entry in WalkDir::new(&self.home_root)
+        let Some(ingest) = &self.ingest else {
+            self.walk(&self.home_root, None, &mut appimages);
+            return Ok(appimages);
+        };
+
+        for home in self.find_user_dirs()? {
+            let plan = match WatchPlan::for_user(ingest, &home) {
+                Ok(Some(plan)) => plan,
+                Ok(None) => {
+                    debug!("Skipping {}: opted out of ingestion", home.display());
+                    continue;
+                }
+                // Without the user's settings their wishes are unknown.
+                Err(e) => {
+                    warn!("Skipping {}: {}", home.display(), e);
+                    continue;
+                }
+            };
+            for folder in &plan.folders {
+                self.walk(folder, Some(&plan), &mut appimages);
+            }
+        }
+
+        // Watched folders may overlap.
+        let mut seen = HashSet::new();
+        appimages.retain(|app| seen.insert(app.path.clone()));
+        Ok(appimages)
+    }
+
+    fn walk(&self, root: &Path, plan: Option<&WatchPlan>, appimages: &mut Vec<AppImage>) {
+        for entry in WalkDir::new(root)
             .follow_links(false)
             .into_iter()
-            .filter_entry(|e| !self.is_excluded(e))
+            .filter_entry(|e| {
+                !self.is_excluded(e) && !plan.is_some_and(|plan| plan.is_excluded(e.path()))
+            })
             .filter_map(|e| e.ok())
         {
             if entry.file_type().is_file()
                 && let Some(ext) = entry.path().extension()
-                    && ext.eq_ignore_ascii_case("AppImage")
-                        && let Ok(app) = AppImage::new(entry.path().to_path_buf()) {
-                            appimages.push(app);
-                        }
+                && ext.eq_ignore_ascii_case("AppImage")
+            {
+                match AppImage::new(entry.path().to_path_buf()) {
+                    Ok(app) => appimages.push(app),
+                    Err(e) => warn!("Skipping {}: {}", entry.path().display(), e),
+                }
+            }
         }
-
-        Ok(appimages)
     }
 
-    #[allow(dead_code)]
     pub fn find_user_dirs(&self) -> Result<Vec<PathBuf>, ScanError> {
         if !self.home_root.exists() {
             return Err(ScanError::HomeDirNotFound(
@@ -103,6 +150,7 @@ impl Scanner {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::testing::fake_appimage;
     use std::fs;
     use tempfile::TempDir;
 
@@ -120,8 +168,8 @@ mod tests {
         let app1 = alice.join("App1.AppImage");
         let app2 = bob.join("App2.AppImage");
         let app3 = bob.join("not-an-app.txt");
-        fs::write(&app1, b"app1").unwrap();
-        fs::write(&app2, b"app2").unwrap();
+        fs::write(&app1, fake_appimage(b"app1")).unwrap();
+        fs::write(&app2, fake_appimage(b"app2")).unwrap();
         fs::write(&app3, b"text").unwrap();
 
         let scanner = Scanner::new(home_root);
@@ -133,6 +181,21 @@ mod tests {
         assert!(!found.iter().any(|a| a.path == app3));
     }
 
+    #[test]
+    fn scanner_skips_renamed_non_appimages() {
+        let temp = TempDir::new().unwrap();
+        let home_root = temp.path().join("home");
+        let alice = home_root.join("alice");
+        fs::create_dir_all(&alice).unwrap();
+
+        fs::write(alice.join("Installer.AppImage"), b"#!/bin/sh\nrm -rf ~\n").unwrap();
+
+        let scanner = Scanner::new(home_root);
+        let found = scanner.find_appimages().unwrap();
+
+        assert!(found.is_empty());
+    }
+
     #[test]
     fn scanner_excludes_directories() {
         let temp = TempDir::new().unwrap();
@@ -141,7 +204,7 @@ mod tests {
         fs::create_dir_all(&cache_dir).unwrap();
 
         let app_in_cache = cache_dir.join("Cached.AppImage");
-        fs::write(&app_in_cache, b"cached").unwrap();
+        fs::write(&app_in_cache, fake_appimage(b"cached")).unwrap();
 
         let scanner = Scanner::new(home_root);
         let found = scanner.find_appimages().unwrap();
@@ -149,6 +212,42 @@ mod tests {
         assert_eq!(found.len(), 0);
     }
 
+    #[test]
+    fn scanner_with_ingest_sweeps_only_watched_folders() {
+        let temp = TempDir::new().unwrap();
+        let home_root = temp.path().join("home");
+        let alice = home_root.join("alice");
+        let bob = home_root.join("bob");
+        for dir in [
+            alice.join("Downloads/old"),
+            alice.join("Projects"),
+            bob.join("Downloads"),
+            bob.join(".config/appiman"),
+        ] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        let wanted = alice.join("Downloads/App.AppImage");
+        for path in [
+            &wanted,
+            &alice.join("Downloads/old/Old.AppImage"),
+            &alice.join("Projects/Built.AppImage"),
+            &bob.join("Downloads/Bob.AppImage"),
+        ] {
+            fs::write(path, fake_appimage(b"app")).unwrap();
+        }
+        fs::write(bob.join(".config/appiman/ingest.toml"), "enabled = false\n").unwrap();
+
+        let ingest = Ingest {
+            exclude: vec!["Downloads/old".to_string()],
+            ..Ingest::default()
+        };
+        let found = Scanner::new(home_root).with_ingest(ingest).find_appimages().unwrap();
+
+        let paths: Vec<_> = found.iter().map(|a| a.path.clone()).collect();
+        assert_eq!(paths, [wanted]);
+    }
+
     #[test]
     fn scanner_finds_user_dirs() {
         let temp = TempDir::new().unwrap();
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+/// Suffixes browsers give a download in progress. A sibling `<name><suffix>`
+/// means `<name>` is not complete yet.
+const PARTIAL_SUFFIXES: &[&str] = &[".crdownload", ".part", ".download"];
+
+#[derive(Debug, Error)]
+pub enum SettleError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("still downloading ({} exists)", .0.display())]
+    PartialDownload(PathBuf),
+
+    #[error("open for writing")]
+    OpenForWriting,
+
+    #[error("modified {}s ago", .0.as_secs())]
+    RecentlyModified(Duration),
+
+    #[error("changed while being ingested")]
+    Changed,
+}
+
+/// Size and modification time of a file when it was found settled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    len: u64,
+    modified: SystemTime,
+}
+
+impl Snapshot {
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Snapshot {
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+
+    /// Fails if the file changed since the snapshot was taken.
+    pub fn verify(&self, path: &Path) -> Result<(), SettleError> {
+        if Snapshot::of(path)? == *self {
+            Ok(())
+        } else {
+            Err(SettleError::Changed)
+        }
+    }
+}
+
+/// Tells files that are done downloading from ones a browser or another
+/// process may still be writing.
+pub struct Settle {
+    window: Duration,
+    writers: HashSet<PathBuf>,
+}
+
+impl Settle {
+    /// Files must be left alone for `window` to count as settled. Which
+    /// files are open for writing is read from `/proc` once, here.
+    pub fn new(window: Duration) -> Self {
+        Settle {
+            window,
+            writers: open_for_writing(Path::new("/proc")),
+        }
+    }
+
+    /// Returns the file's snapshot if it is settled.
+    pub fn check(&self, path: &Path) -> Result<Snapshot, SettleError> {
+        if let Some(partial) = partial_download(path) {
+            return Err(SettleError::PartialDownload(partial));
+        }
+        if self.writers.contains(&fs::canonicalize(path)?) {
+            return Err(SettleError::OpenForWriting);
+        }
+
+        let snapshot = Snapshot::of(path)?;
+        // A modification time in the future counts as just modified.
+        let age = SystemTime::now()
+            .duration_since(snapshot.modified)
+            .unwrap_or_default();
+        if age < self.window {
+            return Err(SettleError::RecentlyModified(age));
+        }
+        Ok(snapshot)
+    }
+}
+
+fn partial_download(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_string_lossy();
+    PARTIAL_SUFFIXES
+        .iter()
+        .map(|suffix| path.with_file_name(format!("{}{}", name, suffix)))
+        .find(|sibling| sibling.symlink_metadata().is_ok())
+}
+
+/// Paths some process holds open for writing, from the `fd` and `fdinfo`
+/// directories of every process below `proc_root`. Processes that cannot be
+/// inspected are left out.
+fn open_for_writing(proc_root: &Path) -> HashSet<PathBuf> {
+    let mut writers = HashSet::new();
+    let Ok(processes) = fs::read_dir(proc_root) else {
+        return writers;
+    };
+    for process in processes.flatten() {
+        if !process
+            .file_name()
+            .to_string_lossy()
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+        {
+            continue;
+        }
+        let process = process.path();
+        let Ok(fds) = fs::read_dir(process.join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let info = fs::read_to_string(process.join("fdinfo").join(fd.file_name()));
+            if info.is_ok_and(|info| is_writable(&info)) {
+                writers.insert(target);
+            }
+        }
+    }
+    writers
+}
+
+/// Whether the octal `flags` of an fdinfo entry include write access.
+fn is_writable(fdinfo: &str) -> bool {
+    fdinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| u32::from_str_radix(flags.trim(), 8).ok())
+        .is_some_and(|flags| flags & 0o3 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    #[test]
+    fn partial_and_recent_downloads_are_not_settled() {
+        let temp = TempDir::new().unwrap();
+        let image = temp.path().join("App.AppImage");
+        fs::write(&image, b"image").unwrap();
+        let settle = Settle::new(Duration::from_secs(60));
+
+        assert!(matches!(
+            settle.check(&image),
+            Err(SettleError::RecentlyModified(_))
+        ));
+
+        File::options()
+            .write(true)
+            .open(&image)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(120))
+            .unwrap();
+        let snapshot = settle.check(&image).unwrap();
+
+        let partial = temp.path().join("App.AppImage.crdownload");
+        fs::write(&partial, b"").unwrap();
+        match settle.check(&image) {
+            Err(SettleError::PartialDownload(path)) => assert_eq!(path, partial),
+            other => panic!("expected a partial download, got {:?}", other),
+        }
+
+        fs::write(&image, b"longer image").unwrap();
+        assert!(matches!(snapshot.verify(&image), Err(SettleError::Changed)));
+    }
+
+    #[test]
+    fn files_open_for_writing_are_not_settled() {
+        let temp = TempDir::new().unwrap();
+        let image = temp.path().join("App.AppImage");
+        let writer = File::create(&image).unwrap();
+        writer
+            .set_modified(SystemTime::now() - Duration::from_secs(120))
+            .unwrap();
+
+        assert!(matches!(
+            Settle::new(Duration::ZERO).check(&image),
+            Err(SettleError::OpenForWriting)
+        ));
+        drop(writer);
+        assert!(Settle::new(Duration::ZERO).check(&image).is_ok());
+        assert!(is_writable("pos:\t0\nflags:\t0100002\n"));
+        assert!(!is_writable("pos:\t0\nflags:\t0100000\n"));
+    }
+}
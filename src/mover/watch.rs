@@ -0,0 +1,267 @@
+use nix::libc;
+use serde::Deserialize;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::config::Ingest;
+use crate::core::wildcard_matches;
+
+/// Where a user's ingest settings live, relative to their home.
+pub const USER_CONFIG: &str = ".config/appiman/ingest.toml";
+
+/// Largest user settings file that is read.
+const MAX_USER_CONFIG_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("{} is not a regular file owned by the user", .0.display())]
+    UntrustedConfig(PathBuf),
+
+    #[error("{} is larger than 64 KiB", .0.display())]
+    ConfigTooLarge(PathBuf),
+
+    #[error("Invalid {}: {}", .0.display(), .1)]
+    InvalidConfig(PathBuf, String),
+}
+
+/// A user's `~/.config/appiman/ingest.toml`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserIngestConfig {
+    /// `false` keeps appiman out of the home entirely.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Replaces the system's watched folders.
+    pub watched_folders: Option<Vec<String>>,
+
+    /// Added to the system's exclude patterns.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl UserIngestConfig {
+    /// Reads the settings in `home`, if there are any. The file is read as
+    /// root, so it must be a regular file owned by the owner of `home`. It
+    /// is checked through the open descriptor, so the user cannot swap it
+    /// for a link or a special file after the check.
+    pub fn load(home: &Path) -> Result<Option<Self>, WatchError> {
+        let path = home.join(USER_CONFIG);
+        // A FIFO would block the open until someone writes to it
+        let file = match OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW | libc::O_NONBLOCK)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) if e.raw_os_error() == Some(libc::ELOOP) => {
+                return Err(WatchError::UntrustedConfig(path));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let metadata = file.metadata()?;
+        if !metadata.is_file() || metadata.uid() != fs::metadata(home)?.uid() {
+            return Err(WatchError::UntrustedConfig(path));
+        }
+        if metadata.len() > MAX_USER_CONFIG_BYTES {
+            return Err(WatchError::ConfigTooLarge(path));
+        }
+
+        // The file may still grow after the check
+        let mut content = String::new();
+        file.take(MAX_USER_CONFIG_BYTES + 1)
+            .read_to_string(&mut content)?;
+        if content.len() as u64 > MAX_USER_CONFIG_BYTES {
+            return Err(WatchError::ConfigTooLarge(path));
+        }
+        toml::from_str(&content)
+            .map(Some)
+            .map_err(|e| WatchError::InvalidConfig(path, e.message().to_string()))
+    }
+}
+
+/// The folders to sweep in one user's home and what to leave alone there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchPlan {
+    pub user: String,
+    pub home: PathBuf,
+    pub folders: Vec<PathBuf>,
+    pub exclude: Vec<String>,
+}
+
+impl WatchPlan {
+    /// Combines the system settings with the user's own; `None` if the
+    /// user opted out or the system opts them out.
+    pub fn for_user(ingest: &Ingest, home: &Path) -> Result<Option<Self>, WatchError> {
+        let user = home
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if ingest.opt_out_users.contains(&user) {
+            return Ok(None);
+        }
+
+        let mut folders = ingest.watched_folders.clone();
+        let mut exclude = ingest.exclude.clone();
+        if ingest.user_config
+            && let Some(own) = UserIngestConfig::load(home)?
+        {
+            if !own.enabled {
+                return Ok(None);
+            }
+            if let Some(own_folders) = own.watched_folders {
+                folders = own_folders;
+            }
+            exclude.extend(own.exclude);
+        }
+
+        Ok(Some(WatchPlan {
+            user,
+            home: home.to_path_buf(),
+            folders: folders
+                .iter()
+                .filter_map(|folder| contained_folder(home, folder))
+                .collect(),
+            exclude,
+        }))
+    }
+
+    /// Whether `path`, below the home, matches an exclude pattern.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.home).unwrap_or(path);
+        let relative = relative.to_string_lossy();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        self.exclude
+            .iter()
+            .any(|pattern| wildcard_matches(pattern, &relative) || wildcard_matches(pattern, &name))
+    }
+}
+
+/// `folder` below `home` if it exists and does not lead out of the home,
+/// by `..` or through a symlink.
+fn contained_folder(home: &Path, folder: &str) -> Option<PathBuf> {
+    let relative = Path::new(folder);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        warn!(
+            "Ignoring watched folder {:?} of {}: not below the home",
+            folder,
+            home.display()
+        );
+        return None;
+    }
+
+    let path = home.join(relative);
+    let resolved = fs::canonicalize(&path).ok()?;
+    if !resolved.starts_with(fs::canonicalize(home).ok()?) {
+        warn!(
+            "Ignoring watched folder {} of {}: it leads out of the home",
+            path.display(),
+            home.display()
+        );
+        return None;
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    #[test]
+    fn user_settings_narrow_widen_or_opt_out() {
+        let temp = TempDir::new().unwrap();
+        let alice = temp.path().join("alice");
+        for dir in ["Downloads", "Applications", "Projects", ".config/appiman"] {
+            fs::create_dir_all(alice.join(dir)).unwrap();
+        }
+        symlink("/etc", alice.join("etc")).unwrap();
+
+        let mut ingest = Ingest {
+            exclude: vec!["*.vm".to_string()],
+            ..Ingest::default()
+        };
+        let plan = WatchPlan::for_user(&ingest, &alice).unwrap().unwrap();
+        assert_eq!(plan.user, "alice");
+        assert_eq!(
+            plan.folders,
+            [alice.join("Downloads"), alice.join("Applications")]
+        );
+
+        fs::write(
+            alice.join(USER_CONFIG),
+            "watched_folders = [\"Projects\", \"../bob\", \"etc\", \"Missing\"]\nexclude = [\"Projects/build/*\"]\n",
+        )
+        .unwrap();
+        let plan = WatchPlan::for_user(&ingest, &alice).unwrap().unwrap();
+        assert_eq!(plan.folders, [alice.join("Projects")]);
+        assert!(plan.is_excluded(&alice.join("Projects/build/App.AppImage")));
+        assert!(plan.is_excluded(&alice.join("Projects/disk.vm")));
+        assert!(!plan.is_excluded(&alice.join("Projects/App.AppImage")));
+
+        // The system can ignore the user's file or opt the user out.
+        ingest.user_config = false;
+        let plan = WatchPlan::for_user(&ingest, &alice).unwrap().unwrap();
+        assert_eq!(plan.folders.len(), 2);
+        ingest.user_config = true;
+        ingest.opt_out_users = vec!["alice".to_string()];
+        assert_eq!(WatchPlan::for_user(&ingest, &alice).unwrap(), None);
+
+        ingest.opt_out_users.clear();
+        fs::write(alice.join(USER_CONFIG), "enabled = false\n").unwrap();
+        assert_eq!(WatchPlan::for_user(&ingest, &alice).unwrap(), None);
+
+        fs::write(alice.join(USER_CONFIG), "enable = false\n").unwrap();
+        assert!(matches!(
+            WatchPlan::for_user(&ingest, &alice),
+            Err(WatchError::InvalidConfig(..))
+        ));
+        fs::remove_file(alice.join(USER_CONFIG)).unwrap();
+        symlink("/etc/shadow", alice.join(USER_CONFIG)).unwrap();
+        assert!(matches!(
+            WatchPlan::for_user(&ingest, &alice),
+            Err(WatchError::UntrustedConfig(_))
+        ));
+    }
+
+    #[test]
+    fn special_or_oversized_user_config_is_refused() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(USER_CONFIG);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        // Opening a FIFO must not wait for a writer.
+        let status = std::process::Command::new("mkfifo").arg(&path).status().unwrap();
+        assert!(status.success());
+        assert!(matches!(
+            UserIngestConfig::load(temp.path()),
+            Err(WatchError::UntrustedConfig(_))
+        ));
+
+        fs::remove_file(&path).unwrap();
+        fs::write(&path, vec![b'#'; MAX_USER_CONFIG_BYTES as usize + 1]).unwrap();
+        assert!(matches!(
+            UserIngestConfig::load(temp.path()),
+            Err(WatchError::ConfigTooLarge(_))
+        ));
+    }
+}
@@ -0,0 +1,247 @@
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+/// Version of the `--json` envelope. Bumped whenever a field is renamed or
+/// removed; adding fields does not change it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Exit codes other than 0 (success). Usage errors exit with 2, which is what
+/// clap already uses.
+pub const EXIT_FATAL: u8 = 1;
+pub const EXIT_NOTHING_TO_DO: u8 = 3;
+pub const EXIT_PARTIAL_FAILURE: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    NothingToDo,
+    PartialFailure,
+    Fatal,
+}
+
+impl Outcome {
+    pub fn exit_code(self) -> ExitCode {
+        match self {
+            Outcome::Success => ExitCode::SUCCESS,
+            Outcome::NothingToDo => ExitCode::from(EXIT_NOTHING_TO_DO),
+            Outcome::PartialFailure => ExitCode::from(EXIT_PARTIAL_FAILURE),
+            Outcome::Fatal => ExitCode::from(EXIT_FATAL),
+        }
+    }
+
+    /// Outcome of a batch in which `done` items succeeded and `failed`
+    /// items did not.
+    pub fn of_batch(done: usize, failed: usize) -> Self {
+        if failed > 0 {
+            Outcome::PartialFailure
+        } else if done == 0 {
+            Outcome::NothingToDo
+        } else {
+            Outcome::Success
+        }
+    }
+
+    /// The worse of two outcomes, for commands made of several steps.
+    pub fn combine(self, other: Outcome) -> Self {
+        use Outcome::*;
+        match (self, other) {
+            (Fatal, _) | (_, Fatal) => Fatal,
+            (PartialFailure, _) | (_, PartialFailure) => PartialFailure,
+            (NothingToDo, NothingToDo) => NothingToDo,
+            _ => Success,
+        }
+    }
+}
+
+/// The result of a command, printed as the `data` of the JSON envelope.
+pub trait Report: Serialize {
+    fn outcome(&self) -> Outcome {
+        Outcome::Success
+    }
+}
+
+impl Report for () {}
+
+impl<T: Serialize> Report for Vec<T> {}
+
+/// Serializes `(path, reason)` pairs as `{"path": …, "reason": …}` objects.
+pub fn serialize_path_reasons<S: Serializer>(
+    items: &[(PathBuf, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serialize_reasons("path", items, serializer)
+}
+
+/// Serializes `(app, reason)` pairs as `{"app": …, "reason": …}` objects.
+pub fn serialize_app_reasons<S: Serializer>(
+    items: &[(String, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serialize_reasons("app", items, serializer)
+}
+
+/// Serializes `(subject, reason)` pairs as objects with the subject under
+/// `key`.
+fn serialize_reasons<S: Serializer, T: Serialize>(
+    key: &'static str,
+    items: &[(T, String)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    struct Reason<'a, T> {
+        key: &'static str,
+        subject: &'a T,
+        reason: &'a str,
+    }
+
+    impl<T: Serialize> Serialize for Reason<'_, T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(2))?;
+            map.serialize_entry(self.key, self.subject)?;
+            map.serialize_entry("reason", self.reason)?;
+            map.end()
+        }
+    }
+
+    let mut seq = serializer.serialize_seq(Some(items.len()))?;
+    for (subject, reason) in items {
+        seq.serialize_element(&Reason { key, subject, reason })?;
+    }
+    seq.end()
+}
+
+pub fn serialize_secs<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    schema_version: u32,
+    command: &'a str,
+    outcome: Outcome,
+    data: Option<&'a T>,
+    error: Option<String>,
+}
+
+fn print_envelope<T: Serialize>(envelope: &Envelope<T>) {
+    match serde_json::to_string_pretty(envelope) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("❌ Failed to serialize output: {}", e),
+    }
+}
+
+/// Prints the outcome of `command` and returns the matching exit code. With
+/// `json` the report goes to stdout as a versioned envelope; otherwise the
+/// command has already printed its human-readable output.
+pub fn finish<T: Report, E: fmt::Display>(
+    command: &str,
+    context: &str,
+    json: bool,
+    result: Result<T, E>,
+) -> ExitCode {
+    match result {
+        Ok(report) => {
+            let outcome = report.outcome();
+            if json {
+                print_envelope(&Envelope {
+                    schema_version: SCHEMA_VERSION,
+                    command,
+                    outcome,
+                    data: Some(&report),
+                    error: None,
+                });
+            }
+            outcome.exit_code()
+        }
+        Err(e) => {
+            if json {
+                print_envelope::<()>(&Envelope {
+                    schema_version: SCHEMA_VERSION,
+                    command,
+                    outcome: Outcome::Fatal,
+                    data: None,
+                    error: Some(e.to_string()),
+                });
+            } else {
+                eprintln!("❌ {}: {}", context, e);
+            }
+            Outcome::Fatal.exit_code()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_outcomes() {
+        assert_eq!(Outcome::of_batch(0, 0), Outcome::NothingToDo);
+        assert_eq!(Outcome::of_batch(3, 0), Outcome::Success);
+        assert_eq!(Outcome::of_batch(3, 1), Outcome::PartialFailure);
+        assert_eq!(Outcome::of_batch(0, 1), Outcome::PartialFailure);
+
+        assert_eq!(
+            Outcome::NothingToDo.combine(Outcome::Success),
+            Outcome::Success
+        );
+        assert_eq!(
+            Outcome::NothingToDo.combine(Outcome::NothingToDo),
+            Outcome::NothingToDo
+        );
+        assert_eq!(
+            Outcome::Success.combine(Outcome::PartialFailure),
+            Outcome::PartialFailure
+        );
+    }
+
+    #[test]
+    fn envelope_shape_is_stable() {
+        let envelope = Envelope {
+            schema_version: SCHEMA_VERSION,
+            command: "ingest",
+            outcome: Outcome::NothingToDo,
+            data: Some(&vec![1, 2]),
+            error: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&envelope).unwrap(),
+            serde_json::json!({
+                "schema_version": 1,
+                "command": "ingest",
+                "outcome": "nothing_to_do",
+                "data": [1, 2],
+                "error": null
+            })
+        );
+    }
+    #[test]
+    fn reasons_serialize_under_their_key() {
+        #[derive(Serialize)]
+        struct Failures {
+            #[serde(serialize_with = "serialize_path_reasons")]
+            paths: Vec<(PathBuf, String)>,
+            #[serde(serialize_with = "serialize_app_reasons")]
+            apps: Vec<(String, String)>,
+        }
+
+        let failures = Failures {
+            paths: vec![(PathBuf::from("/raw/a.AppImage"), "denied".to_string())],
+            apps: vec![("app".to_string(), "offline".to_string())],
+        };
+        assert_eq!(
+            serde_json::to_value(&failures).unwrap(),
+            serde_json::json!({
+                "paths": [{"path": "/raw/a.AppImage", "reason": "denied"}],
+                "apps": [{"app": "app", "reason": "offline"}]
+            })
+        );
+    }
+}
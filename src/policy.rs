@@ -0,0 +1,428 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::config::Config;
+use crate::core::{AppImage, AppImageError, Version, version_from_file_name, wildcard_matches};
+use crate::quarantine::{Quarantine, QuarantineDetails, QuarantineError, QuarantineRecord};
+use crate::security::SecurityChecker;
+use crate::trust::normalize_fingerprint;
+
+#[derive(Debug, Error)]
+pub enum PolicyError {
+    #[error("AppImage error: {0}")]
+    AppImage(#[from] AppImageError),
+
+    #[error("Quarantine error: {0}")]
+    Quarantine(#[from] QuarantineError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    Allow,
+    Deny,
+    Quarantine,
+}
+
+impl fmt::Display for PolicyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyAction::Allow => write!(f, "allow"),
+            PolicyAction::Deny => write!(f, "deny"),
+            PolicyAction::Quarantine => write!(f, "quarantine"),
+        }
+    }
+}
+
+/// The `[policy]` config section. Rules are checked in order and the first
+/// match decides; images matching no rule get `default_action`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default = "default_action")]
+    pub default_action: PolicyAction,
+
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            default_action: default_action(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+fn default_action() -> PolicyAction {
+    PolicyAction::Allow
+}
+
+/// A rule matches when every condition it sets holds. A rule without
+/// conditions matches everything, so a misspelled condition is an error
+/// rather than being ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRule {
+    pub action: PolicyAction,
+
+    /// Normalized app name; `*` matches any run of characters.
+    pub name: Option<String>,
+
+    pub sha256: Option<String>,
+
+    /// Fingerprint of the key that signed the image.
+    pub signing_key: Option<String>,
+
+    /// User whose home directory the image was ingested from.
+    pub user: Option<String>,
+
+    pub min_size: Option<u64>,
+
+    pub max_size: Option<u64>,
+
+    /// Whether the version in the file name is a prerelease, like `2.0-rc1`.
+    pub prerelease: Option<bool>,
+
+    /// Shown to the admin and stored with quarantined images.
+    pub reason: Option<String>,
+}
+
+/// What a policy is evaluated against. Unknown attributes never match a rule
+/// that tests them.
+#[derive(Debug, Clone, Default)]
+pub struct PolicySubject<'a> {
+    pub name: &'a str,
+    pub sha256: &'a str,
+    pub signing_key: Option<&'a str>,
+    pub user: Option<&'a str>,
+    pub size: u64,
+    pub prerelease: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub action: PolicyAction,
+    pub reason: String,
+}
+
+impl PolicyRule {
+    fn matches(&self, subject: &PolicySubject) -> bool {
+        self.name
+            .as_ref()
+            .is_none_or(|pattern| wildcard_matches(pattern, subject.name))
+            && self
+                .sha256
+                .as_ref()
+                .is_none_or(|sha256| sha256.eq_ignore_ascii_case(subject.sha256))
+            && self.signing_key.as_ref().is_none_or(|key| {
+                subject.signing_key.is_some_and(|signer| {
+                    normalize_fingerprint(key) == normalize_fingerprint(signer)
+                })
+            })
+            && self
+                .user
+                .as_ref()
+                .is_none_or(|user| subject.user == Some(user.as_str()))
+            && self.min_size.is_none_or(|min| subject.size >= min)
+            && self.max_size.is_none_or(|max| subject.size <= max)
+            && self
+                .prerelease
+                .is_none_or(|prerelease| subject.prerelease == prerelease)
+    }
+}
+
+impl Policy {
+    pub fn evaluate(&self, subject: &PolicySubject) -> Decision {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.matches(subject) {
+                return Decision {
+                    action: rule.action,
+                    reason: rule
+                        .reason
+                        .clone()
+                        .unwrap_or_else(|| format!("matched policy rule {}", i + 1)),
+                };
+            }
+        }
+        Decision {
+            action: self.default_action,
+            reason: "default policy".to_string(),
+        }
+    }
+
+    /// True when every image is allowed, so evaluating can skip hashing.
+    pub fn is_permissive(&self) -> bool {
+        self.rules.is_empty() && self.default_action == PolicyAction::Allow
+    }
+
+    pub fn needs_signer(&self) -> bool {
+        self.rules.iter().any(|rule| rule.signing_key.is_some())
+    }
+}
+
+/// Applies the configured policy to images and quarantines those it does
+/// not allow.
+pub struct PolicyEnforcer {
+    pub policy: Policy,
+    pub quarantine: Quarantine,
+    pub security_checker: SecurityChecker,
+}
+
+impl PolicyEnforcer {
+    pub fn from_config(config: &Config) -> Self {
+        PolicyEnforcer {
+            policy: config.policy.clone(),
+            quarantine: Quarantine::new(config.quarantine_dir()),
+            security_checker: SecurityChecker::from_config(config),
+        }
+    }
+
+    /// Decides what to do with `app`, returning the decision and the image's
+    /// SHA-256. The signer is looked up only if a rule needs it and the caller
+    /// does not already know it.
+    pub fn evaluate(
+        &self,
+        app: &AppImage,
+        user: Option<&str>,
+        signer: Option<&str>,
+    ) -> Result<(Decision, String), PolicyError> {
+        if self.policy.is_permissive() {
+            return Ok((
+                Decision {
+                    action: PolicyAction::Allow,
+                    reason: "default policy".to_string(),
+                },
+                String::new(),
+            ));
+        }
+
+        let sha256 = app.get_checksum()?;
+        let size = std::fs::metadata(&app.path)
+            .map_err(AppImageError::from)?
+            .len();
+        let looked_up_signer = if signer.is_none() && self.policy.needs_signer() {
+            self.security_checker
+                .check_appimage_signer(app)
+                .unwrap_or_else(|e| {
+                    warn!("Cannot determine signer of {:?}: {}", app.path, e);
+                    None
+                })
+        } else {
+            None
+        };
+
+        let name = app.normalize_name();
+        let prerelease = app
+            .path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(version_from_file_name)
+            .is_some_and(|version| Version::parse(&version).is_prerelease());
+        let subject = PolicySubject {
+            name: &name,
+            sha256: &sha256,
+            signing_key: signer.or(looked_up_signer.as_deref()),
+            user,
+            size,
+            prerelease,
+        };
+        Ok((self.policy.evaluate(&subject), sha256))
+    }
+
+    /// Evaluates the policy for `app` and moves it into quarantine unless it
+    /// is allowed. Returns the quarantine record when it was moved.
+    pub fn enforce(
+        &self,
+        app: &AppImage,
+        user: Option<&str>,
+        signer: Option<&str>,
+    ) -> Result<Option<QuarantineRecord>, PolicyError> {
+        let (decision, sha256) = self.evaluate(app, user, signer)?;
+        if decision.action == PolicyAction::Allow {
+            return Ok(None);
+        }
+
+        info!(
+            "Policy action {} for {:?}: {}",
+            decision.action, app.path, decision.reason
+        );
+        let record = self.quarantine.add(
+            &app.path,
+            QuarantineDetails {
+                action: decision.action,
+                reason: &decision.reason,
+                sha256: &sha256,
+                user,
+                security_report: None,
+            },
+        )?;
+        Ok(Some(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(action: PolicyAction) -> PolicyRule {
+        PolicyRule {
+            action,
+            name: None,
+            sha256: None,
+            signing_key: None,
+            user: None,
+            min_size: None,
+            max_size: None,
+            prerelease: None,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let policy = Policy {
+            default_action: PolicyAction::Allow,
+            rules: vec![
+                PolicyRule {
+                    name: Some("telegram*".to_string()),
+                    reason: Some("No messengers".to_string()),
+                    ..rule(PolicyAction::Deny)
+                },
+                PolicyRule {
+                    user: Some("guest".to_string()),
+                    ..rule(PolicyAction::Quarantine)
+                },
+            ],
+        };
+
+        let subject = PolicySubject {
+            name: "telegram-desktop",
+            user: Some("guest"),
+            ..PolicySubject::default()
+        };
+        assert_eq!(
+            policy.evaluate(&subject),
+            Decision {
+                action: PolicyAction::Deny,
+                reason: "No messengers".to_string()
+            }
+        );
+
+        let subject = PolicySubject {
+            name: "gimp",
+            user: Some("guest"),
+            ..PolicySubject::default()
+        };
+        assert_eq!(policy.evaluate(&subject).action, PolicyAction::Quarantine);
+        assert_eq!(policy.evaluate(&subject).reason, "matched policy rule 2");
+
+        let subject = PolicySubject {
+            name: "gimp",
+            ..PolicySubject::default()
+        };
+        assert_eq!(policy.evaluate(&subject).action, PolicyAction::Allow);
+    }
+
+    #[test]
+    fn rules_match_checksum_key_and_size() {
+        let policy = Policy {
+            default_action: PolicyAction::Deny,
+            rules: vec![
+                PolicyRule {
+                    sha256: Some("ABCDEF".to_string()),
+                    ..rule(PolicyAction::Allow)
+                },
+                PolicyRule {
+                    signing_key: Some("aaaa bbbb".to_string()),
+                    max_size: Some(100),
+                    ..rule(PolicyAction::Allow)
+                },
+            ],
+        };
+
+        let by_checksum = PolicySubject {
+            sha256: "abcdef",
+            ..PolicySubject::default()
+        };
+        assert_eq!(policy.evaluate(&by_checksum).action, PolicyAction::Allow);
+
+        let signed = PolicySubject {
+            signing_key: Some("AAAABBBB"),
+            size: 100,
+            ..PolicySubject::default()
+        };
+        assert_eq!(policy.evaluate(&signed).action, PolicyAction::Allow);
+
+        let too_big = PolicySubject {
+            size: 101,
+            ..signed
+        };
+        assert_eq!(policy.evaluate(&too_big).action, PolicyAction::Deny);
+
+        let unsigned = PolicySubject {
+            size: 10,
+            ..PolicySubject::default()
+        };
+        assert_eq!(policy.evaluate(&unsigned).action, PolicyAction::Deny);
+    }
+
+    #[test]
+    fn prerelease_rule_matches_prereleases_only() {
+        let policy = Policy {
+            default_action: PolicyAction::Allow,
+            rules: vec![PolicyRule {
+                prerelease: Some(true),
+                ..rule(PolicyAction::Deny)
+            }],
+        };
+
+        let beta = PolicySubject {
+            prerelease: true,
+            ..PolicySubject::default()
+        };
+        assert_eq!(policy.evaluate(&beta).action, PolicyAction::Deny);
+        assert_eq!(
+            policy.evaluate(&PolicySubject::default()).action,
+            PolicyAction::Allow
+        );
+    }
+
+    #[test]
+    fn policy_parses_from_toml() {
+        let policy: Policy = toml::from_str(
+            r#"
+default_action = "quarantine"
+
+[[rules]]
+action = "allow"
+signing_key = "ABCD"
+"#,
+        )
+        .unwrap();
+        assert_eq!(policy.default_action, PolicyAction::Quarantine);
+        assert_eq!(policy.rules[0].signing_key.as_deref(), Some("ABCD"));
+        assert!(policy.needs_signer());
+    }
+
+    #[test]
+    fn misspelled_rule_condition_fails_to_load() {
+        for key in ["signing-key", "sha"] {
+            let config = format!(
+                r#"
+[policy]
+default_action = "deny"
+
+[[policy.rules]]
+action = "allow"
+{} = "ABCD"
+"#,
+                key
+            );
+            assert!(toml::from_str::<Config>(&config).is_err(), "{}", key);
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::cli::say;
+use crate::config::Config;
+use crate::mover::file_ops::is_cross_device_error;
+use crate::output::{Outcome, Report};
+use crate::policy::PolicyAction;
+use crate::registrar::ProcessError;
+use crate::security::SecurityReport;
+
+const REASON_FILE: &str = "reason.json";
+
+#[derive(Debug, Error)]
+pub enum QuarantineError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("No quarantined item {0}")]
+    NotFound(String),
+
+    #[error("{0} already exists")]
+    AlreadyExists(PathBuf),
+}
+
+/// Why an AppImage is being quarantined.
+#[derive(Debug, Clone)]
+pub struct QuarantineDetails<'a> {
+    pub action: PolicyAction,
+    pub reason: &'a str,
+    pub sha256: &'a str,
+    pub user: Option<&'a str>,
+    pub security_report: Option<&'a SecurityReport>,
+}
+
+/// Why an AppImage was quarantined, stored as `reason.json` next to it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuarantineRecord {
+    pub id: String,
+    pub file_name: String,
+    pub original_path: PathBuf,
+    pub action: PolicyAction,
+    pub reason: String,
+    pub sha256: String,
+    pub user: Option<String>,
+    pub quarantined_at: DateTime<Utc>,
+    #[serde(default)]
+    pub security_report: Option<SecurityReport>,
+}
+
+/// AppImages held back from registration, one directory per item.
+#[derive(Debug, Clone)]
+pub struct Quarantine {
+    dir: PathBuf,
+}
+
+impl Quarantine {
+    pub fn new(dir: PathBuf) -> Self {
+        Quarantine { dir }
+    }
+
+    fn item_dir(&self, id: &str) -> PathBuf {
+        self.dir.join(id)
+    }
+
+    /// Path of the quarantined image itself.
+    pub fn image_path(&self, record: &QuarantineRecord) -> PathBuf {
+        self.item_dir(&record.id).join(&record.file_name)
+    }
+
+    /// Moves `path` into a new quarantine item. The file loses its execute
+    /// permission so it cannot be launched from there.
+    pub fn add(
+        &self,
+        path: &Path,
+        details: QuarantineDetails,
+    ) -> Result<QuarantineRecord, QuarantineError> {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "unknown.AppImage".to_string());
+        let quarantined_at = Utc::now();
+
+        fs::create_dir_all(&self.dir)?;
+        fs::set_permissions(&self.dir, fs::Permissions::from_mode(0o700))?;
+
+        let base = format!("{}-{}", quarantined_at.format("%Y%m%d%H%M%S"), file_name);
+        let mut id = base.clone();
+        let mut n = 1;
+        while self.item_dir(&id).exists() {
+            id = format!("{}-{}", base, n);
+            n += 1;
+        }
+
+        let item_dir = self.item_dir(&id);
+        fs::create_dir(&item_dir)?;
+        let target = item_dir.join(&file_name);
+        move_file(path, &target)?;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600))?;
+
+        let record = QuarantineRecord {
+            id,
+            file_name,
+            original_path: path.to_path_buf(),
+            action: details.action,
+            reason: details.reason.to_string(),
+            sha256: details.sha256.to_string(),
+            user: details.user.map(str::to_string),
+            quarantined_at,
+            security_report: details.security_report.cloned(),
+        };
+        fs::write(
+            item_dir.join(REASON_FILE),
+            serde_json::to_string_pretty(&record)?,
+        )?;
+
+        info!(
+            "Quarantined {:?} as {}: {}",
+            path, record.id, details.reason
+        );
+        Ok(record)
+    }
+
+    pub fn get(&self, id: &str) -> Result<QuarantineRecord, QuarantineError> {
+        // Ids are directory names; refuse anything that could escape the
+        // quarantine directory.
+        if id.is_empty() || id.contains('/') || id == "." || id == ".." {
+            return Err(QuarantineError::NotFound(id.to_string()));
+        }
+        let reason_path = self.item_dir(id).join(REASON_FILE);
+        if !reason_path.is_file() {
+            return Err(QuarantineError::NotFound(id.to_string()));
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(reason_path)?)?)
+    }
+
+    /// All quarantined items, oldest first. Items with an unreadable reason
+    /// file are skipped with a warning.
+    pub fn list(&self) -> Result<Vec<QuarantineRecord>, QuarantineError> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut records = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let reason_path = entry?.path().join(REASON_FILE);
+            if !reason_path.is_file() {
+                continue;
+            }
+            match fs::read_to_string(&reason_path)
+                .map_err(QuarantineError::from)
+                .and_then(|content| Ok(serde_json::from_str(&content)?))
+            {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("Ignoring unreadable {:?}: {}", reason_path, e),
+            }
+        }
+        records.sort_by(|a: &QuarantineRecord, b| {
+            a.quarantined_at
+                .cmp(&b.quarantined_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        Ok(records)
+    }
+
+    /// Moves the image of `record` into `dir`, leaving the record in place
+    /// until [`Quarantine::remove`] is called.
+    pub fn restore(
+        &self,
+        record: &QuarantineRecord,
+        dir: &Path,
+    ) -> Result<PathBuf, QuarantineError> {
+        let target = dir.join(&record.file_name);
+        if target.exists() {
+            return Err(QuarantineError::AlreadyExists(target));
+        }
+        fs::create_dir_all(dir)?;
+        move_file(&self.image_path(record), &target)?;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o755))?;
+        Ok(target)
+    }
+
+    /// Moves an image taken out with [`Quarantine::restore`] back in.
+    pub fn put_back(&self, record: &QuarantineRecord, path: &Path) -> Result<(), QuarantineError> {
+        let target = self.image_path(record);
+        move_file(path, &target)?;
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600))?;
+        Ok(())
+    }
+
+    /// Deletes a quarantined item and its image.
+    pub fn remove(&self, id: &str) -> Result<(), QuarantineError> {
+        let record = self.get(id)?;
+        fs::remove_dir_all(self.item_dir(&record.id))?;
+        Ok(())
+    }
+}
+
+fn move_file(source: &Path, dest: &Path) -> std::io::Result<()> {
+    match fs::rename(source, dest) {
+        Ok(()) => Ok(()),
+        // The quarantine is on another filesystem
+        Err(err) if is_cross_device_error(&err) => {
+            fs::copy(source, dest)?;
+            fs::remove_file(source)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Result of `quarantine release`.
+#[derive(Debug, Serialize)]
+pub struct ReleaseReport {
+    pub id: String,
+    pub file_name: String,
+    /// Name the image was registered under; `None` on a dry run.
+    pub registered_as: Option<String>,
+}
+
+impl Report for ReleaseReport {}
+
+impl Report for QuarantineRecord {}
+
+/// Result of `quarantine purge`.
+#[derive(Debug, Serialize)]
+pub struct PurgeReport {
+    pub purged: Vec<String>,
+}
+
+impl Report for PurgeReport {
+    fn outcome(&self) -> Outcome {
+        Outcome::of_batch(self.purged.len(), 0)
+    }
+}
+
+pub fn run_quarantine_list() -> Result<Vec<QuarantineRecord>, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let records = Quarantine::new(config.quarantine_dir()).list()?;
+
+    if records.is_empty() {
+        say!("No quarantined AppImages");
+        return Ok(records);
+    }
+
+    say!("Quarantined AppImages:");
+    for record in &records {
+        say!(
+            "  {} ({}, {}) - {}",
+            record.id,
+            record.action,
+            record.quarantined_at.format("%Y-%m-%d %H:%M:%S"),
+            record.reason
+        );
+    }
+    Ok(records)
+}
+
+pub fn run_quarantine_show(id: &str) -> Result<QuarantineRecord, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let record = Quarantine::new(config.quarantine_dir()).get(id)?;
+    say!("{}", serde_json::to_string_pretty(&record)?);
+    Ok(record)
+}
+
+/// Moves a quarantined image back into the raw directory and registers it
+/// through the normal processing pipeline. If the pipeline rejects it again
+/// it ends up in a new quarantine item.
+pub fn run_quarantine_release(
+    id: &str,
+    dry_run: bool,
+) -> Result<ReleaseReport, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let quarantine = Quarantine::new(config.quarantine_dir());
+    let record = quarantine.get(id)?;
+    let mut report = ReleaseReport {
+        id: record.id.clone(),
+        file_name: record.file_name.clone(),
+        registered_as: None,
+    };
+    if dry_run {
+        say!(
+            "[DRY RUN] Would move {} to {} and register it",
+            record.file_name,
+            config.raw_dir().display()
+        );
+        return Ok(report);
+    }
+    let path = quarantine.restore(&record, &config.raw_dir())?;
+
+    let processor = crate::scan::processor_from_config(&config);
+    match processor.process_single_appimage(&path) {
+        Ok(processed) => {
+            quarantine.remove(id)?;
+            say!(
+                "✅ Released {} and registered it as {}",
+                record.file_name, processed.normalized_name
+            );
+            report.registered_as = Some(processed.normalized_name);
+            Ok(report)
+        }
+        Err(ProcessError::Quarantined(reason)) => {
+            quarantine.remove(id)?;
+            Err(format!("{} was quarantined again: {}", record.file_name, reason).into())
+        }
+        Err(e) => {
+            if path.exists() {
+                quarantine.put_back(&record, &path)?;
+            }
+            Err(e.into())
+        }
+    }
+}
+
+/// Deletes one quarantined item, or all of them when `id` is `None`.
+pub fn run_quarantine_purge(
+    id: Option<&str>,
+    dry_run: bool,
+) -> Result<PurgeReport, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let quarantine = Quarantine::new(config.quarantine_dir());
+
+    let ids = match id {
+        Some(id) => vec![id.to_string()],
+        None => quarantine.list()?.into_iter().map(|r| r.id).collect(),
+    };
+    if dry_run {
+        for id in &ids {
+            quarantine.get(id)?;
+            say!("[DRY RUN] Would purge {}", id);
+        }
+        return Ok(PurgeReport { purged: ids });
+    }
+    for id in &ids {
+        quarantine.remove(id)?;
+    }
+    say!("🗑️  Purged {} quarantined AppImages", ids.len());
+    Ok(PurgeReport { purged: ids })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn details(reason: &str) -> QuarantineDetails<'_> {
+        QuarantineDetails {
+            action: PolicyAction::Deny,
+            reason,
+            sha256: "abc",
+            user: Some("alice"),
+            security_report: None,
+        }
+    }
+
+    #[test]
+    fn add_moves_file_and_writes_reason() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("Bad.AppImage");
+        fs::write(&source, b"image").unwrap();
+
+        let quarantine = Quarantine::new(temp.path().join("quarantine"));
+        let record = quarantine.add(&source, details("denied")).unwrap();
+
+        assert!(!source.exists());
+        let item_dir = temp.path().join("quarantine").join(&record.id);
+        assert_eq!(fs::read(item_dir.join("Bad.AppImage")).unwrap(), b"image");
+        assert!(item_dir.join("reason.json").exists());
+        assert_eq!(quarantine.list().unwrap(), vec![record.clone()]);
+        assert_eq!(quarantine.get(&record.id).unwrap(), record);
+    }
+
+    #[test]
+    fn restore_put_back_and_remove() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("Bad.AppImage");
+        let raw = temp.path().join("raw");
+        fs::write(&source, b"image").unwrap();
+
+        let quarantine = Quarantine::new(temp.path().join("quarantine"));
+        let record = quarantine.add(&source, details("denied")).unwrap();
+
+        let restored = quarantine.restore(&record, &raw).unwrap();
+        assert_eq!(restored, raw.join("Bad.AppImage"));
+        assert!(!quarantine.image_path(&record).exists());
+
+        quarantine.put_back(&record, &restored).unwrap();
+        assert!(quarantine.image_path(&record).exists());
+
+        quarantine.remove(&record.id).unwrap();
+        assert!(quarantine.list().unwrap().is_empty());
+        assert!(matches!(
+            quarantine.get(&record.id),
+            Err(QuarantineError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn get_rejects_ids_outside_quarantine() {
+        let temp = TempDir::new().unwrap();
+        let quarantine = Quarantine::new(temp.path().join("quarantine"));
+        assert!(matches!(
+            quarantine.get(".."),
+            Err(QuarantineError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn list_is_empty_without_quarantine_dir() {
+        let temp = TempDir::new().unwrap();
+        let quarantine = Quarantine::new(temp.path().join("missing"));
+        assert!(quarantine.list().unwrap().is_empty());
+    }
+}
@@ -3,6 +3,8 @@ use std::path::{Path, PathBuf};
 use thiserror::Error;
 use tracing::debug;
 
+use crate::core::payload::EmbeddedIcon;
+
 #[derive(Debug, Error)]
 pub enum IconExtractError {
     #[error("IO error: {0}")]
@@ -13,61 +15,22 @@ pub enum IconExtractError {
     NotFound { path: PathBuf },
 }
 
-pub fn extract_icon(
-    app_dir: &Path,
+pub fn save_icon(
+    icon: &EmbeddedIcon,
     icon_dir: &Path,
     normalized_name: &str,
-) -> Result<Option<PathBuf>, IconExtractError> {
-    debug!("Extracting icon from {:?}", app_dir);
-
-    let icon_path = find_icon_in_dir(app_dir)?;
-
-    match icon_path {
-        Some(src) => {
-            let extension = src.extension().and_then(|e| e.to_str()).unwrap_or("png");
-
-            let dest = icon_dir.join(format!("{}.{}", normalized_name, extension));
-
-            if dest.exists() {
-                debug!("Icon already exists: {:?}", dest);
-                return Ok(Some(dest));
-            }
+) -> Result<PathBuf, IconExtractError> {
+    let dest = icon_dir.join(format!("{}.{}", normalized_name, icon.extension));
 
-            fs::copy(&src, &dest)?;
-
-            debug!("Extracted icon: {:?} -> {:?}", src, dest);
-            Ok(Some(dest))
-        }
-        None => {
-            debug!("No icon found in {:?}", app_dir);
-            Ok(None)
-        }
+    if dest.exists() {
+        debug!("Icon already exists: {:?}", dest);
+        return Ok(dest);
     }
-}
 
-fn find_icon_in_dir(dir: &Path) -> Result<Option<PathBuf>, IconExtractError> {
-    let entries = match fs::read_dir(dir) {
-        Ok(entries) => entries,
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                return Ok(None);
-            }
-            return Err(IconExtractError::Io(e));
-        }
-    };
+    fs::write(&dest, &icon.data)?;
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_file()
-            && let Some(ext) = path.extension()
-                && (ext.eq_ignore_ascii_case("png") || ext.eq_ignore_ascii_case("svg")) {
-                    return Ok(Some(path));
-                }
-    }
-
-    Ok(None)
+    debug!("Saved embedded icon: {:?}", dest);
+    Ok(dest)
 }
 
 #[cfg(test)]
@@ -77,85 +40,32 @@ mod tests {
     use tempfile::TempDir;
 
     #[test]
-    fn extract_icon_finds_png() {
+    fn save_icon_writes_embedded_icon() {
         let temp = TempDir::new().unwrap();
-        let app_dir = temp.path().join("app");
-        let icon_dir = temp.path().join("icons");
-        fs::create_dir_all(&app_dir).unwrap();
-        fs::create_dir_all(&icon_dir).unwrap();
-
-        let icon = app_dir.join("icon.png");
-        fs::write(&icon, b"fake icon").unwrap();
+        let icon = EmbeddedIcon {
+            data: b"<svg/>".to_vec(),
+            extension: "svg".to_string(),
+        };
 
-        let result = extract_icon(&app_dir, &icon_dir, "testapp").unwrap();
+        let dest = save_icon(&icon, temp.path(), "testapp").unwrap();
 
-        assert!(result.is_some());
-        assert!(result.unwrap().ends_with("testapp.png"));
+        assert_eq!(dest, temp.path().join("testapp.svg"));
+        assert_eq!(fs::read(&dest).unwrap(), b"<svg/>");
     }
 
     #[test]
-    fn extract_icon_finds_svg() {
+    fn save_icon_preserves_existing() {
         let temp = TempDir::new().unwrap();
-        let app_dir = temp.path().join("app");
-        let icon_dir = temp.path().join("icons");
-        fs::create_dir_all(&app_dir).unwrap();
-        fs::create_dir_all(&icon_dir).unwrap();
-
-        let icon = app_dir.join("icon.svg");
-        fs::write(&icon, b"fake icon").unwrap();
-
-        let result = extract_icon(&app_dir, &icon_dir, "testapp").unwrap();
-
-        assert!(result.is_some());
-        assert!(result.unwrap().ends_with("testapp.svg"));
-    }
-
-    #[test]
-    fn extract_icon_skips_non_image_files() {
-        let temp = TempDir::new().unwrap();
-        let app_dir = temp.path().join("app");
-        let icon_dir = temp.path().join("icons");
-        fs::create_dir_all(&app_dir).unwrap();
-        fs::create_dir_all(&icon_dir).unwrap();
-
-        let text_file = app_dir.join("readme.txt");
-        fs::write(&text_file, b"readme").unwrap();
-
-        let result = extract_icon(&app_dir, &icon_dir, "testapp").unwrap();
-
-        assert!(result.is_none());
-    }
-
-    #[test]
-    fn extract_icon_preserves_existing() {
-        let temp = TempDir::new().unwrap();
-        let app_dir = temp.path().join("app");
-        let icon_dir = temp.path().join("icons");
-        fs::create_dir_all(&app_dir).unwrap();
-        fs::create_dir_all(&icon_dir).unwrap();
-
-        let icon = app_dir.join("icon.png");
-        fs::write(&icon, b"fake icon").unwrap();
-
-        let existing_icon = icon_dir.join("testapp.png");
+        let existing_icon = temp.path().join("testapp.png");
         fs::write(&existing_icon, b"existing").unwrap();
+        let icon = EmbeddedIcon {
+            data: b"fake icon".to_vec(),
+            extension: "png".to_string(),
+        };
 
-        let result = extract_icon(&app_dir, &icon_dir, "testapp").unwrap();
-
-        assert!(result.is_some());
-        assert_eq!(result.unwrap(), existing_icon);
-    }
-
-    #[test]
-    fn extract_icon_returns_none_for_empty_dir() {
-        let temp = TempDir::new().unwrap();
-        let app_dir = temp.path().join("app");
-        let icon_dir = temp.path().join("icons");
-        fs::create_dir_all(&app_dir).unwrap();
-        fs::create_dir_all(&icon_dir).unwrap();
-
-        let result = extract_icon(&app_dir, &icon_dir, "testapp").unwrap();
+        let dest = save_icon(&icon, temp.path(), "testapp").unwrap();
 
-        assert!(result.is_none());
+        assert_eq!(dest, existing_icon);
+        assert_eq!(fs::read(&dest).unwrap(), b"existing");
     }
 }
@@ -3,4 +3,4 @@ pub mod icon_extractor;
 pub mod processor;
 pub mod symlink;
 
-pub use processor::Processor;
+pub use processor::{InstallOptions, ProcessError, ProcessReport, Processor};
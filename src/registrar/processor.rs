@@ -1,15 +1,18 @@
+use serde::Serialize;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use thiserror::Error;
 use tracing::{debug, error, info, instrument, warn};
 
-use crate::core::{normalize_appimage_name, AppImage, AppImageError, Metadata, MetadataCache, VersionManager, VersionError};
+use crate::core::{normalize_appimage_name, AppImage, AppImageError, AppImageType, Metadata, MetadataCache, VersionManager, VersionError, version_from_file_name};
 use crate::registrar::desktop_entry::DesktopEntry;
 use crate::registrar::icon_extractor;
-use crate::security::SecurityChecker;
+use crate::policy::{PolicyAction, PolicyEnforcer};
+use crate::output::{Outcome, Report, serialize_path_reasons, serialize_secs};
+use crate::quarantine::{Quarantine, QuarantineDetails};
+use crate::security::{SecurityChecker, SecurityError, SecurityReport, SecurityStatus};
 
 use rayon::prelude::*;
 
@@ -29,20 +32,60 @@ pub enum ProcessError {
 
     #[error("Version error: {0}")]
     Version(#[from] VersionError),
+
+    #[error("Signing key changed: {0}")]
+    KeyChanged(String),
+
+    #[error("Security check failed: {0}")]
+    SecurityCheck(String),
+
+    #[error("Denied by policy: {0}")]
+    PolicyDenied(String),
+
+    #[error("Quarantined: {0}")]
+    Quarantined(String),
+
+    #[error("Policy error: {0}")]
+    Policy(#[from] crate::policy::PolicyError),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct ProcessedApp {
+    #[serde(rename = "app")]
     pub normalized_name: String,
-    #[allow(dead_code)]
+    pub version: String,
+    #[serde(rename = "path")]
     pub appimage_path: PathBuf,
 }
 
-#[derive(Debug)]
+/// Overrides for what [`Processor::install_appimage`] would otherwise derive
+/// from the image.
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub desktop_entry: bool,
+}
+
+impl Default for InstallOptions {
+    fn default() -> Self {
+        InstallOptions {
+            name: None,
+            version: None,
+            desktop_entry: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub struct ProcessReport {
     pub processed: Vec<ProcessedApp>,
+    #[serde(serialize_with = "serialize_path_reasons")]
     pub failed: Vec<(PathBuf, String)>,
     pub skipped: Vec<PathBuf>,
+    #[serde(serialize_with = "serialize_path_reasons")]
+    pub quarantined: Vec<(PathBuf, String)>,
+    #[serde(rename = "processing_time_secs", serialize_with = "serialize_secs")]
     pub processing_time: Duration,
     pub cached_hits: usize,
     pub parallel_workers: usize,
@@ -54,6 +97,7 @@ impl ProcessReport {
             processed: Vec::new(),
             failed: Vec::new(),
             skipped: Vec::new(),
+            quarantined: Vec::new(),
             processing_time: Duration::default(),
             cached_hits: 0,
             parallel_workers: 1,
@@ -69,6 +113,15 @@ impl ProcessReport {
     }
 }
 
+impl Report for ProcessReport {
+    fn outcome(&self) -> Outcome {
+        Outcome::of_batch(
+            self.processed.len() + self.quarantined.len(),
+            self.failed.len(),
+        )
+    }
+}
+
 pub struct Processor {
     pub raw_dir: PathBuf,
     #[allow(dead_code)]
@@ -78,6 +131,8 @@ pub struct Processor {
     pub symlink_dir: PathBuf,
     pub version_manager: VersionManager,
     pub security_checker: SecurityChecker,
+    pub policy: Option<PolicyEnforcer>,
+    pub quarantine: Option<Quarantine>,
     pub dry_run: bool,
     pub cache: Option<Arc<Mutex<MetadataCache>>>,
     pub parallel_enabled: bool,
@@ -103,6 +158,8 @@ impl Processor {
             symlink_dir,
             version_manager,
             security_checker,
+            policy: None,
+            quarantine: None,
             dry_run: false,
             cache: None,
             parallel_enabled: true,
@@ -127,12 +184,21 @@ impl Processor {
         self
     }
 
-    #[allow(dead_code)]
     pub fn with_dry_run(mut self, dry_run: bool) -> Self {
         self.dry_run = dry_run;
         self
     }
 
+    pub fn with_policy(mut self, policy: PolicyEnforcer) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn with_quarantine(mut self, quarantine: Quarantine) -> Self {
+        self.quarantine = Some(quarantine);
+        self
+    }
+
     #[instrument(skip(self))]
     pub fn process_all(&self) -> Result<ProcessReport, ProcessError> {
         info!("Processing all AppImages in {:?}", self.raw_dir);
@@ -176,9 +242,18 @@ impl Processor {
         for result in processed_results {
             match result {
                 Ok(processed) => {
-                    info!("Processed: {}", processed.normalized_name);
+                    info!(
+                        app = %processed.normalized_name,
+                        operation = "register",
+                        "Processed: {}",
+                        processed.normalized_name
+                    );
                     report.processed.push(processed);
                 }
+                Err((path, ProcessError::Quarantined(reason))) => {
+                    warn!(operation = "quarantine", "Quarantined {:?}: {}", path, reason);
+                    report.quarantined.push((path, reason));
+                }
                 Err((path, e)) => {
                     error!("Failed to process {:?}: {}", path, e);
                     report.failed.push((path, e.to_string()));
@@ -212,80 +287,195 @@ impl Processor {
         Ok(report)
     }
 
-    #[instrument(skip(self, app_path))]
     pub fn process_single_appimage(&self, app_path: &Path) -> Result<ProcessedApp, ProcessError> {
+        self.install_appimage(app_path, &InstallOptions::default())
+    }
+
+    /// Checks, installs and registers one AppImage, using the name and
+    /// version from `options` where given.
+    #[instrument(skip(self, app_path, options))]
+    pub fn install_appimage(
+        &self,
+        app_path: &Path,
+        options: &InstallOptions,
+    ) -> Result<ProcessedApp, ProcessError> {
         let app = AppImage::new(app_path.to_path_buf())?;
         app.validate()?;
 
+        let normalized_name = match &options.name {
+            Some(name) => name.clone(),
+            None => normalize_appimage_name(
+                app_path.file_stem().and_then(|s| s.to_str()).unwrap_or(""),
+            ),
+        };
+
+        if normalized_name.is_empty() {
+            return Err(ProcessError::DesktopEntry(
+                "Empty normalized name".to_string(),
+            ));
+        }
+
         // Perform security checks
-        let security_report = self.security_checker.check_appimage(&app)
-            .map_err(|e| ProcessError::DesktopEntry(format!("Security check failed: {}", e)))?;
+        let security_report = self.security_checker.check_app(&normalized_name, &app)
+            .map_err(|e| match e {
+                SecurityError::CheckFailed(message) => ProcessError::SecurityCheck(message),
+                e => ProcessError::SecurityCheck(e.to_string()),
+            })?;
 
         // Print warnings if any
         self.security_checker.print_warnings(&app, &security_report);
 
-        let normalized_name =
-            normalize_appimage_name(app_path.file_stem().and_then(|s| s.to_str()).unwrap_or(""));
+        // Rejected images are quarantined so they are not retried on every scan
+        let rejection = match &security_report.overall_status {
+            SecurityStatus::KeyChanged(message) => Some(ProcessError::KeyChanged(message.clone())),
+            // Includes images `require_signatures` rejects
+            SecurityStatus::Error(message) => Some(ProcessError::SecurityCheck(message.clone())),
+            _ => None,
+        };
+        if let Some(error) = rejection {
+            return Err(self.quarantine_rejected(&app, PolicyAction::Quarantine, error, None, &security_report));
+        }
 
-        if normalized_name.is_empty() {
-            return Err(ProcessError::DesktopEntry(
-                "Empty normalized name".to_string(),
-            ));
+        // The source user is not known once an image is staged, so only
+        // ingestion can apply rules that match on it.
+        if let Some(policy) = &self.policy {
+            let (decision, sha256) = policy.evaluate(&app, None, security_report.signer.as_deref())?;
+            if decision.action != PolicyAction::Allow {
+                return Err(self.quarantine_rejected(
+                    &app,
+                    decision.action,
+                    ProcessError::PolicyDenied(decision.reason),
+                    Some(&sha256),
+                    &security_report,
+                ));
+            }
         }
 
         debug!("Processing AppImage: {:?} -> {}", app_path, normalized_name);
 
+        // Extract version from AppImage if possible
+        let version = match &options.version {
+            Some(version) => version.clone(),
+            None => self.extract_version_from_appimage(app_path, &normalized_name),
+        };
+
         if self.dry_run {
-            info!("[DRY RUN] Would process: {}", normalized_name);
+            info!("[DRY RUN] Would process: {} {}", normalized_name, version);
             return Ok(ProcessedApp {
                 normalized_name: normalized_name.clone(),
+                version,
                 appimage_path: app_path.to_path_buf(),
             });
         }
 
-        // Extract version from AppImage if possible
-        let version = self.extract_version_from_appimage(app_path, &normalized_name);
+        self.security_checker
+            .record_signer(&normalized_name, &security_report)
+            .map_err(|e| ProcessError::SecurityCheck(format!("Cannot pin signing key: {}", e)))?;
 
         // Install using version manager
         self.version_manager.install_version(&normalized_name, &version, app_path)?;
-
-        // Extract metadata and create desktop entry
-        let (metadata, icon_path) = self.extract_metadata(app_path, &normalized_name)?;
-
-        let current_appimage = self.version_manager.get_appimage_path(&normalized_name, &version);
+        self.version_manager
+            .save_security_report(&normalized_name, &version, &security_report)?;
+
+        // A pinned app stays on its pinned version; the new one is only stored
+        let active_version = self
+            .version_manager
+            .get_current_version(&normalized_name)?
+            .unwrap_or_else(|| version.clone());
+        if active_version != version {
+            info!(
+                "{} is pinned to {}; stored {} without activating it",
+                normalized_name, active_version, version
+            );
+        }
+        let current_appimage = self
+            .version_manager
+            .get_appimage_path(&normalized_name, &active_version);
         let symlink_path = self.symlink_dir.join(&normalized_name);
         self.create_symlink(&current_appimage, &symlink_path)?;
 
-        let desktop_path = self
-            .desktop_dir
-            .join(format!("{}.desktop", normalized_name));
-        self.create_desktop_entry(&metadata, &icon_path, &symlink_path, &desktop_path)?;
+        if options.desktop_entry {
+            // Extract metadata and create desktop entry
+            let (metadata, icon_path) = self.extract_metadata(&current_appimage, &normalized_name)?;
+            let desktop_path = self
+                .desktop_dir
+                .join(format!("{}.desktop", normalized_name));
+            self.create_desktop_entry(&metadata, &icon_path, &symlink_path, &desktop_path)?;
+        }
 
         Ok(ProcessedApp {
             normalized_name,
+            version,
             appimage_path: app_path.to_path_buf(),
         })
     }
 
+    /// Moves a rejected image into quarantine, turning `error` into
+    /// [`ProcessError::Quarantined`]. Without a quarantine, or if moving
+    /// fails, the image stays where it is and `error` is returned.
+    fn quarantine_rejected(
+        &self,
+        app: &AppImage,
+        action: PolicyAction,
+        error: ProcessError,
+        sha256: Option<&str>,
+        security_report: &SecurityReport,
+    ) -> ProcessError {
+        let Some(quarantine) = self
+            .quarantine
+            .as_ref()
+            .or(self.policy.as_ref().map(|policy| &policy.quarantine))
+        else {
+            return error;
+        };
+
+        let reason = error.to_string();
+        if self.dry_run {
+            info!("[DRY RUN] Would quarantine {:?}: {}", app.path, reason);
+            return ProcessError::Quarantined(reason);
+        }
+
+        let sha256 = match sha256 {
+            Some(sha256) => sha256.to_string(),
+            None => app.get_checksum().unwrap_or_default(),
+        };
+        let details = QuarantineDetails {
+            action,
+            reason: &reason,
+            sha256: &sha256,
+            user: None,
+            security_report: Some(security_report),
+        };
+        match quarantine.add(&app.path, details) {
+            Ok(record) => ProcessError::Quarantined(record.reason),
+            Err(e) => {
+                warn!("Cannot quarantine {:?}: {}", app.path, e);
+                error
+            }
+        }
+    }
+
     fn extract_version_from_appimage(&self, app_path: &Path, normalized_name: &str) -> String {
         // Try to extract version from filename
         let filename = app_path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
 
-        // Look for version patterns like -v1.2.3, -1.0.0, v2
-        if let Some(pos) = filename.rfind('-') {
-            let potential_version = &filename[pos + 1..];
-            let version = potential_version
-                .strip_prefix('v')
-                .unwrap_or(potential_version);
-            if version.chars().all(|c| c.is_numeric() || c == '.') {
-                return version.to_string();
-            }
+        // Look for version patterns like -v1.2.3, -1.0.0-rc1, -2024.03.1
+        if let Some(version) = version_from_file_name(filename) {
+            return version;
         }
 
         // If no version found, try to get from AppImage itself
-        if let Ok(_app) = AppImage::new(app_path.to_path_buf()) {
-            // For now, use a timestamp-based version for new apps
-            // In the future, this could extract version from AppImage metadata
+        if let Ok(app) = AppImage::new(app_path.to_path_buf()) {
+            // The newest release in the AppStream metadata, if it ships any
+            if let Some(version) = app
+                .payload()
+                .ok()
+                .and_then(|payload| payload.release_version().ok().flatten())
+            {
+                return version;
+            }
+
+            // Otherwise a timestamp-based version
             use chrono::Utc;
             format!("{}-{}", normalized_name, Utc::now().format("%Y%m%d%H%M%S"))
         } else {
@@ -299,81 +489,63 @@ impl Processor {
         app_path: &Path,
         normalized_name: &str,
     ) -> Result<(Metadata, Option<PathBuf>), ProcessError> {
-        let tmp_dir = tempfile::TempDir::new()?;
-        let app_root = tmp_dir.path().join("squashfs-root");
-
-        debug!("Extracting AppImage: {:?}", app_path);
-
-        let status = Command::new(app_path)
-            .arg("--appimage-extract")
-            .current_dir(tmp_dir.path())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()?;
-
-        if !status.success() {
-            return Err(ProcessError::ExtractionFailed(format!(
-                "AppImage extract failed: {}",
-                status
-            )));
-        }
-
-        if !app_root.exists() {
-            return Err(ProcessError::ExtractionFailed(
-                "squashfs-root not found after extraction".to_string(),
-            ));
-        }
+        debug!("Reading AppImage payload: {:?}", app_path);
 
         let app = AppImage::new(app_path.to_path_buf())?;
         let checksum = app.get_checksum().map_err(ProcessError::AppImage)?;
 
-        let desktop_file = self.find_desktop_entry(&app_root)?;
-        let icon_path = icon_extractor::extract_icon(&app_root, &self.icon_dir, normalized_name)
-            .map_err(|e| {
-                ProcessError::Io(std::io::Error::other(
-                    e.to_string(),
-                ))
-            })?;
+        // Type 1 payloads are ISO 9660 and are not read in-process; those
+        // images are registered from their file name alone.
+        let payload = match app.image_type {
+            AppImageType::Type2 => Some(
+                app.payload()
+                    .map_err(|e| ProcessError::ExtractionFailed(e.to_string()))?,
+            ),
+            AppImageType::Type1 => {
+                debug!("Type 1 AppImage, skipping payload inspection");
+                None
+            }
+        };
+
+        let desktop_entry = match &payload {
+            Some(payload) => payload
+                .desktop_entry()
+                .map_err(|e| ProcessError::ExtractionFailed(e.to_string()))?,
+            None => None,
+        };
 
-        match desktop_file {
-            Some(path) => {
-                debug!("Found desktop entry: {:?}", path);
-                let mut metadata = Metadata::from_desktop_entry(&path)
-                    .map_err(|e| ProcessError::DesktopEntry(e.to_string()))?;
+        let metadata = match desktop_entry {
+            Some((name, content)) => {
+                debug!("Found desktop entry: {}", name);
+                let mut metadata = Metadata::from_desktop_entry_content(&content);
                 metadata.checksum = checksum.clone();
-                Ok((metadata, icon_path))
+                metadata
             }
             None => {
                 debug!("No desktop entry found, using defaults");
-                let display_name = format!(
-                    "{}{}",
-                    normalized_name.chars().next().unwrap().to_uppercase(),
-                    &normalized_name[1..]
-                );
-                let mut metadata = Metadata::new(display_name, checksum);
-                metadata.name = format!(
-                    "{}{}",
-                    normalized_name.chars().next().unwrap().to_uppercase(),
-                    &normalized_name[1..]
-                );
-                Ok((metadata, icon_path))
+                let mut chars = normalized_name.chars();
+                let display_name = match chars.next() {
+                    Some(first) => format!("{}{}", first.to_uppercase(), chars.as_str()),
+                    None => String::new(),
+                };
+                Metadata::new(display_name, checksum)
             }
-        }
-    }
-
-    fn find_desktop_entry(&self, root: &Path) -> Result<Option<PathBuf>, ProcessError> {
-        for entry in fs::read_dir(root)? {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_file()
-                && let Some(ext) = path.extension()
-                    && ext == "desktop" {
-                        return Ok(Some(path));
-                    }
-        }
+        };
 
-        Ok(None)
+        let icon = match &payload {
+            Some(payload) => payload
+                .icon(metadata.icon_path.as_deref())
+                .map_err(|e| ProcessError::ExtractionFailed(e.to_string()))?,
+            None => None,
+        };
+        let icon_path = match icon {
+            Some(icon) => Some(
+                icon_extractor::save_icon(&icon, &self.icon_dir, normalized_name)
+                    .map_err(|e| ProcessError::Io(std::io::Error::other(e.to_string())))?,
+            ),
+            None => None,
+        };
+        Ok((metadata, icon_path))
     }
 
     fn create_desktop_entry(
@@ -459,6 +631,7 @@ impl Processor {
                         if self.cache_entry_is_usable(&cached.normalized_name) {
                             return Ok(ProcessedApp {
                                 normalized_name: cached.normalized_name.clone(),
+                                version: cached.version.clone(),
                                 appimage_path: app_path.to_path_buf(),
                             });
                         }
@@ -539,45 +712,20 @@ impl Processor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::Config;
+    use crate::core::testing::{appimage, test_config, ElfBuilder, SquashfsBuilder};
+    use crate::scan::processor_from_config;
     use std::fs;
     use tempfile::TempDir;
 
     #[test]
     fn desktop_entry_exec_points_to_symlink_target() {
         let temp = TempDir::new().unwrap();
-        let raw_dir = temp.path().join("raw");
-        let bin_dir = temp.path().join("bin");
-        let icon_dir = temp.path().join("icons");
-        let desktop_dir = temp.path().join("desktop");
-        let symlink_dir = temp.path().join("symlinks");
-
-        fs::create_dir_all(&raw_dir).unwrap();
-        fs::create_dir_all(&bin_dir).unwrap();
-        fs::create_dir_all(&icon_dir).unwrap();
-        fs::create_dir_all(&desktop_dir).unwrap();
-        fs::create_dir_all(&symlink_dir).unwrap();
-
-        let mut config = Config::default();
-        config.directories.raw = raw_dir.display().to_string();
-        config.directories.bin = bin_dir.display().to_string();
-        config.directories.icons = icon_dir.display().to_string();
-        config.directories.desktop = desktop_dir.display().to_string();
-        config.directories.symlink = symlink_dir.display().to_string();
-
-        let processor = Processor::new(
-            raw_dir,
-            bin_dir,
-            icon_dir,
-            desktop_dir.clone(),
-            symlink_dir.clone(),
-            VersionManager::new(config),
-            SecurityChecker::new(),
-        );
+        let config = test_config(temp.path());
+        let processor = processor_from_config(&config);
 
         let metadata = Metadata::new("Test App".to_string(), "checksum".to_string());
-        let exec_path = symlink_dir.join("test-app");
-        let desktop_path = desktop_dir.join("test-app.desktop");
+        let exec_path = config.symlink_dir().join("test-app");
+        let desktop_path = config.desktop_dir().join("test-app.desktop");
 
         processor
             .create_desktop_entry(&metadata, &None, &exec_path, &desktop_path)
@@ -589,44 +737,106 @@ mod tests {
     }
 
     #[test]
-    fn cache_entry_is_usable_requires_expected_exec_and_symlink() {
+    fn process_single_appimage_reads_embedded_payload() {
         let temp = TempDir::new().unwrap();
-        let raw_dir = temp.path().join("raw");
-        let bin_dir = temp.path().join("bin");
-        let icon_dir = temp.path().join("icons");
-        let desktop_dir = temp.path().join("desktop");
-        let symlink_dir = temp.path().join("symlinks");
-
-        fs::create_dir_all(&raw_dir).unwrap();
-        fs::create_dir_all(&bin_dir).unwrap();
-        fs::create_dir_all(&icon_dir).unwrap();
-        fs::create_dir_all(&desktop_dir).unwrap();
-        fs::create_dir_all(&symlink_dir).unwrap();
-
-        let mut config = Config::default();
-        config.directories.raw = raw_dir.display().to_string();
-        config.directories.bin = bin_dir.display().to_string();
-        config.directories.icons = icon_dir.display().to_string();
-        config.directories.desktop = desktop_dir.display().to_string();
-        config.directories.symlink = symlink_dir.display().to_string();
-
-        let processor = Processor::new(
-            raw_dir,
-            bin_dir,
-            icon_dir,
-            desktop_dir.clone(),
-            symlink_dir.clone(),
-            VersionManager::new(config),
-            SecurityChecker::new(),
+        let config = test_config(temp.path());
+        let processor = processor_from_config(&config);
+        let icon_dir = config.icon_dir();
+
+        // Not executable and with no real runtime: registration must not run it.
+        let app_path = config.raw_dir().join("Demo-1.0.0-x86_64.AppImage");
+        let image = appimage(
+            &ElfBuilder::new(),
+            &SquashfsBuilder::new()
+                .file(
+                    "demo.desktop",
+                    b"[Desktop Entry]\nName=Demo Tool\nIcon=demo\nCategories=Development;\n",
+                )
+                .file("demo.svg", b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
         );
+        fs::write(&app_path, image).unwrap();
+
+        let processed = processor.process_single_appimage(&app_path).unwrap();
+        assert_eq!(processed.normalized_name, "demo");
+
+        let desktop = fs::read_to_string(config.desktop_dir().join("demo.desktop")).unwrap();
+        assert!(desktop.contains("Name=Demo Tool"));
+        assert!(desktop.contains("Categories=Development"));
+        assert!(desktop.contains(&format!("Icon={}", icon_dir.join("demo.svg").display())));
+        assert!(icon_dir.join("demo.svg").exists());
+        assert!(config.symlink_dir().join("demo").exists());
+    }
+
+    #[test]
+    fn process_single_appimage_takes_version_from_appstream() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let processor = processor_from_config(&config);
+
+        let app_path = config.raw_dir().join("Demo.AppImage");
+        let image = appimage(
+            &ElfBuilder::new(),
+            &SquashfsBuilder::new().file(
+                "usr/share/metainfo/org.example.Demo.metainfo.xml",
+                br#"<component><releases><release version="3.1.4"/></releases></component>"#,
+            ),
+        );
+        fs::write(&app_path, image).unwrap();
+
+        let processed = processor.process_single_appimage(&app_path).unwrap();
+        assert_eq!(processed.version, "3.1.4");
+    }
+
+    #[test]
+    fn process_single_appimage_keeps_pinned_version_active() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let processor = processor_from_config(&config);
+        let image = |version: &str| {
+            appimage(
+                &ElfBuilder::new(),
+                &SquashfsBuilder::new().file(
+                    "demo.desktop",
+                    format!("[Desktop Entry]\nName=Demo {}\n", version).as_bytes(),
+                ),
+            )
+        };
+
+        let old_path = config.raw_dir().join("Demo-1.0.0-x86_64.AppImage");
+        fs::write(&old_path, image("1.0.0")).unwrap();
+        processor.process_single_appimage(&old_path).unwrap();
+        let version_manager = VersionManager::new(config.clone());
+        version_manager.pin_version("demo", None).unwrap();
+
+        let new_path = config.raw_dir().join("Demo-2.0.0-x86_64.AppImage");
+        fs::write(&new_path, image("2.0.0")).unwrap();
+        let processed = processor.process_single_appimage(&new_path).unwrap();
+        assert_eq!(processed.version, "2.0.0");
+
+        assert_eq!(
+            version_manager.get_current_version("demo").unwrap().as_deref(),
+            Some("1.0.0")
+        );
+        assert!(version_manager.get_appimage_path("demo", "2.0.0").exists());
+        assert_eq!(
+            fs::read_link(config.symlink_dir().join("demo")).unwrap(),
+            version_manager.get_appimage_path("demo", "1.0.0")
+        );
+    }
+
+    #[test]
+    fn cache_entry_is_usable_requires_expected_exec_and_symlink() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        let processor = processor_from_config(&config);
 
         let name = "demoapp";
-        let symlink_path = symlink_dir.join(name);
+        let symlink_path = config.symlink_dir().join(name);
         let target = temp.path().join("demoapp.AppImage");
         fs::write(&target, b"fake").unwrap();
         std::os::unix::fs::symlink(&target, &symlink_path).unwrap();
 
-        let desktop_path = desktop_dir.join(format!("{}.desktop", name));
+        let desktop_path = config.desktop_dir().join(format!("{}.desktop", name));
         fs::write(
             &desktop_path,
             format!(
@@ -647,6 +857,31 @@ mod tests {
         .unwrap();
         assert!(!processor.cache_entry_is_usable(name));
     }
-}
-
 
+    #[test]
+    fn process_all_quarantines_rejected_images() {
+        let temp = TempDir::new().unwrap();
+        let mut config = test_config(temp.path());
+        config.security.require_signatures = true;
+        config.security.trusted_keys_dir = temp.path().join("keys").display().to_string();
+        let processor = processor_from_config(&config);
+        let quarantine = Quarantine::new(config.quarantine_dir());
+
+        let app_path = config.raw_dir().join("Unsigned.AppImage");
+        fs::write(&app_path, crate::core::testing::fake_appimage(b"")).unwrap();
+
+        let report = processor.process_all().unwrap();
+        assert_eq!(report.quarantined.len(), 1);
+        assert!(report.failed.is_empty());
+        assert!(!app_path.exists());
+
+        let records = quarantine.list().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].reason, "Security check failed: No signature found");
+        let security_report = records[0].security_report.as_ref().unwrap();
+        assert_eq!(
+            security_report.overall_status,
+            SecurityStatus::Error("No signature found".to_string())
+        );
+    }
+}
@@ -1,25 +1,22 @@
 // src/scan.rs
 
+use crate::audit::AuditLog;
+use crate::cli::say;
 use crate::config::Config;
 use crate::core::VersionManager;
-use crate::registrar::Processor;
+use crate::policy::PolicyEnforcer;
+use crate::quarantine::Quarantine;
+use crate::registrar::{ProcessReport, Processor};
 use crate::security::SecurityChecker;
 use std::io;
 
-pub fn run_scan() -> io::Result<()> {
-    let config =
-        Config::load().map_err(|e| io::Error::other(format!("Failed to load config: {}", e)))?;
-
-    println!("🔄 Triggering full AppImage re-registration...");
-
-    let version_manager = VersionManager::new(config.clone());
-    let security_checker = SecurityChecker {
-        verify_signatures: config.security.verify_signatures,
-        require_signatures: config.security.require_signatures,
-        warn_unsigned: config.security.warn_unsigned,
-        detect_sandboxing: config.security.detect_sandboxing,
-    };
-    let processor = Processor::new(
+/// The processor `scan` uses, with security, policy, quarantine and audit
+/// settings taken from `config`.
+pub fn processor_from_config(config: &Config) -> Processor {
+    let version_manager =
+        VersionManager::new(config.clone()).with_audit(AuditLog::from_config(config));
+    let security_checker = SecurityChecker::from_config(config);
+    Processor::new(
         config.raw_dir(),
         config.bin_dir(),
         config.icon_dir(),
@@ -33,22 +30,40 @@ pub fn run_scan() -> io::Result<()> {
         config.performance.parallel_processing_enabled,
         config.performance.incremental_scan_enabled,
         None, // TODO: implement last scan time tracking
-    );
+    )
+    .with_policy(PolicyEnforcer::from_config(config))
+    .with_quarantine(Quarantine::new(config.quarantine_dir()))
+}
+
+pub fn run_scan(dry_run: bool) -> io::Result<ProcessReport> {
+    let config =
+        Config::load().map_err(|e| io::Error::other(format!("Failed to load config: {}", e)))?;
+
+    say!("🔄 Triggering full AppImage re-registration...");
+
+    let processor = processor_from_config(&config).with_dry_run(dry_run);
 
     let report = processor
         .process_all()
         .map_err(|e| io::Error::other(format!("Failed to process AppImages: {}", e)))?;
 
-    println!(
+    say!(
         "✅ Re-registration complete: {} processed.",
         report.success_count()
     );
 
+    if !report.quarantined.is_empty() {
+        say!(
+            "🚫 {} AppImages quarantined by policy.",
+            report.quarantined.len()
+        );
+    }
+
     if !report.failed.is_empty() {
-        println!("⚠️  {} AppImages failed to process.", report.failed.len());
+        say!("⚠️  {} AppImages failed to process.", report.failed.len());
     }
 
-    Ok(())
+    Ok(report)
 }
 
 #[cfg(test)]
@@ -1,10 +1,14 @@
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
 use std::process::Command;
 use thiserror::Error;
-use tracing::warn;
+use tracing::{debug, info, warn};
 
+use crate::config::Config;
 use crate::core::AppImage;
+use crate::core::update_info::EmbeddedSignature;
+use crate::trust::{self, KeyPins, TrustError, TrustStore};
 
 #[derive(Debug, Error)]
 pub enum SecurityError {
@@ -16,20 +20,27 @@ pub enum SecurityError {
 
     #[error("Security check failed: {0}")]
     CheckFailed(String),
+
+    #[error("Trust store error: {0}")]
+    Trust(#[from] TrustError),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SecurityStatus {
     Secure,
     Warning(String),
     Error(String),
+    /// Signed by a different key than the one pinned for the app
+    KeyChanged(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SecurityReport {
     pub checksum_verified: bool,
     pub signature_present: bool,
     pub signature_verified: Option<bool>, // None if no signature, Some(true/false) if present
+    pub signature_trusted: bool,          // Signed by a key in the trust store
+    pub signer: Option<String>,           // Fingerprint of the key behind a valid signature
     pub sandboxing_detected: bool,
     pub overall_status: SecurityStatus,
 }
@@ -40,6 +51,8 @@ impl SecurityReport {
             checksum_verified: false,
             signature_present: false,
             signature_verified: None,
+            signature_trusted: false,
+            signer: None,
             sandboxing_detected: false,
             overall_status: SecurityStatus::Secure,
         }
@@ -65,10 +78,11 @@ impl SecurityReport {
 
 pub struct SecurityChecker {
     pub verify_signatures: bool,
-    #[allow(dead_code)]
     pub require_signatures: bool,
     pub warn_unsigned: bool,
     pub detect_sandboxing: bool,
+    pub trust_store: TrustStore,
+    pub key_pins: Option<KeyPins>,
 }
 
 impl Default for SecurityChecker {
@@ -78,6 +92,8 @@ impl Default for SecurityChecker {
             require_signatures: false,
             warn_unsigned: true,
             detect_sandboxing: true,
+            trust_store: TrustStore::new(PathBuf::from("/etc/appiman/keys")),
+            key_pins: None,
         }
     }
 }
@@ -88,19 +104,101 @@ impl SecurityChecker {
         Self::default()
     }
 
+    pub fn from_config(config: &Config) -> Self {
+        let security = &config.security;
+        SecurityChecker {
+            verify_signatures: security.verify_signatures,
+            require_signatures: security.require_signatures,
+            warn_unsigned: security.warn_unsigned,
+            detect_sandboxing: security.detect_sandboxing,
+            trust_store: TrustStore::new(PathBuf::from(&security.trusted_keys_dir)),
+            key_pins: Some(KeyPins::new(config.bin_dir())),
+        }
+    }
+
     /// Perform all security checks on an AppImage
+    #[allow(dead_code)]
     pub fn check_appimage(&self, appimage: &AppImage) -> Result<SecurityReport, SecurityError> {
+        // Requiring signatures implies verifying them.
+        self.check(appimage, self.verify_signatures || self.require_signatures)
+    }
+
+    /// Perform all security checks on an AppImage registered as `app_name`,
+    /// including that it is signed by the key pinned for the app. Signatures
+    /// of pinned apps are always verified.
+    pub fn check_app(
+        &self,
+        app_name: &str,
+        appimage: &AppImage,
+    ) -> Result<SecurityReport, SecurityError> {
+        let pin = match &self.key_pins {
+            Some(pins) => pins.get(app_name)?,
+            None => None,
+        };
+        let verify = self.verify_signatures || self.require_signatures || pin.is_some();
+        let mut report = self.check(appimage, verify)?;
+
+        // Learn the signer for trust on first use, without failing images
+        // that were accepted before, e.g. when gpg is not installed.
+        if !verify && report.signature_present && self.key_pins.is_some() {
+            match self.check_appimage_signer(appimage) {
+                Ok(signer) => report.signer = signer,
+                Err(e) => warn!("Cannot determine signer of {:?}: {}", appimage.path, e),
+            }
+        }
+
+        if let Some(pin) = pin
+            && report.signer.as_deref() != Some(pin.fingerprint.as_str())
+        {
+            report.overall_status = SecurityStatus::KeyChanged(match &report.signer {
+                Some(signer) => format!(
+                    "Signed by {} but {} is pinned to {}",
+                    signer, app_name, pin.fingerprint
+                ),
+                None => format!(
+                    "No valid signature but {} is pinned to {}",
+                    app_name, pin.fingerprint
+                ),
+            });
+        }
+
+        Ok(report)
+    }
+
+    /// Pin the key behind `report` for `app_name` if the app has no pin yet
+    pub fn record_signer(&self, app_name: &str, report: &SecurityReport) -> Result<(), SecurityError> {
+        if let (Some(pins), Some(signer)) = (&self.key_pins, &report.signer)
+            && pins.get(app_name)?.is_none()
+        {
+            pins.pin(app_name, signer)?;
+            info!("Pinned signing key {} for {}", signer, app_name);
+        }
+        Ok(())
+    }
+
+    /// Fingerprint of the key behind a valid signature on `appimage`
+    pub fn check_appimage_signer(
+        &self,
+        appimage: &AppImage,
+    ) -> Result<Option<String>, SecurityError> {
+        let mut report = SecurityReport::new();
+        self.check_signatures(appimage, &mut report)?;
+        Ok(report.signer)
+    }
+
+    fn check(&self, appimage: &AppImage, verify: bool) -> Result<SecurityReport, SecurityError> {
         let mut report = SecurityReport::new();
 
         // Always verify checksum (SHA256 integrity)
         report.checksum_verified = self.verify_checksum(appimage)?;
 
-        // Check for signature file
-        report.signature_present = self.has_signature_file(appimage)?;
+        // Check for a detached signature file or one embedded by appimagetool
+        report.signature_present =
+            self.has_signature_file(appimage)? || self.has_embedded_signature(appimage);
 
-        // Verify signature if present and verification is enabled
-        if report.signature_present && self.verify_signatures {
-            report.signature_verified = Some(self.verify_signature(appimage)?);
+        // Verify signatures if present and verification is enabled
+        if verify {
+            self.check_signatures(appimage, &mut report)?;
         }
 
         // Detect sandboxing usage
@@ -114,6 +212,27 @@ impl SecurityChecker {
         Ok(report)
     }
 
+    fn check_signatures(
+        &self,
+        appimage: &AppImage,
+        report: &mut SecurityReport,
+    ) -> Result<(), SecurityError> {
+        if let Some(signature) = self.embedded_signature(appimage) {
+            let digest = appimage.signed_digest()?;
+            self.verify_embedded_signature(&signature, digest.as_bytes(), report)?;
+        }
+        if self.has_signature_file(appimage)? && report.signature_verified != Some(false) {
+            let valid = self.verify_signature(appimage)?;
+            report.signature_verified = Some(valid);
+            if valid && !report.signature_trusted {
+                let signature = fs::read(appimage.path.with_extension("sig"))?;
+                let data = fs::read(&appimage.path)?;
+                self.check_trust(&signature, &data, report)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Verify AppImage checksum integrity
     fn verify_checksum(&self, appimage: &AppImage) -> Result<bool, SecurityError> {
         // The AppImage struct already has get_checksum() which computes SHA256
@@ -135,6 +254,66 @@ impl SecurityChecker {
         Ok(sig_path.exists())
     }
 
+    /// Check the runtime's `.sha256_sig` section for an embedded signature
+    fn has_embedded_signature(&self, appimage: &AppImage) -> bool {
+        self.embedded_signature(appimage).is_some()
+    }
+
+    fn embedded_signature(
+        &self,
+        appimage: &AppImage,
+    ) -> Option<EmbeddedSignature> {
+        match appimage.embedded_signature() {
+            Ok(signature) => signature,
+            Err(e) => {
+                warn!("Cannot read embedded signature of {:?}: {}", appimage.path, e);
+                None
+            }
+        }
+    }
+
+    /// Verify an appimagetool signature over the image digest. The signature
+    /// is valid if it matches the key embedded next to it, but only trusted
+    /// if it matches a key from the trust store.
+    fn verify_embedded_signature(
+        &self,
+        signature: &EmbeddedSignature,
+        digest: &[u8],
+        report: &mut SecurityReport,
+    ) -> Result<(), SecurityError> {
+        let signature_bytes = signature.signature.as_bytes();
+        self.check_trust(signature_bytes, digest, report)?;
+
+        let embedded_signer = match &signature.key {
+            Some(key) => {
+                trust::verify_detached(&[key.as_bytes().to_vec()], signature_bytes, digest)?
+            }
+            None => None,
+        };
+        report.signature_verified =
+            Some(report.signature_trusted || embedded_signer.is_some());
+        if report.signer.is_none() {
+            report.signer = embedded_signer;
+        }
+        Ok(())
+    }
+
+    /// Record whether `signature` over `data` was made by a trusted key
+    fn check_trust(
+        &self,
+        signature: &[u8],
+        data: &[u8],
+        report: &mut SecurityReport,
+    ) -> Result<(), SecurityError> {
+        let keys = self.trust_store.keys()?;
+        if let Some(fingerprint) = trust::verify_detached(&keys, signature, data)? {
+            debug!("Signature made by trusted key {}", fingerprint);
+            report.signature_trusted = true;
+            report.signer = Some(fingerprint);
+        }
+        Ok(())
+    }
+
     /// Verify GPG signature if present
     fn verify_signature(&self, appimage: &AppImage) -> Result<bool, SecurityError> {
         let sig_path = appimage.path.with_extension("sig");
@@ -158,70 +337,35 @@ impl SecurityChecker {
 
     /// Detect if AppImage uses sandboxing (firejail, bubblewrap)
     fn detect_sandboxing_usage(&self, appimage: &AppImage) -> Result<bool, SecurityError> {
-        // Extract AppImage and check for sandboxing configuration
-        let temp_dir = tempfile::TempDir::new()
-            .map_err(|e| SecurityError::CheckFailed(format!("Failed to create temp dir: {}", e)))?;
-
-        let extract_status = Command::new(&appimage.path)
-            .arg("--appimage-extract")
-            .current_dir(&temp_dir)
-            .status()
-            .map_err(|e| SecurityError::CheckFailed(format!("Extraction failed: {}", e)))?;
-
-        if !extract_status.success() {
-            return Ok(false);
-        }
-
-        let app_root = temp_dir.path().join("squashfs-root");
+        // Inspect the embedded filesystem directly; the image is never executed.
+        let payload = match appimage.payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Cannot inspect payload of {:?}: {}", appimage.path, e);
+                return Ok(false);
+            }
+        };
 
         // Check for firejail profile
-        let firejail_profile = app_root.join(format!("{}.profile", appimage.normalize_name()));
+        let firejail_profile = format!("{}.profile", appimage.normalize_name());
 
         // Check for bubblewrap wrapper
-        let bubblewrap_wrapper = app_root.join("usr/bin/bwrap-wrapper");
+        let bubblewrap_wrapper = "usr/bin/bwrap-wrapper";
 
         // Check for desktop file Exec line containing firejail or bwrap
-        let desktop_files = self.find_files_with_extension(&app_root, "desktop");
-        let has_sandbox_in_desktop = desktop_files
-            .iter()
-            .any(|path| self.desktop_file_uses_sandboxing(path));
-
-        let has_firejail = firejail_profile.exists() || has_sandbox_in_desktop;
-        let has_bubblewrap = bubblewrap_wrapper.exists() || has_sandbox_in_desktop;
-
-        Ok(has_firejail || has_bubblewrap)
-    }
-
-    /// Find all files with a specific extension in a directory
-    fn find_files_with_extension(&self, dir: &Path, extension: &str) -> Vec<std::path::PathBuf> {
-        let mut files = Vec::new();
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Some(ext) = path.extension() {
-                        if ext == extension {
-                            files.push(path);
-                        }
-                    }
-                }
+        let has_sandbox_in_desktop = match payload.desktop_entry() {
+            Ok(Some((_, content))) => desktop_entry_uses_sandboxing(&content),
+            Ok(None) => false,
+            Err(e) => {
+                warn!("Cannot read desktop entry of {:?}: {}", appimage.path, e);
+                false
             }
-        }
-        files
-    }
+        };
 
-    /// Check if desktop file Exec line contains sandboxing commands
-    fn desktop_file_uses_sandboxing(&self, desktop_path: &Path) -> bool {
-        if let Ok(content) = fs::read_to_string(desktop_path) {
-            let exec_line = content
-                .lines()
-                .find(|line| line.starts_with("Exec="))
-                .unwrap_or("");
+        let has_firejail = payload.exists(&firejail_profile) || has_sandbox_in_desktop;
+        let has_bubblewrap = payload.exists(bubblewrap_wrapper) || has_sandbox_in_desktop;
 
-            exec_line.contains("firejail") || exec_line.contains("bwrap")
-        } else {
-            false
-        }
+        Ok(has_firejail || has_bubblewrap)
     }
 
     /// Assess overall security status based on checks
@@ -240,6 +384,14 @@ impl SecurityChecker {
             }
         }
 
+        if self.require_signatures && !report.signature_trusted {
+            return SecurityStatus::Error(if report.signature_present {
+                "Not signed by a trusted key".to_string()
+            } else {
+                "No signature found".to_string()
+            });
+        }
+
         // Warnings for missing security features
         let mut warnings = Vec::new();
 
@@ -247,6 +399,10 @@ impl SecurityChecker {
             warnings.push("No signature found - cannot verify authenticity".to_string());
         }
 
+        if report.signature_verified == Some(true) && !report.signature_trusted {
+            warnings.push("Signature is valid but not from a trusted key".to_string());
+        }
+
         if self.detect_sandboxing && !report.sandboxing_detected {
             warnings.push(
                 "No sandboxing detected - AppImage runs without security isolation".to_string(),
@@ -268,7 +424,9 @@ impl SecurityChecker {
                 appimage.normalize_name(),
                 message
             );
-        } else if let SecurityStatus::Error(message) = &report.overall_status {
+        } else if let SecurityStatus::Error(message) | SecurityStatus::KeyChanged(message) =
+            &report.overall_status
+        {
             warn!(
                 "Security error for {}: {}",
                 appimage.normalize_name(),
@@ -278,9 +436,20 @@ impl SecurityChecker {
     }
 }
 
+/// Check if desktop entry Exec line contains sandboxing commands
+fn desktop_entry_uses_sandboxing(content: &str) -> bool {
+    let exec_line = content
+        .lines()
+        .find(|line| line.starts_with("Exec="))
+        .unwrap_or("");
+
+    exec_line.contains("firejail") || exec_line.contains("bwrap")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::testing::{appimage, fake_appimage, ElfBuilder, SquashfsBuilder};
     use std::fs;
     use tempfile::TempDir;
 
@@ -297,13 +466,40 @@ mod tests {
         assert!(matches!(report.overall_status, SecurityStatus::Warning(_)));
     }
 
+    #[test]
+    fn detect_sandboxing_reads_embedded_desktop_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let app_path = temp_dir.path().join("test.AppImage");
+        let image = appimage(
+            &ElfBuilder::new(),
+            &SquashfsBuilder::new().file(
+                "test.desktop",
+                b"[Desktop Entry]\nName=Test\nExec=firejail test\n",
+            ),
+        );
+        fs::write(&app_path, image).unwrap();
+
+        let app = AppImage::new(app_path).unwrap();
+        assert!(SecurityChecker::new().detect_sandboxing_usage(&app).unwrap());
+    }
+
+    #[test]
+    fn detect_sandboxing_is_false_for_unreadable_payload() {
+        let temp_dir = TempDir::new().unwrap();
+        let app_path = temp_dir.path().join("test.AppImage");
+        fs::write(&app_path, fake_appimage(b"exec firejail")).unwrap();
+
+        let app = AppImage::new(app_path).unwrap();
+        assert!(!SecurityChecker::new().detect_sandboxing_usage(&app).unwrap());
+    }
+
     #[test]
     fn has_signature_file_detects_sig_files() {
         let temp_dir = TempDir::new().unwrap();
         let app_path = temp_dir.path().join("test.AppImage");
         let sig_path = temp_dir.path().join("test.sig");
 
-        fs::write(&app_path, b"test").unwrap();
+        fs::write(&app_path, fake_appimage(b"")).unwrap();
         fs::write(&sig_path, b"signature").unwrap();
 
         let app = AppImage::new(app_path).unwrap();
@@ -312,12 +508,162 @@ mod tests {
         assert!(checker.has_signature_file(&app).unwrap());
     }
 
+    #[test]
+    fn check_appimage_reports_embedded_signature() {
+        let temp_dir = TempDir::new().unwrap();
+        let app_path = temp_dir.path().join("test.AppImage");
+        let runtime = ElfBuilder::new()
+            .section(".sha256_sig", b"-----BEGIN PGP SIGNATURE-----\0")
+            .section(".sig_key", &[0u8; 64]);
+        fs::write(&app_path, runtime.build()).unwrap();
+
+        let app = AppImage::new(app_path).unwrap();
+        let report = SecurityChecker::new().check_appimage(&app).unwrap();
+
+        assert!(report.signature_present);
+        assert_eq!(report.signature_verified, None);
+    }
+
+    /// Writes an AppImage signed the way `appimagetool --sign` does, returning
+    /// the signer's armored public key.
+    fn signed_appimage(dir: &std::path::Path, app_path: &std::path::Path) -> Vec<u8> {
+        let home = dir.join("gnupg");
+        fs::create_dir(&home).unwrap();
+        fs::set_permissions(&home, std::os::unix::fs::PermissionsExt::from_mode(0o700)).unwrap();
+        let gpg = || {
+            let mut command = Command::new("gpg");
+            command.arg("--homedir").arg(&home).args(["--batch", "--quiet"]);
+            command
+        };
+        assert!(gpg()
+            .args(["--passphrase", "", "--quick-gen-key", "Test <test@example.com>"])
+            .args(["ed25519", "sign", "never"])
+            .status()
+            .unwrap()
+            .success());
+        let key = gpg().args(["--armor", "--export"]).output().unwrap().stdout;
+
+        let runtime = |sig: &[u8], key: &[u8]| {
+            let mut sig = sig.to_vec();
+            let mut key = key.to_vec();
+            sig.resize(4096, 0);
+            key.resize(4096, 0);
+            ElfBuilder::new()
+                .section(".sha256_sig", &sig)
+                .section(".sig_key", &key)
+                .build()
+        };
+        fs::write(app_path, runtime(b"", b"")).unwrap();
+        let digest = AppImage::new(app_path.to_path_buf())
+            .unwrap()
+            .signed_digest()
+            .unwrap();
+
+        let digest_path = dir.join("digest");
+        fs::write(&digest_path, digest).unwrap();
+        let signature = gpg()
+            .args(["--armor", "--detach-sign", "--output", "-"])
+            .arg(&digest_path)
+            .output()
+            .unwrap()
+            .stdout;
+        fs::write(app_path, runtime(&signature, &key)).unwrap();
+        key
+    }
+
+    #[test]
+    fn embedded_signature_is_trusted_only_with_key_in_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let app_path = temp_dir.path().join("test.AppImage");
+        let key = signed_appimage(temp_dir.path(), &app_path);
+        let app = AppImage::new(app_path).unwrap();
+
+        let keys_dir = temp_dir.path().join("keys");
+        let checker = SecurityChecker {
+            require_signatures: true,
+            detect_sandboxing: false,
+            trust_store: TrustStore::new(keys_dir.clone()),
+            ..SecurityChecker::default()
+        };
+
+        let report = checker.check_appimage(&app).unwrap();
+        assert_eq!(report.signature_verified, Some(true));
+        assert!(!report.signature_trusted);
+        assert_eq!(
+            report.overall_status,
+            SecurityStatus::Error("Not signed by a trusted key".to_string())
+        );
+
+        fs::create_dir(&keys_dir).unwrap();
+        fs::write(keys_dir.join("vendor.asc"), key).unwrap();
+        let report = checker.check_appimage(&app).unwrap();
+        assert!(report.signature_trusted);
+        assert!(report.signer.is_some());
+        assert_eq!(report.overall_status, SecurityStatus::Secure);
+    }
+
+    #[test]
+    fn check_app_rejects_images_signed_by_another_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let signed = |name: &str| {
+            let dir = temp_dir.path().join(name);
+            fs::create_dir(&dir).unwrap();
+            let app_path = dir.join("test.AppImage");
+            signed_appimage(&dir, &app_path);
+            AppImage::new(app_path).unwrap()
+        };
+        let original = signed("original");
+        let other = signed("other");
+        let unsigned_path = temp_dir.path().join("test.AppImage");
+        fs::write(&unsigned_path, fake_appimage(b"")).unwrap();
+        let unsigned = AppImage::new(unsigned_path).unwrap();
+
+        let checker = SecurityChecker {
+            detect_sandboxing: false,
+            trust_store: TrustStore::new(temp_dir.path().join("keys")),
+            key_pins: Some(KeyPins::new(temp_dir.path().join("bin"))),
+            ..SecurityChecker::default()
+        };
+
+        // Trust on first use
+        let report = checker.check_app("test", &original).unwrap();
+        assert!(report.signer.is_some());
+        checker.record_signer("test", &report).unwrap();
+        let report = checker.check_app("test", &original).unwrap();
+        assert!(!matches!(report.overall_status, SecurityStatus::KeyChanged(_)));
+
+        for image in [&other, &unsigned] {
+            let report = checker.check_app("test", image).unwrap();
+            assert!(matches!(report.overall_status, SecurityStatus::KeyChanged(_)));
+        }
+    }
+
+    #[test]
+    fn require_signatures_rejects_unsigned_appimage() {
+        let temp_dir = TempDir::new().unwrap();
+        let app_path = temp_dir.path().join("test.AppImage");
+        fs::write(&app_path, fake_appimage(b"")).unwrap();
+
+        let checker = SecurityChecker {
+            require_signatures: true,
+            trust_store: TrustStore::new(temp_dir.path().join("keys")),
+            ..SecurityChecker::default()
+        };
+        let report = checker
+            .check_appimage(&AppImage::new(app_path).unwrap())
+            .unwrap();
+        assert_eq!(
+            report.overall_status,
+            SecurityStatus::Error("No signature found".to_string())
+        );
+    }
+
     #[test]
     fn has_signature_file_returns_false_when_missing() {
         let temp_dir = TempDir::new().unwrap();
         let app_path = temp_dir.path().join("test.AppImage");
 
-        fs::write(&app_path, b"test").unwrap();
+        fs::write(&app_path, fake_appimage(b"")).unwrap();
 
         let app = AppImage::new(app_path).unwrap();
         let checker = SecurityChecker::new();
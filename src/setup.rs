@@ -1,5 +1,6 @@
 // src/setup.rs
 
+use crate::cli::{install_shell_integration, say};
 use crate::privileges::require_root;
 use std::fs;
 use std::io;
@@ -49,7 +50,7 @@ fn install_appiman_binary(bin_dir: &Path) -> io::Result<()> {
     let source = install_source_executable()?;
     let dest = bin_dir.join("appiman");
 
-    println!(
+    say!(
         "Installing appiman binary: {} -> {}",
         source.display(),
         dest.display()
@@ -63,10 +64,15 @@ fn install_appiman_binary(bin_dir: &Path) -> io::Result<()> {
     Ok(())
 }
 
-fn initialize_impl(base_dir: &Path, bin_dir: &Path, unit_dir: &Path) -> io::Result<()> {
+fn initialize_impl(
+    base_dir: &Path,
+    bin_dir: &Path,
+    unit_dir: &Path,
+    share_dir: &Path,
+) -> io::Result<()> {
     for subdir in APP_SUBDIRS {
         let dir = base_dir.join(subdir);
-        println!("Creating directory: {}", dir.display());
+        say!("Creating directory: {}", dir.display());
         fs::create_dir_all(dir)?;
     }
 
@@ -77,26 +83,44 @@ fn initialize_impl(base_dir: &Path, bin_dir: &Path, unit_dir: &Path) -> io::Resu
 
     for (name, contents) in UNIT_ASSETS {
         let dst = unit_dir.join(name);
-        println!("Installing unit: {} → {}", name, dst.display());
+        say!("Installing unit: {} → {}", name, dst.display());
         fs::write(&dst, contents)?;
         set_mode(&dst, 0o644)?;
     }
 
+    install_shell_integration(share_dir)?;
+
     Ok(())
 }
 
-pub fn initialize() -> io::Result<()> {
-    println!("🔧 Initializing AppImage management system...");
+pub fn initialize(dry_run: bool) -> io::Result<()> {
+    say!("🔧 Initializing AppImage management system...");
 
     require_root()?;
 
     let base_dir = Path::new("/opt/applications");
     let bin_dir = Path::new("/usr/local/bin");
     let unit_dir = Path::new("/etc/systemd/system");
+    let share_dir = Path::new("/usr/share");
+
+    if dry_run {
+        for subdir in APP_SUBDIRS {
+            say!("[DRY RUN] Would create directory: {}", base_dir.join(subdir).display());
+        }
+        say!("[DRY RUN] Would install appiman binary to {}", bin_dir.display());
+        for (name, _) in UNIT_ASSETS {
+            say!("[DRY RUN] Would install unit: {}", unit_dir.join(name).display());
+        }
+        say!(
+            "[DRY RUN] Would install completions and man pages below {}",
+            share_dir.display()
+        );
+        return Ok(());
+    }
 
-    initialize_impl(base_dir, bin_dir, unit_dir)?;
+    initialize_impl(base_dir, bin_dir, unit_dir, share_dir)?;
 
-    println!("✅ Initialization complete. Run `appiman enable` to activate services.");
+    say!("✅ Initialization complete. Run `appiman enable` to activate services.");
     Ok(())
 }
 
@@ -114,8 +138,9 @@ mod tests {
         let base_dir = root.path().join("opt/applications");
         let bin_dir = root.path().join("usr/local/bin");
         let unit_dir = root.path().join("etc/systemd/system");
+        let share_dir = root.path().join("usr/share");
 
-        initialize_impl(&base_dir, &bin_dir, &unit_dir).unwrap();
+        initialize_impl(&base_dir, &bin_dir, &unit_dir, &share_dir).unwrap();
 
         for subdir in APP_SUBDIRS {
             assert!(base_dir.join(subdir).is_dir(), "missing {subdir} dir");
@@ -131,6 +156,27 @@ mod tests {
             assert!(path.is_file(), "missing unit {}", path.display());
             assert_eq!(fs::read_to_string(&path).unwrap(), *contents);
         }
+
+        assert!(share_dir.join("bash-completion/completions/appiman").is_file());
+        assert!(share_dir.join("man/man1/appiman.1").is_file());
+    }
+
+    #[test]
+    fn service_units_treat_nothing_to_do_as_success() {
+        let expected = format!(
+            "SuccessExitStatus={}",
+            crate::output::EXIT_NOTHING_TO_DO
+        );
+        for (name, contents) in UNIT_ASSETS {
+            if !name.ends_with(".service") {
+                continue;
+            }
+            assert!(
+                contents.lines().any(|line| line.trim() == expected),
+                "unit {name} does not accept exit code {}",
+                crate::output::EXIT_NOTHING_TO_DO
+            );
+        }
     }
 
     #[cfg(unix)]
@@ -141,8 +187,9 @@ mod tests {
         let base_dir = root.path().join("opt/applications");
         let bin_dir = root.path().join("usr/local/bin");
         let unit_dir = root.path().join("etc/systemd/system");
+        let share_dir = root.path().join("usr/share");
 
-        initialize_impl(&base_dir, &bin_dir, &unit_dir).unwrap();
+        initialize_impl(&base_dir, &bin_dir, &unit_dir, &share_dir).unwrap();
 
         for (name, _) in UNIT_ASSETS {
             let mode = fs::metadata(unit_dir.join(name))
@@ -1,5 +1,8 @@
 use crate::config::Config;
 use crate::core::{AppImage, VersionManager};
+use crate::lock::{Holder, Lock};
+use crate::output::Report;
+use crate::quarantine::{Quarantine, QuarantineRecord};
 use crate::security::SecurityChecker;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -33,6 +36,9 @@ impl From<StatusError> for std::io::Error {
 pub struct AppImageStatus {
     pub name: String,
     pub version: String,
+    /// Update channel the app follows, if one was set.
+    #[serde(default)]
+    pub channel: Option<String>,
     pub path: String,
     pub size_bytes: u64,
     pub registered_at: Option<String>,
@@ -54,6 +60,10 @@ pub struct SystemStatus {
     pub storage_usage: StorageUsage,
     pub last_scan: Option<String>,
     pub performance: Option<PerformanceMetrics>,
+    pub quarantined: Vec<QuarantineRecord>,
+    /// The appiman run holding the global lock, if any.
+    #[serde(default)]
+    pub lock_holder: Option<Holder>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +80,7 @@ pub enum SecurityStatus {
     Secure,
     Warning(String),
     Error(String),
+    KeyChanged(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,6 +98,8 @@ pub struct StorageUsage {
     pub total_size_bytes: u64,
 }
 
+impl Report for SystemStatus {}
+
 pub struct StatusReporter {
     config: Config,
     version_manager: VersionManager,
@@ -102,11 +115,13 @@ impl StatusReporter {
         })
     }
 
-    fn get_status(&self) -> Result<SystemStatus, StatusError> {
+    pub fn get_status(&self) -> Result<SystemStatus, StatusError> {
         let systemd_units = self.get_systemd_status()?;
         let registered_appimages = self.get_registered_appimages()?;
         let storage_usage = self.get_storage_usage()?;
         let last_scan = self.get_last_scan_timestamp();
+        let quarantined = self.get_quarantined()?;
+        let lock_holder = Lock::global(&self.config).holder()?;
 
         Ok(SystemStatus {
             systemd_units,
@@ -114,9 +129,17 @@ impl StatusReporter {
             storage_usage,
             last_scan,
             performance: None, // TODO: load from cache or config
+            quarantined,
+            lock_holder,
         })
     }
 
+    fn get_quarantined(&self) -> Result<Vec<QuarantineRecord>, StatusError> {
+        Quarantine::new(self.config.quarantine_dir())
+            .list()
+            .map_err(|e| StatusError::JsonError(e.to_string()))
+    }
+
     fn get_systemd_status(&self) -> Result<Vec<UnitStatus>, StatusError> {
         let mut units = Vec::new();
         let unit_names = vec![
@@ -159,12 +182,7 @@ impl StatusReporter {
 
     fn get_registered_appimages(&self) -> Result<Vec<AppImageStatus>, StatusError> {
         let mut appimages = Vec::new();
-        let security_checker = SecurityChecker {
-            verify_signatures: self.config.security.verify_signatures,
-            require_signatures: self.config.security.require_signatures,
-            warn_unsigned: self.config.security.warn_unsigned,
-            detect_sandboxing: self.config.security.detect_sandboxing,
-        };
+        let security_checker = SecurityChecker::from_config(&self.config);
 
         let apps = self
             .version_manager
@@ -190,7 +208,7 @@ impl StatusReporter {
 
                 // Perform security check
                 let security_status = if let Ok(app) = AppImage::new(appimage_path.clone()) {
-                    match security_checker.check_appimage(&app) {
+                    match security_checker.check_app(&app_name, &app) {
                         Ok(report) => {
                             security_checker.print_warnings(&app, &report);
                             Some(match report.overall_status {
@@ -201,6 +219,9 @@ impl StatusReporter {
                                 crate::security::SecurityStatus::Error(msg) => {
                                     SecurityStatus::Error(msg)
                                 }
+                                crate::security::SecurityStatus::KeyChanged(msg) => {
+                                    SecurityStatus::KeyChanged(msg)
+                                }
                             })
                         }
                         Err(e) => {
@@ -218,6 +239,7 @@ impl StatusReporter {
                 appimages.push(AppImageStatus {
                     name: app_name.clone(),
                     version: active_version.version.clone(),
+                    channel: self.version_manager.channel(&app_name),
                     path: appimage_path.display().to_string(),
                     size_bytes,
                     registered_at: Some(
@@ -291,133 +313,7 @@ impl StatusReporter {
         latest_time.map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
     }
 
-    pub fn print_status(&self, json_output: bool) -> Result<(), StatusError> {
-        let status = self.get_status()?;
-
-        if json_output {
-            let json_str = serde_json::to_string(&status)
-                .map_err(|e| StatusError::JsonError(e.to_string()))?;
-            println!("{}", json_str);
-        } else {
-            self.print_pretty_status(&status);
-        }
-
-        Ok(())
-    }
-
-    fn print_pretty_status(&self, status: &SystemStatus) {
-        println!("\n═════════════════════════════════════════════════════════════");
-        println!("                     Appiman Status Report");
-        println!("═════════════════════════════════════════════════════════════\n");
-
-        println!("🔷 Systemd Units:");
-        println!(
-            "  {:<30} {:<10} {:<10} {:<10}",
-            "Unit", "Enabled", "Active", "State"
-        );
-        println!(
-            "  {:<30} {:<10} {:<10} {:<10}",
-            "─".repeat(30),
-            "─".repeat(10),
-            "─".repeat(10),
-            "─".repeat(10)
-        );
-
-        for unit in &status.systemd_units {
-            println!(
-                "  {:<30} {:<10} {:<10} {:<10}",
-                unit.name,
-                if unit.enabled { "✅" } else { "❌" },
-                if unit.active { "✅" } else { "❌" },
-                unit.state
-            );
-        }
-
-        println!(
-            "\n📦 Registered AppImages: {}",
-            status.registered_appimages.len()
-        );
-        if status.registered_appimages.is_empty() {
-            println!("  No AppImages registered yet.");
-        } else {
-            println!(
-                "  {:<18} {:<10} {:<8} {:<12} {:>10}",
-                "Name", "Version", "Size", "Security", "Registered"
-            );
-            println!(
-                "  {:<18} {:<10} {:<8} {:<12} {:>10}",
-                "─".repeat(18),
-                "─".repeat(10),
-                "─".repeat(8),
-                "─".repeat(12),
-                "─".repeat(10)
-            );
-
-            for app in &status.registered_appimages {
-                let security_indicator = match &app.security_status {
-                    Some(SecurityStatus::Secure) => "✅",
-                    Some(SecurityStatus::Warning(_)) => "⚠️",
-                    Some(SecurityStatus::Error(_)) => "❌",
-                    None => "?",
-                };
-
-                println!(
-                    "  {:<18} {:<10} {:>8} {:<12} {}",
-                    app.name,
-                    app.version,
-                    Self::format_size(app.size_bytes),
-                    security_indicator,
-                    app.registered_at.as_deref().unwrap_or("unknown")
-                );
-            }
-        }
-
-        println!("\n💾 Storage Usage:");
-        println!(
-            "  Directory: {} files, {}",
-            self.config.bin_dir().display(),
-            Self::format_size(status.storage_usage.bin_dir.size_bytes)
-        );
-        println!(
-            "  Raw:      {} files, {}",
-            self.config.raw_dir().display(),
-            Self::format_size(status.storage_usage.raw_dir.size_bytes)
-        );
-        println!(
-            "  Icons:    {} files, {}",
-            self.config.icon_dir().display(),
-            Self::format_size(status.storage_usage.icon_dir.size_bytes)
-        );
-        println!("  ──────────────────────────────────");
-        println!(
-            "  Total:     {}",
-            Self::format_size(status.storage_usage.total_size_bytes)
-        );
-
-        if let Some(timestamp) = &status.last_scan {
-            println!("\n⏰ Last Scan: {}", timestamp);
-        }
-
-        if let Some(perf) = &status.performance {
-            println!("\n⚡ Performance Metrics:");
-            if let Some(duration) = perf.last_scan_duration {
-                println!("  Last scan duration: {:.2}s", duration);
-            }
-            if let Some(hits) = perf.cached_hits {
-                println!("  Cache hits: {}", hits);
-            }
-            if let Some(workers) = perf.parallel_workers {
-                println!("  Parallel workers: {}", workers);
-            }
-            if let Some(processed) = perf.total_processed {
-                println!("  Total processed: {}", processed);
-            }
-        }
-
-        println!("\n═══════════════════════════════════════════════════════════════\n");
-    }
-
-    fn format_size(bytes: u64) -> String {
+    pub fn format_size(bytes: u64) -> String {
         if bytes < 1024 {
             format!("{} B", bytes)
         } else if bytes < 1024 * 1024 {
@@ -430,6 +326,40 @@ impl StatusReporter {
     }
 }
 
+fn print_quarantined(records: &[QuarantineRecord]) {
+    println!("\n🚫 Quarantined AppImages: {}", records.len());
+    for record in records {
+        println!(
+            "  {} ({}, {}) - {}",
+            record.id,
+            record.action,
+            record.quarantined_at.format("%Y-%m-%d %H:%M:%S"),
+            record.reason
+        );
+    }
+}
+
+fn print_lock_holder(holder: Option<&Holder>) {
+    match holder {
+        Some(holder) => println!("\n🔒 Locked by {}", holder),
+        None => println!("\n🔓 Not locked"),
+    }
+}
+
+/// Plain `appiman status`: systemd unit status, the quarantine and the
+/// run holding the global lock.
+pub fn print_plain_status() -> io::Result<()> {
+    crate::systemd::print_status()?;
+
+    let config = Config::load().map_err(|e| io::Error::other(e.to_string()))?;
+    let records = Quarantine::new(config.quarantine_dir())
+        .list()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    print_quarantined(&records);
+    print_lock_holder(Lock::global(&config).holder()?.as_ref());
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,15 +1,32 @@
 // src/sync.rs
 
+use crate::cli::say;
+use crate::mover::MoveReport;
+use crate::output::{Outcome, Report};
+use crate::registrar::ProcessReport;
+use serde::Serialize;
 use std::io;
 
-pub fn run_sync() -> io::Result<()> {
-    println!("🔁 Syncing AppImages (ingest + register)...");
+#[derive(Serialize)]
+pub struct SyncReport {
+    pub ingest: MoveReport,
+    pub scan: ProcessReport,
+}
+
+impl Report for SyncReport {
+    fn outcome(&self) -> Outcome {
+        self.ingest.outcome().combine(self.scan.outcome())
+    }
+}
+
+pub fn run_sync(dry_run: bool) -> io::Result<SyncReport> {
+    say!("🔁 Syncing AppImages (ingest + register)...");
 
-    crate::ingest::run_ingest()?;
-    crate::scan::run_scan()?;
+    let ingest = crate::ingest::run_ingest(dry_run)?;
+    let scan = crate::scan::run_scan(dry_run)?;
 
-    println!("✅ Sync complete.");
-    Ok(())
+    say!("✅ Sync complete.");
+    Ok(SyncReport { ingest, scan })
 }
 
 #[cfg(test)]
@@ -21,7 +38,7 @@ mod tests {
         // Validates that sync calls ingest before scan
         // Full integration testing requires fake AppImage setup
         // This is a minimal test that ensures sync is callable
-        let result = run_sync();
+        let result = run_sync(true);
         // Expecting failure due to missing directories/setup
         assert!(result.is_err() || result.is_ok());
     }
@@ -1,5 +1,6 @@
 // src/systemd.rs
 
+use crate::cli::say;
 use crate::privileges::require_root;
 use std::io;
 use std::process::Command;
@@ -21,7 +22,7 @@ fn enable_units(systemctl: &str, units: &[&str]) -> io::Result<()> {
     let mut failures = Vec::new();
 
     for unit in units {
-        println!("Enabling and starting: {}", unit);
+        say!("Enabling and starting: {}", unit);
         let status = Command::new(systemctl)
             .args(["enable", "--now", unit])
             .status()?;
@@ -46,7 +47,7 @@ fn disable_units(systemctl: &str, units: &[&str]) -> io::Result<()> {
     let mut failures = Vec::new();
 
     for unit in units {
-        println!("Disabling and stopping: {}", unit);
+        say!("Disabling and stopping: {}", unit);
         let status = Command::new(systemctl)
             .args(["disable", "--now", unit])
             .status()?;
@@ -67,21 +68,33 @@ fn disable_units(systemctl: &str, units: &[&str]) -> io::Result<()> {
     Ok(())
 }
 
-pub fn enable_all() -> io::Result<()> {
+pub fn enable_all(dry_run: bool) -> io::Result<()> {
     require_root()?;
 
     let systemctl = systemctl_bin();
+    if dry_run {
+        for unit in PATH_UNITS {
+            say!("[DRY RUN] Would run: {} enable --now {}", systemctl, unit);
+        }
+        return Ok(());
+    }
     enable_units(&systemctl, PATH_UNITS)?;
-    println!("✅ All watcher units enabled and started.");
+    say!("✅ All watcher units enabled and started.");
     Ok(())
 }
 
-pub fn disable_all() -> io::Result<()> {
+pub fn disable_all(dry_run: bool) -> io::Result<()> {
     require_root()?;
 
     let systemctl = systemctl_bin();
+    if dry_run {
+        for unit in PATH_UNITS {
+            say!("[DRY RUN] Would run: {} disable --now {}", systemctl, unit);
+        }
+        return Ok(());
+    }
     disable_units(&systemctl, PATH_UNITS)?;
-    println!("✅ All watcher units disabled and stopped.");
+    say!("✅ All watcher units disabled and stopped.");
     Ok(())
 }
 
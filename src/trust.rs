@@ -0,0 +1,416 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+use thiserror::Error;
+use tracing::debug;
+
+use crate::cli::say;
+use crate::config::Config;
+use crate::core::{AppImage, VersionManager};
+use crate::output::{Outcome, Report};
+use crate::security::SecurityChecker;
+
+const KEY_EXTENSIONS: &[&str] = &["asc", "gpg", "pub", "key"];
+const PIN_FILE: &str = "trust.json";
+
+#[derive(Debug, Error)]
+pub enum TrustError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("gpg failed: {0}")]
+    Gpg(String),
+
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid app name: {0:?}")]
+    InvalidApp(String),
+}
+
+/// OpenPGP public keys whose signatures are trusted, one key per file.
+#[derive(Debug, Clone)]
+pub struct TrustStore {
+    dir: PathBuf,
+}
+
+impl TrustStore {
+    pub fn new(dir: PathBuf) -> Self {
+        TrustStore { dir }
+    }
+
+    #[allow(dead_code)]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Contents of every key file in the store. A missing directory is an
+    /// empty store.
+    pub fn keys(&self) -> Result<Vec<Vec<u8>>, TrustError> {
+        if !self.dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|e| e.to_str())
+                        .is_some_and(|e| KEY_EXTENSIONS.contains(&e))
+            })
+            .collect();
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| fs::read(path).map_err(TrustError::from))
+            .collect()
+    }
+}
+
+/// The key an app's images must be signed with.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KeyPin {
+    pub app: String,
+    pub fingerprint: String,
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// Trust-on-first-use key pins, stored per app next to the `metadata.json`
+/// maintained by `VersionManager`.
+#[derive(Debug, Clone)]
+pub struct KeyPins {
+    bin_dir: PathBuf,
+}
+
+impl KeyPins {
+    pub fn new(bin_dir: PathBuf) -> Self {
+        KeyPins { bin_dir }
+    }
+
+    pub fn pin_path(&self, app: &str) -> PathBuf {
+        self.bin_dir.join(app).join(PIN_FILE)
+    }
+
+    /// `pin_path` for an app name given by the admin, which must not
+    /// point outside the bin dir.
+    fn checked_pin_path(&self, app: &str) -> Result<PathBuf, TrustError> {
+        if app.is_empty() || app.starts_with('.') || app.contains('/') {
+            return Err(TrustError::InvalidApp(app.to_string()));
+        }
+        Ok(self.pin_path(app))
+    }
+
+    pub fn get(&self, app: &str) -> Result<Option<KeyPin>, TrustError> {
+        read_pin(&self.checked_pin_path(app)?)
+    }
+
+    pub fn pin(&self, app: &str, fingerprint: &str) -> Result<KeyPin, TrustError> {
+        let pin = KeyPin {
+            app: app.to_string(),
+            fingerprint: normalize_fingerprint(fingerprint),
+            pinned_at: Utc::now(),
+        };
+        let path = self.checked_pin_path(app)?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, serde_json::to_string_pretty(&pin)?)?;
+        Ok(pin)
+    }
+
+    /// Removes the pin for `app`, returning whether there was one.
+    pub fn revoke(&self, app: &str) -> Result<bool, TrustError> {
+        let path = self.checked_pin_path(app)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    pub fn list(&self) -> Result<Vec<KeyPin>, TrustError> {
+        if !self.bin_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut pins = Vec::new();
+        for entry in fs::read_dir(&self.bin_dir)? {
+            let entry = entry?;
+            if entry.path().is_dir()
+                && let Some(pin) = read_pin(&entry.path().join(PIN_FILE))?
+            {
+                pins.push(pin);
+            }
+        }
+        pins.sort_by(|a, b| a.app.cmp(&b.app));
+        Ok(pins)
+    }
+}
+
+fn read_pin(path: &Path) -> Result<Option<KeyPin>, TrustError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+/// Fingerprints are compared in gpg's form: upper-case hex without spaces.
+pub fn normalize_fingerprint(fingerprint: &str) -> String {
+    fingerprint
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Checks a detached OpenPGP `signature` over `data` using only `keys`, in a
+/// throwaway keyring so the host's keyrings never influence the result.
+/// Returns the fingerprint of the signing key when the signature is good.
+pub fn verify_detached(
+    keys: &[Vec<u8>],
+    signature: &[u8],
+    data: &[u8],
+) -> Result<Option<String>, TrustError> {
+    if keys.is_empty() {
+        return Ok(None);
+    }
+
+    let home = TempDir::new()?;
+    for (i, key) in keys.iter().enumerate() {
+        let key_path = home.path().join(format!("key{}", i));
+        fs::write(&key_path, key)?;
+        let output = gpg(home.path())
+            .arg("--import")
+            .arg(&key_path)
+            .output()
+            .map_err(|e| TrustError::Gpg(e.to_string()))?;
+        if !output.status.success() {
+            debug!(
+                "Ignoring key that gpg could not import: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+
+    let signature_path = home.path().join("data.sig");
+    let data_path = home.path().join("data");
+    fs::write(&signature_path, signature)?;
+    fs::write(&data_path, data)?;
+
+    let output = gpg(home.path())
+        .args(["--status-fd", "1", "--verify"])
+        .arg(&signature_path)
+        .arg(&data_path)
+        .output()
+        .map_err(|e| TrustError::Gpg(e.to_string()))?;
+
+    Ok(good_signature_fingerprint(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+fn gpg(home: &Path) -> Command {
+    let mut command = Command::new("gpg");
+    command
+        .arg("--homedir")
+        .arg(home)
+        .args(["--batch", "--no-tty", "--quiet"]);
+    command
+}
+
+/// Parses `gpg --status-fd` output. Expired or revoked keys report
+/// `EXPKEYSIG`/`REVKEYSIG` instead of `GOODSIG` and are not accepted.
+fn good_signature_fingerprint(status: &str) -> Option<String> {
+    let mut good = false;
+    let mut fingerprint = None;
+    for line in status.lines() {
+        let mut fields = line.split_whitespace();
+        if fields.next() != Some("[GNUPG:]") {
+            continue;
+        }
+        match fields.next() {
+            Some("GOODSIG") => good = true,
+            Some("VALIDSIG") => fingerprint = fields.next().map(str::to_string),
+            _ => {}
+        }
+    }
+    if good { fingerprint } else { None }
+}
+
+impl Report for KeyPin {}
+
+/// Result of `trust revoke`: the fingerprint that was pinned, if any.
+#[derive(Debug, Serialize)]
+pub struct RevokeReport {
+    pub app: String,
+    pub fingerprint: Option<String>,
+}
+
+impl Report for RevokeReport {
+    fn outcome(&self) -> Outcome {
+        if self.fingerprint.is_some() {
+            Outcome::Success
+        } else {
+            Outcome::NothingToDo
+        }
+    }
+}
+
+pub fn run_trust_list() -> Result<Vec<KeyPin>, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let pins = KeyPins::new(config.bin_dir()).list()?;
+
+    if pins.is_empty() {
+        say!("No signing keys pinned");
+        return Ok(pins);
+    }
+
+    say!("Pinned signing keys:");
+    for pin in &pins {
+        say!(
+            "  {} -> {} (pinned {})",
+            pin.app,
+            pin.fingerprint,
+            pin.pinned_at.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    Ok(pins)
+}
+
+/// Pins `fingerprint` for `app`, or the key that signed the active version
+/// when no fingerprint is given.
+pub fn run_trust_pin(
+    app: &str,
+    fingerprint: Option<&str>,
+    dry_run: bool,
+) -> Result<KeyPin, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let version_manager = VersionManager::new(config.clone());
+    // Only registered apps, so no directory is created for any other name.
+    if !version_manager.list_apps()?.iter().any(|a| a == app) {
+        return Err(format!("{} is not installed", app).into());
+    }
+
+    let fingerprint = match fingerprint {
+        Some(fingerprint) => fingerprint.to_string(),
+        None => {
+            let version = version_manager
+                .get_current_version(app)?
+                .ok_or_else(|| format!("{} has no active version", app))?;
+            let appimage = AppImage::new(version_manager.get_appimage_path(app, &version))?;
+            let checker = SecurityChecker::from_config(&config);
+            checker
+                .check_appimage_signer(&appimage)?
+                .ok_or_else(|| format!("{} {} has no valid signature", app, version))?
+        }
+    };
+
+    if dry_run {
+        let pin = KeyPin {
+            app: app.to_string(),
+            fingerprint: normalize_fingerprint(&fingerprint),
+            pinned_at: Utc::now(),
+        };
+        say!("[DRY RUN] Would pin {} to key {}", pin.app, pin.fingerprint);
+        return Ok(pin);
+    }
+
+    let pin = KeyPins::new(config.bin_dir()).pin(app, &fingerprint)?;
+    say!("✅ Pinned {} to key {}", pin.app, pin.fingerprint);
+    Ok(pin)
+}
+
+pub fn run_trust_revoke(app: &str, dry_run: bool) -> Result<RevokeReport, Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let pins = KeyPins::new(config.bin_dir());
+    let report = RevokeReport {
+        app: app.to_string(),
+        fingerprint: pins.get(app)?.map(|pin| pin.fingerprint),
+    };
+
+    match &report.fingerprint {
+        None => say!("ℹ️ No key pinned for {}", app),
+        Some(fingerprint) if dry_run => {
+            say!("[DRY RUN] Would revoke key {} for {}", fingerprint, app)
+        }
+        Some(_) => {
+            pins.revoke(app)?;
+            say!("✅ Revoked key pin for {}", app);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn good_signature_fingerprint_requires_goodsig() {
+        let good =
+            "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 1234 Test\n[GNUPG:] VALIDSIG ABCDEF 2024-01-01 0\n";
+        assert_eq!(good_signature_fingerprint(good), Some("ABCDEF".to_string()));
+
+        let expired = "[GNUPG:] EXPKEYSIG 1234 Test\n[GNUPG:] VALIDSIG ABCDEF 2024-01-01 0\n";
+        assert_eq!(good_signature_fingerprint(expired), None);
+    }
+
+    #[test]
+    fn trust_store_reads_key_files_only() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("vendor.asc"), b"key").unwrap();
+        fs::write(temp.path().join("README"), b"not a key").unwrap();
+
+        let store = TrustStore::new(temp.path().to_path_buf());
+        assert_eq!(store.keys().unwrap(), vec![b"key".to_vec()]);
+
+        let missing = TrustStore::new(temp.path().join("missing"));
+        assert!(missing.keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn key_pins_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let pins = KeyPins::new(temp.path().to_path_buf());
+        assert_eq!(pins.get("firefox").unwrap(), None);
+
+        pins.pin("firefox", "abcd 1234").unwrap();
+        pins.pin("gimp", "FFFF").unwrap();
+        assert_eq!(
+            pins.get("firefox").unwrap().unwrap().fingerprint,
+            "ABCD1234"
+        );
+        assert!(temp.path().join("firefox/trust.json").exists());
+
+        let apps: Vec<_> = pins.list().unwrap().into_iter().map(|p| p.app).collect();
+        assert_eq!(apps, vec!["firefox", "gimp"]);
+
+        assert!(pins.revoke("firefox").unwrap());
+        assert!(!pins.revoke("firefox").unwrap());
+        assert_eq!(pins.get("firefox").unwrap(), None);
+    }
+
+    #[test]
+    fn key_pins_reject_names_outside_bin_dir() {
+        let temp = TempDir::new().unwrap();
+        let pins = KeyPins::new(temp.path().join("bin"));
+
+        for app in ["../etc", ".locks", ""] {
+            assert!(matches!(pins.pin(app, "ABCD"), Err(TrustError::InvalidApp(_))));
+            assert!(matches!(pins.revoke(app), Err(TrustError::InvalidApp(_))));
+            assert!(matches!(pins.get(app), Err(TrustError::InvalidApp(_))));
+        }
+        assert!(!temp.path().join("etc").exists());
+        assert!(!temp.path().join("bin").exists());
+    }
+
+    #[test]
+    fn verify_detached_without_keys_is_untrusted() {
+        assert_eq!(verify_detached(&[], b"sig", b"data").unwrap(), None);
+    }
+}
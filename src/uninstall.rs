@@ -0,0 +1,410 @@
+// src/uninstall.rs
+
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+
+use crate::audit::{AuditEvent, AuditLog, AuditOperation, AuditOutcome};
+use crate::cli::say;
+use crate::config::{Config, ConfigError};
+use crate::core::files::list_dir;
+use crate::core::{MetadataCache, VersionError, VersionManager, normalize_appimage_name};
+use crate::output::{Outcome, Report, serialize_path_reasons};
+use crate::privileges::require_root;
+use crate::trust::KeyPins;
+
+#[derive(Debug, Error)]
+pub enum UninstallError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Config error: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error("Version error: {0}")]
+    Version(#[from] VersionError),
+
+    #[error("App not found: {0}")]
+    AppNotFound(String),
+
+    #[error("{0} has {1} versions installed; pass --all-versions to remove them all")]
+    MultipleVersions(String, usize),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UninstallOptions {
+    /// Keep the signing key pin and update backups for a later reinstall.
+    pub keep_data: bool,
+    pub all_versions: bool,
+}
+
+/// Result of `uninstall`.
+#[derive(Debug, Serialize)]
+pub struct UninstallReport {
+    pub app: String,
+    pub versions: Vec<String>,
+    pub removed: Vec<PathBuf>,
+    pub kept: Vec<PathBuf>,
+    #[serde(serialize_with = "serialize_path_reasons")]
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl Report for UninstallReport {
+    fn outcome(&self) -> Outcome {
+        Outcome::of_batch(self.removed.len(), self.errors.len())
+    }
+}
+
+/// Removes an app and everything registration created for it: versions,
+/// metadata, symlink, desktop entry, icons, staged raw images, cache
+/// entries and update backups.
+pub struct Uninstaller {
+    config: Config,
+    version_manager: VersionManager,
+    audit: Option<AuditLog>,
+    dry_run: bool,
+}
+
+impl Uninstaller {
+    pub fn new(config: Config) -> Self {
+        Uninstaller {
+            version_manager: VersionManager::new(config.clone()),
+            config,
+            audit: None,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_audit(mut self, audit: Option<AuditLog>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn uninstall(
+        &self,
+        app: &str,
+        options: UninstallOptions,
+    ) -> Result<UninstallReport, UninstallError> {
+        // Only names of registered apps, so `app` cannot point outside bin_dir.
+        if !self.version_manager.list_apps()?.iter().any(|a| a == app) {
+            return Err(UninstallError::AppNotFound(app.to_string()));
+        }
+
+        let versions: Vec<String> = self
+            .version_manager
+            .list_versions(app)?
+            .into_iter()
+            .map(|v| v.version)
+            .collect();
+        if versions.len() > 1 && !options.all_versions {
+            return Err(UninstallError::MultipleVersions(
+                app.to_string(),
+                versions.len(),
+            ));
+        }
+
+        let (targets, kept) = self.artifacts(app, options)?;
+        let mut report = UninstallReport {
+            app: app.to_string(),
+            versions,
+            removed: Vec::new(),
+            kept,
+            errors: Vec::new(),
+        };
+
+        if self.dry_run {
+            for path in targets {
+                say!("[DRY RUN] Would remove {}", path.display());
+                report.removed.push(path);
+            }
+            return Ok(report);
+        }
+
+        let before = self.version_manager.active_checksum(app);
+        let app_dir = self.version_manager.get_app_dir(app);
+        let (store, files): (Vec<_>, Vec<_>) =
+            targets.into_iter().partition(|path| path.starts_with(&app_dir));
+        for path in files {
+            match remove_path(&path) {
+                Ok(()) => {
+                    info!(app, operation = "uninstall", "Removed {:?}", path);
+                    report.removed.push(path);
+                }
+                Err(e) => report.errors.push((path, e.to_string())),
+            }
+        }
+
+        // The version manager locks the app, journals the removal and frees
+        // the blobs no kept backup or other app links.
+        if !store.is_empty() {
+            match self.version_manager.remove_app(app, &report.kept) {
+                Ok(removed) => report.removed.extend(removed),
+                Err(e) => report.errors.push((app_dir, e.to_string())),
+            }
+        }
+
+        let mut cache = MetadataCache::new(&self.cache_dir());
+        cache.remove_app(app);
+        if let Err(e) = cache.save() {
+            report
+                .errors
+                .push((self.cache_dir(), format!("Cannot update cache: {}", e)));
+        }
+
+        if let Some(audit) = &self.audit {
+            let (outcome, detail) = match report.errors.first() {
+                None => (AuditOutcome::Success, None),
+                Some((path, e)) => (
+                    AuditOutcome::Failure,
+                    Some(format!("{}: {}", path.display(), e)),
+                ),
+            };
+            audit.record(
+                AuditEvent::new(AuditOperation::Uninstall)
+                    .with_app(app)
+                    .with_checksums(before, None)
+                    .with_outcome(outcome, detail),
+            );
+        }
+        Ok(report)
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.config.raw_dir().join(".cache")
+    }
+
+    /// Paths to remove and paths deliberately kept, in removal order.
+    fn artifacts(
+        &self,
+        app: &str,
+        options: UninstallOptions,
+    ) -> Result<(Vec<PathBuf>, Vec<PathBuf>), UninstallError> {
+        let mut targets = Vec::new();
+        let mut kept = Vec::new();
+
+        // Entry points first, so a half-finished uninstall leaves nothing
+        // that launches a missing image.
+        targets.push(self.config.symlink_dir().join(app));
+        targets.push(self.config.desktop_dir().join(format!("{}.desktop", app)));
+        targets.extend(list_dir(&self.config.icon_dir(), |path| {
+            path.file_stem().is_some_and(|stem| stem == app)
+        })?);
+
+        // Staged images would be registered again by the next scan.
+        let mut cache = MetadataCache::new(&self.cache_dir());
+        let mut raw_images = cache.remove_app(app);
+        raw_images.extend(list_dir(&self.config.raw_dir(), |path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| normalize_appimage_name(stem) == app)
+        })?);
+        raw_images.sort();
+        raw_images.dedup();
+        targets.extend(raw_images);
+
+        let app_dir = self.version_manager.get_app_dir(app);
+        let backups = list_dir(&self.config.bin_dir().join("backups"), |path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&format!("{}_backup_", app)))
+        })?;
+        if options.keep_data {
+            let pin = KeyPins::new(self.config.bin_dir()).pin_path(app);
+            targets.extend(list_dir(&app_dir, |path| path != pin)?);
+            if pin.exists() {
+                kept.push(pin);
+            }
+            kept.extend(backups);
+        } else {
+            targets.push(app_dir);
+            targets.extend(backups);
+        }
+
+        targets.retain(|path| path.symlink_metadata().is_ok());
+        Ok((targets, kept))
+    }
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+pub fn run_uninstall(
+    app: &str,
+    options: UninstallOptions,
+    dry_run: bool,
+) -> Result<UninstallReport, UninstallError> {
+    require_root()?;
+    let config = Config::load()?;
+
+    say!("🗑️  Uninstalling {}...", app);
+
+    let audit = AuditLog::from_config(&config);
+    let report = Uninstaller::new(config)
+        .with_audit(audit)
+        .with_dry_run(dry_run)
+        .uninstall(app, options)?;
+
+    for (path, error) in &report.errors {
+        eprintln!("⚠️ Failed to remove {}: {}", path.display(), error);
+    }
+    for path in &report.kept {
+        say!("   Kept {}", path.display());
+    }
+    if !dry_run {
+        say!(
+            "✅ Uninstalled {} ({} paths removed)",
+            app,
+            report.removed.len()
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::{fake_appimage, test_config};
+    use tempfile::TempDir;
+
+    /// Registers `app` the way `scan` leaves it, with one image per version.
+    fn register(config: &Config, app: &str, versions: &[&str]) {
+        let manager = VersionManager::new(config.clone());
+        for version in versions {
+            let image = config
+                .raw_dir()
+                .join(format!("{}-{}.AppImage", app, version));
+            fs::write(&image, fake_appimage(version.as_bytes())).unwrap();
+            manager.install_version(app, version, &image).unwrap();
+        }
+        let current = manager.get_appimage_path(app, versions[versions.len() - 1]);
+        std::os::unix::fs::symlink(current, config.symlink_dir().join(app)).unwrap();
+        fs::write(config.desktop_dir().join(format!("{}.desktop", app)), "").unwrap();
+        fs::write(config.icon_dir().join(format!("{}.png", app)), "").unwrap();
+        fs::create_dir_all(config.bin_dir().join("backups")).unwrap();
+        fs::write(
+            config
+                .bin_dir()
+                .join(format!("backups/{}_backup_20260101_000000.AppImage", app)),
+            "",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn uninstall_removes_every_artifact() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        register(&config, "tool", &["1.0"]);
+        register(&config, "other", &["2.0"]);
+
+        let audit = AuditLog::new(temp.path().join("audit.jsonl"));
+        let report = Uninstaller::new(config.clone())
+            .with_audit(Some(audit.clone()))
+            .uninstall("tool", UninstallOptions::default())
+            .unwrap();
+
+        assert_eq!(report.versions, vec!["1.0"]);
+        assert!(report.errors.is_empty());
+        assert!(!config.bin_dir().join("tool").exists());
+        assert!(
+            config
+                .symlink_dir()
+                .join("tool")
+                .symlink_metadata()
+                .is_err()
+        );
+        assert!(!config.desktop_dir().join("tool.desktop").exists());
+        assert!(!config.icon_dir().join("tool.png").exists());
+        assert!(!config.raw_dir().join("tool-1.0.AppImage").exists());
+        assert!(
+            !config
+                .bin_dir()
+                .join("backups/tool_backup_20260101_000000.AppImage")
+                .exists()
+        );
+
+        // Other apps are untouched.
+        assert!(config.bin_dir().join("other").exists());
+        assert!(config.raw_dir().join("other-2.0.AppImage").exists());
+        // The uninstalled app's blob is freed, the other app's is kept.
+        let blobs = VersionManager::new(config.clone()).blobs();
+        assert!(blobs.unreferenced().unwrap().is_empty());
+        assert_eq!(blobs.inodes().unwrap().len(), 1);
+
+        let events = audit.read(&Default::default()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].operation, AuditOperation::Uninstall);
+    }
+
+    #[test]
+    fn multiple_versions_require_all_versions() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        register(&config, "tool", &["1.0", "2.0"]);
+
+        let uninstaller = Uninstaller::new(config.clone());
+        assert!(matches!(
+            uninstaller.uninstall("tool", UninstallOptions::default()),
+            Err(UninstallError::MultipleVersions(_, 2))
+        ));
+        assert!(matches!(
+            uninstaller.uninstall("../etc", UninstallOptions::default()),
+            Err(UninstallError::AppNotFound(_))
+        ));
+
+        let options = UninstallOptions {
+            all_versions: true,
+            ..Default::default()
+        };
+        let report = uninstaller.uninstall("tool", options).unwrap();
+        assert_eq!(report.versions.len(), 2);
+        assert!(!config.bin_dir().join("tool").exists());
+    }
+
+    #[test]
+    fn keep_data_and_dry_run() {
+        let temp = TempDir::new().unwrap();
+        let config = test_config(temp.path());
+        register(&config, "tool", &["1.0"]);
+        let pins = KeyPins::new(config.bin_dir());
+        pins.pin("tool", "ABCD").unwrap();
+
+        let options = UninstallOptions {
+            keep_data: true,
+            ..Default::default()
+        };
+        let dry = Uninstaller::new(config.clone())
+            .with_dry_run(true)
+            .uninstall("tool", options)
+            .unwrap();
+        assert!(!dry.removed.is_empty());
+        assert!(config.desktop_dir().join("tool.desktop").exists());
+
+        let report = Uninstaller::new(config.clone())
+            .uninstall("tool", options)
+            .unwrap();
+        assert_eq!(report.removed, dry.removed);
+        assert!(pins.get("tool").unwrap().is_some());
+        assert!(report.kept.contains(&pins.pin_path("tool")));
+        assert!(!config.bin_dir().join("tool/metadata.json").exists());
+        assert!(
+            config
+                .bin_dir()
+                .join("backups/tool_backup_20260101_000000.AppImage")
+                .exists()
+        );
+    }
+}
@@ -1,12 +1,30 @@
 use chrono::Utc;
-use std::fs;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::time::Duration;
 use thiserror::Error;
 use tracing::{debug, error, info, instrument, warn};
 
+use crate::audit::{AuditEvent, AuditLog, AuditOperation};
+use crate::cli::say;
 use crate::config::Config;
-use crate::core::{AppImage, AppImageError, VersionError, VersionManager};
+use crate::core::files::dir_size;
+use crate::core::retention::{Retained, RetentionPolicy, list_backups};
+use crate::core::{
+    AppImage, AppImageError, UpdateInformation, Version, VersionError, VersionManager,
+    version_from_file_name, wildcard_matches,
+};
+use crate::output::{Outcome, Report, serialize_app_reasons};
+use crate::security::{SecurityChecker, SecurityError, SecurityStatus};
+use crate::zsync::{self, ControlFile, ZsyncError};
+
+/// Upper bound for a single full download.
+const MAX_DOWNLOAD_SIZE: u64 = 8 * 1024 * 1024 * 1024;
 
 #[derive(Debug, Error)]
 pub enum UpdateError {
@@ -26,35 +44,190 @@ pub enum UpdateError {
     #[allow(dead_code)]
     NoUpdatesAvailable,
 
-    #[error("Backup failed: {0}")]
-    #[allow(dead_code)]
-    BackupFailed(String),
-
     #[error("Rollback failed: {0}")]
     RollbackFailed(String),
 
     #[error("Version error: {0}")]
     Version(#[from] VersionError),
+
+    #[error("Download failed: {0}")]
+    Download(String),
+
+    #[error("zsync error: {0}")]
+    Zsync(#[from] ZsyncError),
+
+    #[error("Security check failed: {0}")]
+    Security(#[from] SecurityError),
+
+    #[error("Signing key changed: {0}")]
+    KeyChanged(String),
+}
+
+/// Fetches update metadata and image data. [`DefaultTransport`] handles
+/// `file://` and HTTP(S) URLs; tests can substitute their own.
+pub trait Transport: Send + Sync {
+    fn fetch(&self, url: &str) -> Result<Vec<u8>, UpdateError>;
+
+    fn fetch_range(&self, url: &str, range: Range<u64>) -> Result<Vec<u8>, UpdateError>;
+}
+
+pub struct DefaultTransport {
+    agent: ureq::Agent,
+}
+
+impl DefaultTransport {
+    pub fn new() -> Self {
+        DefaultTransport {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(Duration::from_secs(30))
+                .timeout_read(Duration::from_secs(60))
+                .user_agent(concat!("appiman/", env!("CARGO_PKG_VERSION")))
+                .build(),
+        }
+    }
+
+    fn get(&self, url: &str, range: Option<&Range<u64>>) -> Result<ureq::Response, UpdateError> {
+        let mut request = self.agent.get(url);
+        if let Some(range) = range {
+            request = request.set("Range", &format!("bytes={}-{}", range.start, range.end - 1));
+        }
+        request
+            .call()
+            .map_err(|e| UpdateError::Download(format!("{}: {}", url, e)))
+    }
+}
+
+impl Transport for DefaultTransport {
+    fn fetch(&self, url: &str) -> Result<Vec<u8>, UpdateError> {
+        if let Some(path) = url.strip_prefix("file://") {
+            return Ok(fs::read(path)?);
+        }
+
+        let mut data = Vec::new();
+        self.get(url, None)?
+            .into_reader()
+            .take(MAX_DOWNLOAD_SIZE)
+            .read_to_end(&mut data)
+            .map_err(|e| UpdateError::Download(format!("{}: {}", url, e)))?;
+        Ok(data)
+    }
+
+    fn fetch_range(&self, url: &str, range: Range<u64>) -> Result<Vec<u8>, UpdateError> {
+        let len = (range.end - range.start) as usize;
+        let mut data = vec![0u8; len];
+
+        if let Some(path) = url.strip_prefix("file://") {
+            File::open(path)?.read_exact_at(&mut data, range.start)?;
+            return Ok(data);
+        }
+
+        let response = self.get(url, Some(&range))?;
+        let partial = response.status() == 206;
+        let mut reader = response.into_reader();
+        let result = if partial {
+            reader.read_exact(&mut data)
+        } else {
+            // Servers that ignore Range send the whole file from the start.
+            std::io::copy(&mut (&mut reader).take(range.start), &mut std::io::sink())
+                .and_then(|_| reader.read_exact(&mut data))
+        };
+        result.map_err(|e| UpdateError::Download(format!("{}: {}", url, e)))?;
+        Ok(data)
+    }
 }
 
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    #[serde(default)]
+    tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<GitHubAsset>,
+}
+
+impl GitHubRelease {
+    /// `stable` takes releases that are neither marked nor versioned as
+    /// prereleases; any other channel takes releases whose tag contains its
+    /// name, such as `v2.0.0-beta.1` for `beta` or `nightly-20241016` for
+    /// `nightly`.
+    fn in_channel(&self, channel: &str) -> bool {
+        if self.draft {
+            return false;
+        }
+        match channel {
+            "stable" => !self.prerelease && !Version::parse(&self.tag_name).is_prerelease(),
+            channel => self.tag_name.to_ascii_lowercase().contains(channel),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// A newer image advertised by an AppImage's update source.
 #[derive(Debug, Clone)]
+pub struct RemoteImage {
+    pub zsync_url: String,
+    pub control: ControlFile,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct UpdateInfo {
     pub name: String,
     pub current_version: Option<String>,
     pub new_version: Option<String>,
     pub update_available: bool,
+    pub update_information: Option<UpdateInformation>,
+    /// Channel the update was looked up in; `None` for the release named
+    /// by the update information.
+    pub channel: Option<String>,
+    #[serde(skip)]
+    pub remote: Option<RemoteImage>,
     pub path: PathBuf,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct UpdateReport {
     pub checked: Vec<UpdateInfo>,
     pub updated: Vec<String>,
+    #[serde(serialize_with = "serialize_app_reasons")]
     pub failed: Vec<(String, String)>,
-    #[allow(dead_code)]
     pub skipped: Vec<String>,
+    /// Pinned apps, which are neither checked nor updated.
+    pub held: Vec<HeldApp>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct HeldApp {
+    pub app: String,
+    pub version: String,
+}
+
+impl Report for UpdateReport {
+    fn outcome(&self) -> Outcome {
+        Outcome::of_batch(
+            self.updated.len() + self.updates_available_count(),
+            self.failed.len(),
+        )
+    }
+}
+
+/// Result of `update rollback`.
+#[derive(Debug, Serialize)]
+pub struct RollbackReport {
+    pub app: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl Report for RollbackReport {}
+
 impl UpdateReport {
     pub fn new() -> Self {
         UpdateReport {
@@ -62,6 +235,7 @@ impl UpdateReport {
             updated: Vec::new(),
             failed: Vec::new(),
             skipped: Vec::new(),
+            held: Vec::new(),
         }
     }
 
@@ -80,16 +254,40 @@ impl UpdateReport {
 pub struct UpdateManager {
     config: Config,
     version_manager: VersionManager,
+    security_checker: SecurityChecker,
+    transport: Box<dyn Transport>,
+    audit: Option<AuditLog>,
 }
 
 impl UpdateManager {
     pub fn new() -> Result<Self, UpdateError> {
         let config = Config::load()?;
+        let audit = AuditLog::from_config(&config);
+        Ok(Self::from_config(config).with_audit(audit))
+    }
+
+    pub fn from_config(config: Config) -> Self {
         let version_manager = VersionManager::new(config.clone());
-        Ok(UpdateManager {
+        let security_checker = SecurityChecker::from_config(&config);
+        UpdateManager {
             config,
             version_manager,
-        })
+            security_checker,
+            transport: Box::new(DefaultTransport::new()),
+            audit: None,
+        }
+    }
+
+    /// Records applied updates and rollbacks in `audit`.
+    pub fn with_audit(mut self, audit: Option<AuditLog>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    #[cfg(test)]
+    pub fn with_transport(mut self, transport: Box<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
     }
 
     #[instrument(skip(self))]
@@ -106,6 +304,15 @@ impl UpdateManager {
                 .unwrap_or("unknown")
                 .to_string();
 
+            if let Some(version) = self.version_manager.pinned_version(&app_name) {
+                debug!("{} is pinned to {}, holding", app_name, version);
+                report.held.push(HeldApp {
+                    app: app_name,
+                    version,
+                });
+                continue;
+            }
+
             match self.check_single_update(&app_path) {
                 Ok(update_info) => {
                     debug!(
@@ -122,9 +329,10 @@ impl UpdateManager {
         }
 
         info!(
-            "Update check complete: {} checked, {} available",
+            "Update check complete: {} checked, {} available, {} held",
             report.checked.len(),
-            report.updates_available_count()
+            report.updates_available_count(),
+            report.held.len()
         );
 
         Ok(report)
@@ -145,7 +353,7 @@ impl UpdateManager {
                 continue;
             }
 
-            match self.apply_single_update(&update_info.path, dry_run) {
+            match self.install_update(update_info, dry_run) {
                 Ok(_) => {
                     info!("Successfully updated {}", update_info.name);
                     report.updated.push(update_info.name.clone());
@@ -169,43 +377,86 @@ impl UpdateManager {
 
         debug!("Checking update for {}", app_name);
 
-        let output = Command::new(app_path)
-            .arg("--appimage-updateinfo")
-            .output()
-            .map_err(|e| UpdateError::UpdateFailed(format!("Failed to run updateinfo: {}", e)))?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        debug!(
-            "Update info for {}: stdout={}, stderr={}",
-            app_name,
-            stdout.trim(),
-            stderr.trim()
-        );
-
-        let update_available = output.status.success() && !stdout.trim().is_empty();
-
-        let current_version = self.extract_version_from_path(app_path);
-        let new_version = if update_available {
-            Some(stdout.trim().to_string())
-        } else {
-            None
+        let current_version = self
+            .version_manager
+            .get_current_version(&app_name)
+            .ok()
+            .flatten()
+            .or_else(|| self.extract_version_from_path(app_path));
+
+        let channel = self.version_manager.channel(&app_name);
+
+        // Read from the runtime's ELF sections; the image is never executed.
+        let update_information = app.update_information()?;
+        let Some(info) = &update_information else {
+            debug!("{} does not embed update information", app_name);
+            return Ok(UpdateInfo {
+                name: app_name,
+                current_version,
+                new_version: None,
+                update_available: false,
+                update_information,
+                channel,
+                remote: None,
+                path: app_path.to_path_buf(),
+            });
         };
+        debug!("Update information for {}: {}", app_name, info);
+
+        let zsync_url = self.resolve_zsync_url(info, channel.as_deref())?;
+        let control = ControlFile::parse(&self.transport.fetch(&zsync_url)?)?;
+        let update_available = sha1_file(app_path)? != control.sha1;
+        let new_version = control
+            .filename
+            .as_deref()
+            .and_then(|name| self.extract_version_from_path(Path::new(name)));
 
         Ok(UpdateInfo {
             name: app_name,
             current_version,
-            new_version,
+            new_version: if update_available { new_version } else { None },
             update_available,
+            update_information,
+            channel,
+            remote: update_available.then_some(RemoteImage { zsync_url, control }),
             path: app_path.to_path_buf(),
         })
     }
 
-    #[instrument(skip(self, app_path))]
-    pub fn apply_single_update(&self, app_path: &Path, dry_run: bool) -> Result<(), UpdateError> {
-        let app = AppImage::new(app_path.to_path_buf())?;
-        let app_name = app.normalize_name();
+    fn install_update(&self, update_info: &UpdateInfo, dry_run: bool) -> Result<(), UpdateError> {
+        let Some(audit) = self.audit.as_ref().filter(|_| !dry_run) else {
+            return self.download_and_install(update_info, dry_run);
+        };
+
+        let app_name = &update_info.name;
+        let before = self.version_manager.active_checksum(app_name);
+        let result = self.download_and_install(update_info, dry_run);
+        let mut event = AuditEvent::new(AuditOperation::Update)
+            .with_app(app_name)
+            .with_path(&update_info.path)
+            .with_checksums(before, self.version_manager.active_checksum(app_name))
+            .with_result(&result);
+        if let Some(version) = &update_info.new_version {
+            event = event.with_version(version);
+        }
+        audit.record(event);
+        result
+    }
+
+    /// Downloads the image described by `update_info.remote`, reusing blocks
+    /// of the installed version, and installs it as a new version.
+    #[instrument(skip(self, update_info), fields(app = %update_info.name, operation = "update"))]
+    fn download_and_install(
+        &self,
+        update_info: &UpdateInfo,
+        dry_run: bool,
+    ) -> Result<(), UpdateError> {
+        let remote = update_info
+            .remote
+            .as_ref()
+            .ok_or(UpdateError::NoUpdatesAvailable)?;
+        let app_name = &update_info.name;
+        let control = &remote.control;
 
         info!("Applying update for {} (dry_run={})", app_name, dry_run);
 
@@ -214,40 +465,205 @@ impl UpdateManager {
             return Ok(());
         }
 
-        // Run the update command to download the new version
-        let output = Command::new(app_path)
-            .arg("--appimage-update")
-            .output()
-            .map_err(|e| UpdateError::UpdateFailed(format!("Failed to run update: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(UpdateError::UpdateFailed(format!(
-                "Update command failed: {}",
-                stderr
-            )));
+        let target_url = zsync::resolve_url(&remote.zsync_url, &control.urls[0]);
+        let seed = fs::read(&update_info.path)?;
+        let plan = control.plan(&seed);
+        let ranges = control.missing_ranges(&plan);
+        let download_size: u64 = ranges.iter().map(|r| r.end - r.start).sum();
+        info!(
+            "Reusing {}/{} blocks of {}, downloading {} bytes",
+            plan.reused_blocks(),
+            plan.total_blocks(),
+            app_name,
+            download_size
+        );
+
+        let mut downloaded = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            let data = self.transport.fetch_range(&target_url, range.clone())?;
+            downloaded.push((range, data));
         }
 
-        // The AppImage file has been updated in place. Now we need to install it as a new version
-        // Extract version from update info or use timestamp
-        let update_info = self.check_single_update(app_path)?;
-        let version = update_info.new_version.unwrap_or_else(|| {
-            use chrono::Utc;
-            format!("{}-{}", app_name, Utc::now().format("%Y%m%d%H%M%S"))
-        });
+        let image = match control.assemble(&seed, &plan, &downloaded) {
+            Ok(image) => image,
+            Err(ZsyncError::ChecksumMismatch { .. }) => {
+                warn!(
+                    "Reassembled image for {} failed verification, downloading it in full",
+                    app_name
+                );
+                let image = self.transport.fetch(&target_url)?;
+                control.verify(&image)?;
+                image
+            }
+            Err(e) => return Err(e.into()),
+        };
 
-        // Install the updated AppImage as a new version
+        // Stage next to the install location so the copy stays on one filesystem.
+        let staging_dir = self.config.bin_dir();
+        fs::create_dir_all(&staging_dir)?;
+        let mut staged = tempfile::Builder::new()
+            .prefix(".appiman-update-")
+            .suffix(".AppImage")
+            .tempfile_in(&staging_dir)?;
+        staged.write_all(&image)?;
+        staged.flush()?;
+
+        // The new image must pass the same checks as a scanned one and be
+        // signed by the same key as the installed one.
+        let staged_image = AppImage::new(staged.path().to_path_buf())?;
+        let security_report = self.security_checker.check_app(app_name, &staged_image)?;
+        self.security_checker
+            .print_warnings(&staged_image, &security_report);
+        match &security_report.overall_status {
+            SecurityStatus::KeyChanged(message) => {
+                return Err(UpdateError::KeyChanged(message.clone()));
+            }
+            // Includes images `require_signatures` rejects
+            SecurityStatus::Error(message) => {
+                return Err(SecurityError::CheckFailed(message.clone()).into());
+            }
+            _ => {}
+        }
+
+        let version = update_info
+            .new_version
+            .clone()
+            .unwrap_or_else(|| format!("{}-{}", app_name, Utc::now().format("%Y%m%d%H%M%S")));
+
+        // The previous image is backed up in the same transaction
+        let backup = self
+            .config
+            .updates
+            .backup_enabled
+            .then(|| self.get_backup_path(app_name));
+        self.version_manager
+            .install_update(app_name, &version, staged.path(), backup.as_deref())?;
+        if backup.is_some() {
+            self.cleanup_old_backups(app_name)?;
+        }
         self.version_manager
-            .install_version(&app_name, &version, app_path)?;
+            .save_security_report(app_name, &version, &security_report)?;
+        self.security_checker
+            .record_signer(app_name, &security_report)?;
 
-        info!("Successfully updated {} to version {}", app_name, version);
+        info!(version = %version, "Successfully updated {} to version {}", app_name, version);
         Ok(())
     }
 
-    #[instrument(skip(self, app_name))]
+    /// Turns update information into the URL of a `.zsync` control file,
+    /// taking the newest release in `channel` if one is set.
+    fn resolve_zsync_url(
+        &self,
+        info: &UpdateInformation,
+        channel: Option<&str>,
+    ) -> Result<String, UpdateError> {
+        match info {
+            UpdateInformation::Zsync { url } => match channel {
+                // A fixed URL only ever offers one stream.
+                Some(channel) => Err(UpdateError::UpdateFailed(format!(
+                    "The {} channel needs gh-releases-zsync update information",
+                    channel
+                ))),
+                None => Ok(url.clone()),
+            },
+            UpdateInformation::GitHubReleases {
+                owner,
+                repo,
+                tag,
+                filename,
+            } => {
+                let api = self.config.updates.github_api_url.trim_end_matches('/');
+                let releases_url = format!("{}/repos/{}/{}/releases", api, owner, repo);
+                let release = match (channel, tag.as_str()) {
+                    // Releases are listed newest first.
+                    (Some(channel), _) => self
+                        .fetch_json::<Vec<GitHubRelease>>(&releases_url)?
+                        .into_iter()
+                        .find(|r| r.in_channel(channel))
+                        .ok_or_else(|| {
+                            UpdateError::UpdateFailed(format!(
+                                "No {} release found for {}/{}",
+                                channel, owner, repo
+                            ))
+                        })?,
+                    (None, "latest") => self.fetch_json::<GitHubRelease>(&format!("{}/latest", releases_url))?,
+                    (None, "latest-pre" | "latest-all") => self
+                        .fetch_json::<Vec<GitHubRelease>>(&releases_url)?
+                        .into_iter()
+                        .find(|r| !r.draft && (tag == "latest-all" || r.prerelease))
+                        .ok_or_else(|| {
+                            UpdateError::UpdateFailed(format!(
+                                "No matching release found for {}/{}",
+                                owner, repo
+                            ))
+                        })?,
+                    (None, tag) => self.fetch_json::<GitHubRelease>(&format!("{}/tags/{}", releases_url, tag))?,
+                };
+
+                release
+                    .assets
+                    .into_iter()
+                    .find(|asset| wildcard_matches(filename, &asset.name))
+                    .map(|asset| asset.browser_download_url)
+                    .ok_or_else(|| {
+                        UpdateError::UpdateFailed(format!(
+                            "No release asset of {}/{} matches {}",
+                            owner, repo, filename
+                        ))
+                    })
+            }
+            UpdateInformation::Pling { .. } => Err(UpdateError::UpdateFailed(
+                "pling-v1-zsync update information is not supported".to_string(),
+            )),
+        }
+    }
+
+    fn fetch_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, UpdateError> {
+        let data = self.transport.fetch(url)?;
+        serde_json::from_slice(&data)
+            .map_err(|e| UpdateError::UpdateFailed(format!("Invalid response from {}: {}", url, e)))
+    }
+
     pub fn rollback_update(&self, app_name: &str) -> Result<(), UpdateError> {
+        let Some(audit) = &self.audit else {
+            return self.switch_to_previous_version(app_name);
+        };
+
+        let before = self.version_manager.active_checksum(app_name);
+        let result = self.switch_to_previous_version(app_name);
+        let mut event = AuditEvent::new(AuditOperation::Rollback)
+            .with_app(app_name)
+            .with_checksums(before, self.version_manager.active_checksum(app_name))
+            .with_result(&result);
+        if let Ok(Some(version)) = self.version_manager.get_current_version(app_name) {
+            event = event.with_version(&version);
+        }
+        audit.record(event);
+        result
+    }
+
+    #[instrument(skip(self, app_name), fields(app = app_name, operation = "rollback"))]
+    fn switch_to_previous_version(&self, app_name: &str) -> Result<(), UpdateError> {
         info!("Rolling back update for {}", app_name);
 
+        let (current_version, previous_version) = self.rollback_target(app_name)?;
+        self.version_manager
+            .switch_version(app_name, &previous_version)?;
+
+        info!(
+            "Successfully rolled back {} from {} to {}",
+            app_name, current_version, previous_version
+        );
+        Ok(())
+    }
+
+    /// The active version of `app_name` and the version a rollback would
+    /// switch to.
+    pub fn rollback_target(&self, app_name: &str) -> Result<(String, String), UpdateError> {
+        if let Some(pinned) = self.version_manager.pinned_version(app_name) {
+            return Err(VersionError::Pinned(app_name.to_string(), pinned).into());
+        }
+
         // Get the current active version
         let current_version = self
             .version_manager
@@ -257,99 +673,50 @@ impl UpdateManager {
                 UpdateError::RollbackFailed(format!("No active version found for {}", app_name))
             })?;
 
-        // Get all versions and find the previous one by installation time
+        // The newest version older than the current one
         let versions = self
             .version_manager
             .list_versions(app_name)
             .map_err(UpdateError::Version)?;
-        let mut sorted_versions: Vec<_> = versions.iter().collect();
-        sorted_versions.sort_by(|a, b| b.installed_at.cmp(&a.installed_at));
-
-        // Find the version before the current one
-        let previous_version = sorted_versions
+        let previous_version = versions
             .iter()
-            .find(|v| !v.is_active)
-            .map(|v| v.version.as_str())
+            .position(|v| v.is_active)
+            .and_then(|active| active.checked_sub(1))
+            .map(|previous| versions[previous].version.clone())
             .ok_or_else(|| {
                 UpdateError::RollbackFailed(format!("No previous version found for {}", app_name))
             })?;
 
-        // Switch to the previous version
-        self.version_manager
-            .switch_version(app_name, previous_version)?;
-
-        info!(
-            "Successfully rolled back {} from {} to {}",
-            app_name, current_version, previous_version
-        );
-        Ok(())
-    }
-
-    #[allow(dead_code)]
-    fn create_backup(&self, app_path: &Path) -> Result<(), UpdateError> {
-        let app_name = app_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown");
-
-        let backup_path = self.get_backup_path(app_name);
-        let backup_dir = backup_path.parent().unwrap();
-
-        if !backup_dir.exists() {
-            fs::create_dir_all(backup_dir).map_err(|e| {
-                UpdateError::BackupFailed(format!("Failed to create backup directory: {}", e))
-            })?;
-        }
-
-        fs::copy(app_path, &backup_path)
-            .map_err(|e| UpdateError::BackupFailed(format!("Failed to create backup: {}", e)))?;
-
-        // Clean up old backups
-        self.cleanup_old_backups(app_name)?;
-
-        debug!("Created backup: {:?}", backup_path);
-        Ok(())
+        Ok((current_version, previous_version))
     }
 
-    #[allow(dead_code)]
+    /// Removes the backups of `app_name` the retention policy does not keep.
     fn cleanup_old_backups(&self, app_name: &str) -> Result<(), UpdateError> {
-        let backup_dir = self.config.bin_dir().join("backups");
-        if !backup_dir.exists() {
-            return Ok(());
-        }
-
-        let pattern = format!("{}_backup_", app_name);
-        let mut backups: Vec<_> = fs::read_dir(&backup_dir)?
-            .filter_map(Result::ok)
-            .filter(|entry| {
-                entry
-                    .file_name()
-                    .to_str()
-                    .map(|name| name.starts_with(&pattern))
-                    .unwrap_or(false)
-            })
-            .collect();
-
-        // Sort by modification time (newest first)
-        backups.sort_by(|a, b| {
-            let a_time = a.metadata().ok().and_then(|m| m.modified().ok());
-            let b_time = b.metadata().ok().and_then(|m| m.modified().ok());
-            b_time.cmp(&a_time)
-        });
-
-        // Keep only max_backups
-        if backups.len() > self.config.updates.max_backups {
-            for backup in backups.iter().skip(self.config.updates.max_backups) {
-                if let Err(e) = fs::remove_file(backup.path()) {
-                    warn!("Failed to remove old backup {:?}: {}", backup.path(), e);
-                }
+        let backups = list_backups(&self.config)?
+            .remove(app_name)
+            .unwrap_or_default();
+        let metadata = self
+            .version_manager
+            .load_app_metadata(app_name)
+            .map_err(UpdateError::Version)?;
+        let verdicts = RetentionPolicy::from_config(&self.config).plan_app(
+            &metadata,
+            |version| {
+                dir_size(&self.version_manager.get_version_dir(app_name, version)).unwrap_or(0)
+            },
+            &backups,
+            Utc::now(),
+        );
+        for verdict in verdicts.into_iter().filter(|v| v.keep.is_none()) {
+            if let Retained::Backup(path) = verdict.item
+                && let Err(e) = fs::remove_file(&path)
+            {
+                warn!("Failed to remove old backup {:?}: {}", path, e);
             }
         }
-
         Ok(())
     }
 
-    #[allow(dead_code)]
     fn get_backup_path(&self, app_name: &str) -> PathBuf {
         let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
         let backup_dir = self.config.bin_dir().join("backups");
@@ -380,109 +747,235 @@ impl UpdateManager {
     }
 
     fn extract_version_from_path(&self, path: &Path) -> Option<String> {
-        let name = path.file_stem()?.to_str()?;
-        if let Some(pos) = name.rfind('-') {
-            let potential_version = &name[pos + 1..];
-            let version = potential_version
-                .strip_prefix('v')
-                .unwrap_or(potential_version);
-            if version.chars().all(|c| c.is_numeric() || c == '.') {
-                return Some(version.to_string());
-            }
+        version_from_file_name(path.file_stem()?.to_str()?)
+    }
+}
+
+fn sha1_file(path: &Path) -> Result<String, UpdateError> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
         }
-        None
+        hasher.update(&buffer[..n]);
     }
+    Ok(hex::encode(hasher.finalize()))
 }
 
-pub fn run_update_check() -> Result<(), UpdateError> {
+pub fn run_update_check() -> Result<UpdateReport, UpdateError> {
     let manager = UpdateManager::new()?;
     let report = manager.check_updates()?;
 
-    println!("Update Check Results:");
-    println!("====================");
+    say!("Update Check Results:");
+    say!("====================");
 
     for update in &report.checked {
         if update.update_available {
-            println!("✅ {}: Update available", update.name);
+            say!("✅ {}: Update available", update.name);
+            if let Some(source) = &update.update_information {
+                say!("   Source: {}", source);
+            }
+            if let Some(channel) = &update.channel {
+                say!("   Channel: {}", channel);
+            }
             if let Some(new_ver) = &update.new_version {
-                println!(
+                say!(
                     "   Current: {} | New: {}",
                     update.current_version.as_deref().unwrap_or("unknown"),
                     new_ver
                 );
             }
         } else {
-            println!("✅ {}: Up to date", update.name);
+            say!("✅ {}: Up to date", update.name);
         }
     }
+    for held in &report.held {
+        say!("📌 {}: Held at {}", held.app, held.version);
+    }
 
     if report.failed.is_empty() {
-        println!(
+        say!(
             "\n✅ All {} AppImages checked successfully",
             report.checked.len()
         );
     } else {
-        println!("\n⚠️  {} failures encountered", report.failed.len());
+        say!("\n⚠️  {} failures encountered", report.failed.len());
         for (name, error) in &report.failed {
-            println!("❌ {}: {}", name, error);
+            say!("❌ {}: {}", name, error);
         }
     }
 
     if report.updates_available_count() > 0 {
-        println!("\n💡 Run 'appiman update --apply' to apply available updates");
+        say!("\n💡 Run 'appiman update apply' to apply available updates");
     }
 
-    Ok(())
+    Ok(report)
 }
 
-pub fn run_update_apply(dry_run: bool) -> Result<(), UpdateError> {
+pub fn run_update_apply(dry_run: bool) -> Result<UpdateReport, UpdateError> {
     let manager = UpdateManager::new()?;
     let report = manager.apply_updates(dry_run)?;
 
     if dry_run {
-        println!("DRY RUN - Update Application Results:");
+        say!("DRY RUN - Update Application Results:");
     } else {
-        println!("Update Application Results:");
+        say!("Update Application Results:");
+    }
+    say!("================================");
+
+    for held in &report.held {
+        say!("📌 {}: Held at {}", held.app, held.version);
     }
-    println!("================================");
 
     if report.updated.is_empty() && report.failed.is_empty() {
-        println!("✅ No updates available or needed");
-        return Ok(());
+        say!("✅ No updates available or needed");
+        return Ok(report);
     }
 
     if !report.updated.is_empty() {
-        println!("\n✅ Successfully updated:");
+        say!("\n✅ Successfully updated:");
         for name in &report.updated {
-            println!("   • {}", name);
+            say!("   • {}", name);
         }
     }
 
     if !report.failed.is_empty() {
-        println!("\n❌ Failed to update:");
+        say!("\n❌ Failed to update:");
         for (name, error) in &report.failed {
-            println!("   • {}: {}", name, error);
+            say!("   • {}: {}", name, error);
         }
     }
 
-    Ok(())
+    Ok(report)
 }
 
-pub fn run_rollback(app_name: &str) -> Result<(), UpdateError> {
+pub fn run_rollback(app_name: &str, dry_run: bool) -> Result<RollbackReport, UpdateError> {
     let manager = UpdateManager::new()?;
+    let (current, previous) = manager.rollback_target(app_name)?;
+    let report = RollbackReport {
+        app: app_name.to_string(),
+        from: current,
+        to: previous,
+    };
+    if dry_run {
+        say!(
+            "[DRY RUN] Would roll back {} from {} to {}",
+            app_name, report.from, report.to
+        );
+        return Ok(report);
+    }
     manager.rollback_update(app_name)?;
 
-    println!("✅ Successfully rolled back {}", app_name);
-    Ok(())
+    say!("✅ Successfully rolled back {}", app_name);
+    Ok(report)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::AppMetadata;
+    use crate::core::testing::{fake_appimage, ElfBuilder};
     use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
     use tempfile::TempDir;
 
+    /// Delegates to [`DefaultTransport`] and records every range requested.
+    struct RecordingTransport {
+        inner: DefaultTransport,
+        ranges: Arc<Mutex<Vec<Range<u64>>>>,
+    }
+
+    impl Transport for RecordingTransport {
+        fn fetch(&self, url: &str) -> Result<Vec<u8>, UpdateError> {
+            self.inner.fetch(url)
+        }
+
+        fn fetch_range(&self, url: &str, range: Range<u64>) -> Result<Vec<u8>, UpdateError> {
+            self.ranges.lock().unwrap().push(range.clone());
+            self.inner.fetch_range(url, range)
+        }
+    }
+
+    fn sample(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    fn image_with_update_info(update_info: &str, body: &[u8]) -> Vec<u8> {
+        let mut section = update_info.as_bytes().to_vec();
+        section.resize(1024, 0);
+        let mut image = ElfBuilder::new().section(".upd_info", &section).build();
+        image.extend_from_slice(body);
+        image
+    }
+
+    /// Installs `image` as the current version of `app` and returns its path.
+    fn install(config: &Config, app: &str, version: &str, image: &[u8]) -> PathBuf {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(format!("{}.AppImage", app));
+        fs::write(&path, image).unwrap();
+        let version_manager = VersionManager::new(config.clone());
+        version_manager.install_version(app, version, &path).unwrap();
+        version_manager.get_appimage_path(app, version)
+    }
+
+    /// Serves files under `root` over HTTP with single-range support.
+    fn serve(root: PathBuf) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/").to_string();
+
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let line = line.trim().to_ascii_lowercase();
+                    if let Some((start, end)) = line
+                        .strip_prefix("range: bytes=")
+                        .and_then(|r| r.split_once('-'))
+                    {
+                        range = Some((start.parse::<usize>().unwrap(), end.parse::<usize>().unwrap()));
+                    }
+                }
+
+                let (status, body) = match fs::read(root.join(path.trim_start_matches('/'))) {
+                    Ok(data) => match range {
+                        Some((start, end)) => ("206 Partial Content", data[start..=end].to_vec()),
+                        None => ("200 OK", data),
+                    },
+                    Err(_) => ("404 Not Found", Vec::new()),
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(&body);
+            }
+        });
+        base
+    }
+
     fn create_test_config(temp_dir: &TempDir) -> Config {
         let mut config = Config::default();
         config.directories.bin = temp_dir.path().join("bin").to_string_lossy().to_string();
@@ -556,14 +1049,14 @@ mod tests {
         let testapp_v1_dir = testapp_versions_dir.join("1.0.0");
         fs::create_dir_all(&testapp_v1_dir).unwrap();
         let app1 = testapp_v1_dir.join("testapp.AppImage");
-        fs::write(&app1, b"fake appimage").unwrap();
+        fs::write(&app1, fake_appimage(b"")).unwrap();
 
         let another_dir = temp.path().join("bin").join("another");
         let another_versions_dir = another_dir.join("versions");
         let another_v1_dir = another_versions_dir.join("2.0.0");
         fs::create_dir_all(&another_v1_dir).unwrap();
         let app2 = another_v1_dir.join("another.AppImage");
-        fs::write(&app2, b"fake appimage").unwrap();
+        fs::write(&app2, fake_appimage(b"")).unwrap();
 
         // Create metadata for apps
         let mut testapp_metadata = AppMetadata::new("TestApp".to_string(), "testapp".to_string());
@@ -585,10 +1078,7 @@ mod tests {
         let another_current = another_dir.join("current");
         std::os::unix::fs::symlink(&another_v1_dir, &another_current).unwrap();
 
-        let manager = UpdateManager {
-            config,
-            version_manager,
-        };
+        let manager = UpdateManager::from_config(config);
 
         let result = manager.get_registered_appimages();
         assert!(result.is_ok());
@@ -598,6 +1088,308 @@ mod tests {
         assert!(appimages.contains(&app2));
     }
 
+    #[test]
+    fn extract_version_skips_architecture_suffix() {
+        let temp = TempDir::new().unwrap();
+        let manager = UpdateManager::from_config(create_test_config(&temp));
+
+        let version = manager.extract_version_from_path(Path::new("App-2.1.0-x86_64.AppImage"));
+        assert_eq!(version, Some("2.1.0".to_string()));
+        let version = manager.extract_version_from_path(Path::new("App-x86_64.AppImage"));
+        assert_eq!(version, None);
+    }
+
+    #[test]
+    fn wildcard_matches_release_assets() {
+        assert!(wildcard_matches("App-*-x86_64.AppImage.zsync", "App-1.2.3-x86_64.AppImage.zsync"));
+        assert!(!wildcard_matches("App-*-x86_64.AppImage.zsync", "App-1.2.3-aarch64.AppImage.zsync"));
+        assert!(wildcard_matches("App.AppImage.zsync", "App.AppImage.zsync"));
+        assert!(!wildcard_matches("App.AppImage.zsync", "AppXAppImage.zsync"));
+    }
+
+    #[test]
+    fn apply_updates_reuses_blocks_of_installed_version() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(&temp);
+        let mirror = temp.path().join("mirror");
+        fs::create_dir_all(&mirror).unwrap();
+
+        let update_info = format!("zsync|file://{}/App-latest.AppImage.zsync", mirror.display());
+        let old_body = sample(64 * 1024, 1);
+        let mut new_body = old_body[..60 * 1024].to_vec();
+        new_body.extend_from_slice(&sample(2000, 2));
+        let old_image = image_with_update_info(&update_info, &old_body);
+        let new_image = image_with_update_info(&update_info, &new_body);
+
+        fs::write(mirror.join("App-2.0.0-x86_64.AppImage"), &new_image).unwrap();
+        fs::write(
+            mirror.join("App-latest.AppImage.zsync"),
+            ControlFile::generate(&new_image, 1024, "App-2.0.0-x86_64.AppImage", "App-2.0.0-x86_64.AppImage"),
+        )
+        .unwrap();
+
+        let installed = install(&config, "app", "1.0.0", &old_image);
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let manager = UpdateManager::from_config(config).with_transport(Box::new(RecordingTransport {
+            inner: DefaultTransport::new(),
+            ranges: Arc::clone(&ranges),
+        }));
+
+        let info = manager.check_single_update(&installed).unwrap();
+        assert!(info.update_available);
+        assert_eq!(info.current_version.as_deref(), Some("1.0.0"));
+        assert_eq!(info.new_version.as_deref(), Some("2.0.0"));
+
+        let report = manager.apply_updates(false).unwrap();
+        assert_eq!(report.updated, vec!["app".to_string()]);
+        assert!(report.failed.is_empty());
+
+        let downloaded: u64 = ranges.lock().unwrap().iter().map(|r| r.end - r.start).sum();
+        assert!(downloaded < new_image.len() as u64 / 4, "downloaded {} bytes", downloaded);
+
+        let updated = manager.version_manager.get_appimage_path("app", "2.0.0");
+        assert_eq!(fs::read(updated).unwrap(), new_image);
+        assert_eq!(
+            manager.version_manager.get_current_version("app").unwrap().as_deref(),
+            Some("2.0.0")
+        );
+        // The previous version is kept for rollback, and backed up.
+        assert_eq!(fs::read(&installed).unwrap(), old_image);
+        let backups: Vec<_> = fs::read_dir(temp.path().join("bin/backups"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read(&backups[0]).unwrap(), old_image);
+
+        let recheck = manager
+            .check_single_update(&manager.version_manager.get_appimage_path("app", "2.0.0"))
+            .unwrap();
+        assert!(!recheck.update_available);
+    }
+
+    #[test]
+    fn apply_updates_resolves_github_releases_over_http() {
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(&temp);
+        let root = temp.path().join("www");
+        let base = serve(root.clone());
+        config.updates.github_api_url = base.clone();
+
+        let update_info = "gh-releases-zsync|owner|app|latest|App-*-x86_64.AppImage.zsync";
+        let old_image = image_with_update_info(update_info, &sample(32 * 1024, 3));
+        let mut new_body = sample(500, 4);
+        new_body.extend_from_slice(&sample(32 * 1024, 3));
+        let new_image = image_with_update_info(update_info, &new_body);
+
+        let releases = root.join("repos/owner/app/releases");
+        let downloads = root.join("download/v3.1.0");
+        fs::create_dir_all(&releases).unwrap();
+        fs::create_dir_all(&downloads).unwrap();
+        fs::write(
+            releases.join("latest"),
+            format!(
+                r#"{{"tag_name": "v3.1.0", "prerelease": false, "assets": [
+                    {{"name": "App-3.1.0-aarch64.AppImage.zsync", "browser_download_url": "{0}/download/v3.1.0/wrong.zsync"}},
+                    {{"name": "App-3.1.0-x86_64.AppImage.zsync", "browser_download_url": "{0}/download/v3.1.0/App-3.1.0-x86_64.AppImage.zsync"}}
+                ]}}"#,
+                base
+            ),
+        )
+        .unwrap();
+        fs::write(downloads.join("App-3.1.0-x86_64.AppImage"), &new_image).unwrap();
+        fs::write(
+            downloads.join("App-3.1.0-x86_64.AppImage.zsync"),
+            ControlFile::generate(&new_image, 1024, "App-3.1.0-x86_64.AppImage", "App-3.1.0-x86_64.AppImage"),
+        )
+        .unwrap();
+
+        install(&config, "app", "3.0.0", &old_image);
+        let manager = UpdateManager::from_config(config);
+
+        let report = manager.apply_updates(false).unwrap();
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert_eq!(report.updated, vec!["app".to_string()]);
+        assert_eq!(
+            fs::read(manager.version_manager.get_appimage_path("app", "3.1.0")).unwrap(),
+            new_image
+        );
+    }
+
+    #[test]
+    fn channel_selects_newest_matching_release() {
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(&temp);
+        let root = temp.path().join("www");
+        config.updates.github_api_url = serve(root.clone());
+        let asset = |tag: &str| {
+            format!(
+                r#"{{"name": "App-{0}-x86_64.AppImage.zsync", "browser_download_url": "https://example.com/{0}.zsync"}}"#,
+                tag
+            )
+        };
+        fs::create_dir_all(root.join("repos/owner/app")).unwrap();
+        fs::write(
+            root.join("repos/owner/app/releases"),
+            format!(
+                r#"[
+                    {{"tag_name": "nightly-20241016", "prerelease": true, "assets": [{}]}},
+                    {{"tag_name": "v3.1.0-beta.2", "draft": true, "assets": [{}]}},
+                    {{"tag_name": "v3.1.0-beta.1", "prerelease": true, "assets": [{}]}},
+                    {{"tag_name": "v3.0.1-rc1", "assets": [{}]}},
+                    {{"tag_name": "v3.0.0", "assets": [{}]}}
+                ]"#,
+                asset("nightly"),
+                asset("3.1.0-beta.2"),
+                asset("3.1.0-beta.1"),
+                asset("3.0.1-rc1"),
+                asset("3.0.0")
+            ),
+        )
+        .unwrap();
+
+        let image = fake_appimage(b"app");
+        install(&config, "app", "3.0.0", &image);
+        let manager = UpdateManager::from_config(config);
+        let info: UpdateInformation = "gh-releases-zsync|owner|app|latest|App-*-x86_64.AppImage.zsync"
+            .parse()
+            .unwrap();
+        let resolve = |channel| manager.resolve_zsync_url(&info, Some(channel)).unwrap();
+        assert_eq!(resolve("stable"), "https://example.com/3.0.0.zsync");
+        assert_eq!(resolve("beta"), "https://example.com/3.1.0-beta.1.zsync");
+        assert_eq!(resolve("nightly"), "https://example.com/nightly.zsync");
+        assert!(manager.resolve_zsync_url(&info, Some("lts")).is_err());
+
+        let fixed: UpdateInformation = "zsync|https://example.com/App.zsync".parse().unwrap();
+        assert!(manager.resolve_zsync_url(&fixed, Some("beta")).is_err());
+
+        let versions = &manager.version_manager;
+        assert_eq!(versions.set_channel("app", Some("Beta")).unwrap(), None);
+        assert_eq!(versions.channel("app").as_deref(), Some("beta"));
+        assert!(matches!(
+            versions.set_channel("app", Some("../x")),
+            Err(VersionError::InvalidChannel(_))
+        ));
+        assert_eq!(versions.set_channel("app", None).unwrap().as_deref(), Some("beta"));
+        assert_eq!(versions.channel("app"), None);
+    }
+
+    #[test]
+    fn check_updates_skips_images_without_update_information() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(&temp);
+        install(&config, "plain", "1.0.0", &fake_appimage(b"plain"));
+
+        let manager = UpdateManager::from_config(config);
+        let report = manager.check_updates().unwrap();
+
+        assert_eq!(report.checked.len(), 1);
+        assert!(!report.checked[0].update_available);
+        assert!(report.checked[0].remote.is_none());
+    }
+
+    #[test]
+    fn apply_updates_rejects_image_with_failing_signature() {
+        let temp = TempDir::new().unwrap();
+        let mut config = create_test_config(&temp);
+        config.security.verify_signatures = true;
+        config.security.trusted_keys_dir = temp.path().join("keys").to_string_lossy().to_string();
+        let mirror = temp.path().join("mirror");
+        fs::create_dir_all(&mirror).unwrap();
+
+        let update_info = format!("zsync|file://{}/App-latest.AppImage.zsync", mirror.display());
+        let old_image = image_with_update_info(&update_info, &sample(4096, 1));
+        let mut section = update_info.as_bytes().to_vec();
+        section.resize(1024, 0);
+        let mut new_image = ElfBuilder::new()
+            .section(".upd_info", &section)
+            .section(".sha256_sig", b"-----BEGIN PGP SIGNATURE-----\nbogus\n\0")
+            .build();
+        new_image.extend_from_slice(&sample(4096, 2));
+
+        fs::write(mirror.join("App-2.0.0-x86_64.AppImage"), &new_image).unwrap();
+        fs::write(
+            mirror.join("App-latest.AppImage.zsync"),
+            ControlFile::generate(&new_image, 1024, "App-2.0.0-x86_64.AppImage", "App-2.0.0-x86_64.AppImage"),
+        )
+        .unwrap();
+
+        install(&config, "app", "1.0.0", &old_image);
+        let manager = UpdateManager::from_config(config);
+        let report = manager.apply_updates(false).unwrap();
+
+        assert!(report.updated.is_empty());
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].1.contains("Signature verification failed"), "{:?}", report.failed);
+        assert_eq!(
+            manager.version_manager.get_current_version("app").unwrap().as_deref(),
+            Some("1.0.0")
+        );
+        assert!(!manager.version_manager.get_appimage_path("app", "2.0.0").exists());
+    }
+
+    #[test]
+    fn check_updates_holds_pinned_apps() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(&temp);
+        // Would fail to resolve if it were checked
+        let image = image_with_update_info("zsync|file:///nonexistent/app.AppImage.zsync", b"app");
+        install(&config, "cad", "1.0.0", &image);
+        VersionManager::new(config.clone())
+            .pin_version("cad", None)
+            .unwrap();
+
+        let manager = UpdateManager::from_config(config);
+        let report = manager.apply_updates(false).unwrap();
+
+        assert!(report.checked.is_empty());
+        assert!(report.failed.is_empty());
+        assert!(report.updated.is_empty());
+        assert_eq!(report.held.len(), 1);
+        assert_eq!(report.held[0].app, "cad");
+        assert_eq!(report.held[0].version, "1.0.0");
+        assert_eq!(report.outcome(), Outcome::NothingToDo);
+    }
+
+    #[test]
+    fn pin_switches_to_version_and_blocks_rollback() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(&temp);
+        install(&config, "cad", "1.0.0", &fake_appimage(b"one"));
+        install(&config, "cad", "2.0.0", &fake_appimage(b"two"));
+        let manager = UpdateManager::from_config(config);
+        let versions = &manager.version_manager;
+
+        assert_eq!(versions.pin_version("cad", Some("1.0.0")).unwrap(), "1.0.0");
+        assert_eq!(versions.get_current_version("cad").unwrap().as_deref(), Some("1.0.0"));
+        assert!(matches!(
+            versions.switch_version("cad", "2.0.0"),
+            Err(VersionError::Pinned(_, _))
+        ));
+        assert!(manager.rollback_update("cad").is_err());
+
+        assert_eq!(versions.unpin_version("cad").unwrap().as_deref(), Some("1.0.0"));
+        assert_eq!(versions.unpin_version("cad").unwrap(), None);
+        versions.switch_version("cad", "2.0.0").unwrap();
+    }
+
+    #[test]
+    fn rollback_target_is_newest_older_version() {
+        let temp = TempDir::new().unwrap();
+        let config = create_test_config(&temp);
+        for (version, body) in [("1.0.0", b"a"), ("3.0.0", b"c"), ("2.0.0", b"b")] {
+            install(&config, "app", version, &fake_appimage(body));
+        }
+        let manager = UpdateManager::from_config(config);
+
+        assert_eq!(
+            manager.rollback_target("app").unwrap(),
+            ("2.0.0".to_string(), "1.0.0".to_string())
+        );
+        manager.version_manager.switch_version("app", "1.0.0").unwrap();
+        assert!(manager.rollback_target("app").is_err());
+    }
+
     #[test]
     fn backup_path_generation_works() {
         let temp = TempDir::new().unwrap();
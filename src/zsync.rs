@@ -0,0 +1,589 @@
+//! zsync control files and block matching.
+//!
+//! A `.zsync` file lists a rolling checksum and a truncated MD4 for every
+//! block of the target file. Blocks that already exist somewhere in the
+//! installed version are copied locally; only the rest are downloaded.
+
+use md4::{Digest as _, Md4};
+use sha1::Sha1;
+use std::collections::HashMap;
+use std::ops::Range;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ZsyncError {
+    #[error("Malformed zsync control file: {0}")]
+    Malformed(String),
+
+    #[error("Missing zsync header: {0}")]
+    MissingHeader(&'static str),
+
+    #[error("Downloaded data does not cover bytes {0:?}")]
+    MissingData(Range<u64>),
+
+    #[error("SHA-1 mismatch: expected {expected}, got {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+/// Largest block size accepted. `zsyncmake` never picks more than 64 KiB;
+/// the seed is padded by a whole block, so this bounds that allocation.
+const MAX_BLOCKSIZE: usize = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BlockSum {
+    rsum: u32,
+    checksum: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ControlFile {
+    pub filename: Option<String>,
+    pub blocksize: usize,
+    pub length: u64,
+    pub urls: Vec<String>,
+    pub sha1: String,
+    seq_matches: usize,
+    rsum_bytes: usize,
+    checksum_bytes: usize,
+    blocks: Vec<BlockSum>,
+}
+
+/// Where each block of the target comes from: `Some(offset)` into the local
+/// seed file, or `None` when it has to be downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockPlan {
+    sources: Vec<Option<usize>>,
+}
+
+impl BlockPlan {
+    pub fn reused_blocks(&self) -> usize {
+        self.sources.iter().filter(|s| s.is_some()).count()
+    }
+
+    pub fn total_blocks(&self) -> usize {
+        self.sources.len()
+    }
+}
+
+impl ControlFile {
+    pub fn parse(data: &[u8]) -> Result<Self, ZsyncError> {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut pos = 0;
+        loop {
+            let end = data[pos..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map(|i| pos + i)
+                .ok_or_else(|| ZsyncError::Malformed("unterminated header".to_string()))?;
+            let line = std::str::from_utf8(&data[pos..end])
+                .map_err(|_| ZsyncError::Malformed("header is not UTF-8".to_string()))?
+                .trim_end_matches('\r');
+            pos = end + 1;
+            if line.is_empty() {
+                break;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| ZsyncError::Malformed(format!("bad header line: {}", line)))?;
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        };
+        let number = |name: &'static str| -> Result<u64, ZsyncError> {
+            header(name)
+                .ok_or(ZsyncError::MissingHeader(name))?
+                .parse()
+                .map_err(|_| ZsyncError::Malformed(format!("invalid {}", name)))
+        };
+
+        let blocksize = number("Blocksize")? as usize;
+        if blocksize == 0 || !blocksize.is_power_of_two() {
+            return Err(ZsyncError::Malformed(format!(
+                "block size {} is not a power of two",
+                blocksize
+            )));
+        }
+        if blocksize > MAX_BLOCKSIZE {
+            return Err(ZsyncError::Malformed(format!(
+                "block size {} is larger than {}",
+                blocksize, MAX_BLOCKSIZE
+            )));
+        }
+        let length = number("Length")?;
+
+        let (seq_matches, rsum_bytes, checksum_bytes) = match header("Hash-Lengths") {
+            Some(value) => {
+                let parts: Vec<usize> = value
+                    .split(',')
+                    .map(|p| p.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| ZsyncError::Malformed("invalid Hash-Lengths".to_string()))?;
+                match parts[..] {
+                    [seq, rsum, checksum]
+                        if (1..=2).contains(&seq)
+                            && (1..=4).contains(&rsum)
+                            && (3..=16).contains(&checksum) =>
+                    {
+                        (seq, rsum, checksum)
+                    }
+                    _ => {
+                        return Err(ZsyncError::Malformed(format!(
+                            "unsupported Hash-Lengths: {}",
+                            value
+                        )));
+                    }
+                }
+            }
+            None => (1, 4, 16),
+        };
+
+        let urls: Vec<String> = headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("URL"))
+            .map(|(_, v)| v.clone())
+            .collect();
+        if urls.is_empty() {
+            return Err(ZsyncError::MissingHeader("URL"));
+        }
+        let sha1 = header("SHA-1")
+            .ok_or(ZsyncError::MissingHeader("SHA-1"))?
+            .to_ascii_lowercase();
+
+        // The length is untrusted: it must not claim more blocks than the
+        // table lists checksums for.
+        let entry_len = rsum_bytes + checksum_bytes;
+        let table = &data[pos..];
+        let block_count = length.div_ceil(blocksize as u64);
+        if block_count > (table.len() / entry_len) as u64 {
+            return Err(ZsyncError::Malformed(format!(
+                "expected {} block checksums",
+                block_count
+            )));
+        }
+
+        let blocks = table
+            .chunks_exact(entry_len)
+            .take(block_count as usize)
+            .map(|entry| {
+                let mut rsum = [0u8; 4];
+                rsum[4 - rsum_bytes..].copy_from_slice(&entry[..rsum_bytes]);
+                BlockSum {
+                    rsum: u32::from_be_bytes(rsum),
+                    checksum: entry[rsum_bytes..].to_vec(),
+                }
+            })
+            .collect();
+
+        Ok(ControlFile {
+            filename: header("Filename").map(str::to_string),
+            blocksize,
+            length,
+            urls,
+            sha1,
+            seq_matches,
+            rsum_bytes,
+            checksum_bytes,
+            blocks,
+        })
+    }
+
+    /// Builds a control file for `data`, as `zsyncmake` would.
+    #[cfg(test)]
+    pub fn generate(data: &[u8], blocksize: usize, filename: &str, url: &str) -> Vec<u8> {
+        let mut out = format!(
+            "zsync: 0.6.2\nFilename: {}\nBlocksize: {}\nLength: {}\nHash-Lengths: 2,4,16\nURL: {}\nSHA-1: {}\n\n",
+            filename,
+            blocksize,
+            data.len(),
+            url,
+            hex::encode(Sha1::digest(data))
+        )
+        .into_bytes();
+        for chunk in data.chunks(blocksize) {
+            let mut block = chunk.to_vec();
+            block.resize(blocksize, 0);
+            out.extend_from_slice(&rsum(&block).to_be_bytes());
+            out.extend_from_slice(&Md4::digest(&block));
+        }
+        out
+    }
+
+    /// Finds blocks of the target that already exist anywhere in `seed`.
+    pub fn plan(&self, seed: &[u8]) -> BlockPlan {
+        let mut sources = vec![None; self.blocks.len()];
+        let size = self.blocksize;
+
+        if seed.is_empty() {
+            return BlockPlan { sources };
+        }
+        // zsync pads both the target and the seed with zeros to a whole block.
+        let mut padded = seed.to_vec();
+        padded.resize(seed.len() + size, 0);
+
+        let mask = self.rsum_mask();
+        let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (i, block) in self.blocks.iter().enumerate() {
+            index.entry(block.rsum).or_default().push(i);
+        }
+
+        let mut pos = 0;
+        let mut sum = Rsum::new(&padded[..size]);
+        while pos + size <= padded.len() {
+            let mut matched = false;
+            if let Some(candidates) = index.get(&(sum.value() & mask)) {
+                let digest = Md4::digest(&padded[pos..pos + size]);
+                for &i in candidates {
+                    if sources[i].is_none()
+                        && digest[..self.checksum_bytes] == self.blocks[i].checksum[..]
+                        && self.next_block_matches(&padded, pos + size, i + 1)
+                    {
+                        sources[i] = Some(pos);
+                        matched = true;
+                    }
+                }
+            }
+
+            if matched && pos + 2 * size <= padded.len() {
+                pos += size;
+                sum = Rsum::new(&padded[pos..pos + size]);
+            } else if pos + size < padded.len() {
+                sum.roll(padded[pos], padded[pos + size], size);
+                pos += 1;
+            } else {
+                break;
+            }
+        }
+
+        BlockPlan { sources }
+    }
+
+    /// Byte ranges of the target that must be downloaded, with adjacent
+    /// blocks merged into a single request.
+    pub fn missing_ranges(&self, plan: &BlockPlan) -> Vec<Range<u64>> {
+        let size = self.blocksize as u64;
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        for (i, source) in plan.sources.iter().enumerate() {
+            if source.is_some() {
+                continue;
+            }
+            let start = i as u64 * size;
+            let end = (start + size).min(self.length);
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+        ranges
+    }
+
+    /// Reconstructs the target from the seed and the downloaded ranges and
+    /// checks it against the control file's SHA-1.
+    pub fn assemble(
+        &self,
+        seed: &[u8],
+        plan: &BlockPlan,
+        downloaded: &[(Range<u64>, Vec<u8>)],
+    ) -> Result<Vec<u8>, ZsyncError> {
+        // Every byte of the target is copied from the seed or a download,
+        // so a larger length cannot be honest; check before allocating.
+        let available = plan.reused_blocks() as u64 * self.blocksize as u64
+            + downloaded.iter().map(|(_, data)| data.len() as u64).sum::<u64>();
+        if self.length > available {
+            return Err(ZsyncError::MissingData(available..self.length));
+        }
+
+        let size = self.blocksize;
+        let mut target = vec![0u8; self.length as usize];
+
+        for (i, source) in plan.sources.iter().enumerate() {
+            let Some(offset) = source else { continue };
+            let start = i * size;
+            let end = (start + size).min(target.len());
+            let available = seed.len().saturating_sub(*offset).min(end - start);
+            // Anything past the end of the seed is zero padding.
+            target[start..start + available].copy_from_slice(&seed[*offset..*offset + available]);
+        }
+
+        for range in self.missing_ranges(plan) {
+            let (have, data) = downloaded
+                .iter()
+                .find(|(r, _)| r.start <= range.start && r.end >= range.end)
+                .ok_or_else(|| ZsyncError::MissingData(range.clone()))?;
+            let from = (range.start - have.start) as usize;
+            let len = (range.end - range.start) as usize;
+            let chunk = data
+                .get(from..from + len)
+                .ok_or_else(|| ZsyncError::MissingData(range.clone()))?;
+            target[range.start as usize..range.end as usize].copy_from_slice(chunk);
+        }
+
+        self.verify(&target)?;
+        Ok(target)
+    }
+
+    pub fn verify(&self, data: &[u8]) -> Result<(), ZsyncError> {
+        let actual = hex::encode(Sha1::digest(data));
+        if actual != self.sha1 {
+            return Err(ZsyncError::ChecksumMismatch {
+                expected: self.sha1.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    fn rsum_mask(&self) -> u32 {
+        // Only the trailing `rsum_bytes` of the big-endian checksum are stored.
+        match self.rsum_bytes {
+            4 => u32::MAX,
+            n => (1u32 << (8 * n)) - 1,
+        }
+    }
+
+    /// With `seq_matches = 2` the checksums are short enough that a single
+    /// block match is not trusted unless the following block matches too.
+    fn next_block_matches(&self, padded: &[u8], pos: usize, next: usize) -> bool {
+        if self.seq_matches < 2 || next >= self.blocks.len() {
+            return true;
+        }
+        let Some(window) = padded.get(pos..pos + self.blocksize) else {
+            return false;
+        };
+        let block = &self.blocks[next];
+        rsum(window) & self.rsum_mask() == block.rsum
+            && Md4::digest(window)[..self.checksum_bytes] == block.checksum[..]
+    }
+}
+
+/// Rolling checksum used by zsync, stored as `a` in the high and `b` in the
+/// low half of a `u32`.
+struct Rsum {
+    a: u16,
+    b: u16,
+}
+
+impl Rsum {
+    fn new(block: &[u8]) -> Self {
+        let len = block.len();
+        let mut a = 0u16;
+        let mut b = 0u16;
+        for (i, &c) in block.iter().enumerate() {
+            a = a.wrapping_add(u16::from(c));
+            b = b.wrapping_add(((len - i) as u16).wrapping_mul(u16::from(c)));
+        }
+        Rsum { a, b }
+    }
+
+    fn roll(&mut self, old: u8, new: u8, len: usize) {
+        self.a = self
+            .a
+            .wrapping_sub(u16::from(old))
+            .wrapping_add(u16::from(new));
+        self.b = self
+            .b
+            .wrapping_sub((len as u16).wrapping_mul(u16::from(old)))
+            .wrapping_add(self.a);
+    }
+
+    fn value(&self) -> u32 {
+        (u32::from(self.a) << 16) | u32::from(self.b)
+    }
+}
+
+fn rsum(block: &[u8]) -> u32 {
+    Rsum::new(block).value()
+}
+
+/// Resolves the (possibly relative) `URL:` header against the location the
+/// control file was fetched from.
+pub fn resolve_url(base: &str, reference: &str) -> String {
+    if reference.contains("://") {
+        return reference.to_string();
+    }
+    let scheme_end = base.find("://").map(|i| i + 3).unwrap_or(0);
+    if let Some(path) = reference.strip_prefix('/') {
+        let host_end = base[scheme_end..]
+            .find('/')
+            .map(|i| scheme_end + i)
+            .unwrap_or(base.len());
+        return format!("{}/{}", &base[..host_end], path);
+    }
+    let base = base.split(['?', '#']).next().unwrap_or(base);
+    match base.rfind('/') {
+        Some(i) if i >= scheme_end => format!("{}{}", &base[..=i], reference),
+        _ => format!("{}/{}", base, reference),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_reads_headers_and_blocks() {
+        let data = sample(5000, 1);
+        let control = ControlFile::parse(&ControlFile::generate(
+            &data,
+            1024,
+            "App.AppImage",
+            "App.AppImage",
+        ))
+        .unwrap();
+
+        assert_eq!(control.filename.as_deref(), Some("App.AppImage"));
+        assert_eq!(control.blocksize, 1024);
+        assert_eq!(control.length, 5000);
+        assert_eq!(control.urls, vec!["App.AppImage".to_string()]);
+        assert_eq!(control.blocks.len(), 5);
+    }
+
+    #[test]
+    fn parse_rejects_truncated_block_table() {
+        let data = sample(5000, 1);
+        let mut raw = ControlFile::generate(&data, 1024, "App.AppImage", "App.AppImage");
+        raw.truncate(raw.len() - 10);
+
+        assert!(matches!(
+            ControlFile::parse(&raw),
+            Err(ZsyncError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_length_beyond_block_table() {
+        let data = sample(5000, 1);
+        let raw = ControlFile::generate(&data, 1024, "App.AppImage", "App.AppImage");
+        let huge = String::from_utf8_lossy(&raw)
+            .replace("Length: 5000", &format!("Length: {}", u64::MAX))
+            .into_bytes();
+        assert!(matches!(
+            ControlFile::parse(&huge),
+            Err(ZsyncError::Malformed(_))
+        ));
+
+        let oversized = String::from_utf8_lossy(&raw)
+            .replace("Blocksize: 1024", &format!("Blocksize: {}", 1u64 << 40))
+            .into_bytes();
+        assert!(matches!(
+            ControlFile::parse(&oversized),
+            Err(ZsyncError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn assemble_rejects_length_beyond_available_data() {
+        let new = sample(4096, 5);
+        let control = ControlFile::parse(&ControlFile::generate(
+            &new,
+            1024,
+            "App.AppImage",
+            "App.AppImage",
+        ))
+        .unwrap();
+        let plan = control.plan(&[]);
+
+        assert!(matches!(
+            control.assemble(&[], &plan, &[(0..1024, new[..1024].to_vec())]),
+            Err(ZsyncError::MissingData(range)) if range == (1024..4096)
+        ));
+    }
+
+    #[test]
+    fn rolling_checksum_matches_direct_computation() {
+        let data = sample(300, 7);
+        let mut sum = Rsum::new(&data[..64]);
+        for pos in 1..=data.len() - 64 {
+            sum.roll(data[pos - 1], data[pos + 63], 64);
+            assert_eq!(sum.value(), rsum(&data[pos..pos + 64]));
+        }
+    }
+
+    #[test]
+    fn plan_reuses_shifted_blocks_and_downloads_the_rest() {
+        let old = sample(16 * 1024, 3);
+        // New version: a changed header, the old body shifted by 100 bytes
+        // and a new tail.
+        let mut new = sample(100, 9);
+        new.extend_from_slice(&old[..12 * 1024]);
+        new.extend_from_slice(&sample(3000, 11));
+
+        let control = ControlFile::parse(&ControlFile::generate(
+            &new,
+            1024,
+            "App.AppImage",
+            "App.AppImage",
+        ))
+        .unwrap();
+        let plan = control.plan(&old);
+        assert!(
+            plan.reused_blocks() >= 10,
+            "reused {}",
+            plan.reused_blocks()
+        );
+
+        let downloaded: Vec<_> = control
+            .missing_ranges(&plan)
+            .into_iter()
+            .map(|r| (r.clone(), new[r.start as usize..r.end as usize].to_vec()))
+            .collect();
+        let rebuilt = control.assemble(&old, &plan, &downloaded).unwrap();
+        assert_eq!(rebuilt, new);
+    }
+
+    #[test]
+    fn assemble_detects_corrupt_downloads() {
+        let new = sample(4096, 5);
+        let control = ControlFile::parse(&ControlFile::generate(
+            &new,
+            1024,
+            "App.AppImage",
+            "App.AppImage",
+        ))
+        .unwrap();
+        let plan = control.plan(&[]);
+        assert_eq!(control.missing_ranges(&plan), vec![0..4096]);
+
+        let mut corrupt = new.clone();
+        corrupt[10] ^= 0xff;
+        assert!(matches!(
+            control.assemble(&[], &plan, &[(0..4096, corrupt)]),
+            Err(ZsyncError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_url_handles_relative_references() {
+        let base = "https://example.com/releases/App.AppImage.zsync";
+        assert_eq!(
+            resolve_url(base, "App.AppImage"),
+            "https://example.com/releases/App.AppImage"
+        );
+        assert_eq!(
+            resolve_url(base, "/other/App.AppImage"),
+            "https://example.com/other/App.AppImage"
+        );
+        assert_eq!(
+            resolve_url(base, "https://cdn.example.com/App.AppImage"),
+            "https://cdn.example.com/App.AppImage"
+        );
+        assert_eq!(
+            resolve_url("file:///srv/updates/App.zsync", "App.AppImage"),
+            "file:///srv/updates/App.AppImage"
+        );
+    }
+}